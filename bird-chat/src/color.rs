@@ -23,6 +23,8 @@ pub enum Color {
     Custom { r: u8, g: u8, b: u8 },
 }
 
+bird_util::into_owned_self!(Color);
+
 impl Color {
     pub const fn get_color(&self) -> u32 {
         match self {
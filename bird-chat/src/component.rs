@@ -1,5 +1,6 @@
 use std::borrow::Cow;
 use serde::{Deserialize, Serialize};
+use bird_util::IntoOwned;
 use crate::color::Color;
 use crate::identifier::Identifier;
 
@@ -65,4 +66,79 @@ pub struct Score<'a> {
     name: Cow<'a, str>, // possible uuid but actually string in json
     objective: Cow<'a, str>,
     value: Cow<'a, str>,
-}
\ No newline at end of file
+}
+
+impl<'a> IntoOwned for Component<'a> {
+    type Owned = Component<'static>;
+
+    fn into_owned(self) -> Component<'static> {
+        Component {
+            bold: self.bold,
+            italic: self.italic,
+            underlined: self.underlined,
+            strikethrough: self.strikethrough,
+            obfuscated: self.obfuscated,
+            font: self.font.into_owned(),
+            color: self.color,
+            insertion: self.insertion.into_owned(),
+            click_event: self.click_event.into_owned(),
+            extra: IntoOwned::into_owned(self.extra),
+            hover_event: self.hover_event.into_owned(),
+            ty: self.ty.into_owned(),
+        }
+    }
+}
+
+impl<'a> IntoOwned for ClickEvent<'a> {
+    type Owned = ClickEvent<'static>;
+
+    fn into_owned(self) -> ClickEvent<'static> {
+        match self {
+            Self::OpenUrl(url) => ClickEvent::OpenUrl(IntoOwned::into_owned(url)),
+            Self::RunCommand(command) => ClickEvent::RunCommand(IntoOwned::into_owned(command)),
+            Self::SuggestCommand(command) => ClickEvent::SuggestCommand(IntoOwned::into_owned(command)),
+            Self::ChangePage(page) => ClickEvent::ChangePage(page),
+        }
+    }
+}
+
+impl<'a> IntoOwned for HoverEvent<'a> {
+    type Owned = HoverEvent<'static>;
+
+    fn into_owned(self) -> HoverEvent<'static> {
+        match self {
+            Self::ShowText(text) => HoverEvent::ShowText(text.into_owned()),
+            Self::ShowItem(item) => HoverEvent::ShowItem(IntoOwned::into_owned(item)),
+            Self::ShowEntity(entity) => HoverEvent::ShowEntity(IntoOwned::into_owned(entity)),
+        }
+    }
+}
+
+impl<'a> IntoOwned for ComponentType<'a> {
+    type Owned = ComponentType<'static>;
+
+    fn into_owned(self) -> ComponentType<'static> {
+        match self {
+            Self::Text { text } => ComponentType::Text { text: IntoOwned::into_owned(text) },
+            Self::Translation { with, key } => ComponentType::Translation {
+                with: IntoOwned::into_owned(with),
+                key: IntoOwned::into_owned(key),
+            },
+            Self::KeyBind { key_bind } => ComponentType::KeyBind { key_bind: IntoOwned::into_owned(key_bind) },
+            Self::Selector { selector } => ComponentType::Selector { selector: IntoOwned::into_owned(selector) },
+            Self::Score { score } => ComponentType::Score { score: score.into_owned() },
+        }
+    }
+}
+
+impl<'a> IntoOwned for Score<'a> {
+    type Owned = Score<'static>;
+
+    fn into_owned(self) -> Score<'static> {
+        Score {
+            name: IntoOwned::into_owned(self.name),
+            objective: IntoOwned::into_owned(self.objective),
+            value: IntoOwned::into_owned(self.value),
+        }
+    }
+}
@@ -2,6 +2,7 @@ use std::borrow::Cow;
 use std::fmt::{Debug, Display, Formatter};
 use std::str::pattern::{Pattern, Searcher};
 use serde::{Deserialize, Serialize};
+use bird_util::IntoOwned;
 
 #[derive(Debug, Clone)]
 pub enum IdentifierInner<'a> {
@@ -106,6 +107,18 @@ impl<'a> Identifier<'a> {
     }
 }
 
+impl<'a> IntoOwned for Identifier<'a> {
+    type Owned = Identifier<'static>;
+
+    fn into_owned(self) -> Identifier<'static> {
+        Identifier(match self.0 {
+            IdentifierInner::Full(full) => IdentifierInner::Full(IntoOwned::into_owned(full)),
+            IdentifierInner::Partial(key, value) =>
+                IdentifierInner::Partial(IntoOwned::into_owned(key), IntoOwned::into_owned(value)),
+        })
+    }
+}
+
 impl<'a> Display for Identifier<'a> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self.get_inner() {
@@ -2,7 +2,7 @@ use biomes::generate_biomes;
 use blocks::generate_blocks;
 use items::generate_items;
 use materials::generate_materials;
-use minecraft_data_rs::{api::versions_by_minecraft_version, Api};
+use minecraft_data_rs::{api::versions_by_minecraft_version, models::version::Version, Api};
 use proc_macro::TokenTree;
 use proc_macro2::Span;
 use quote::quote;
@@ -11,13 +11,31 @@ mod biomes;
 mod items;
 mod materials;
 mod blocks;
+mod packets;
 
 #[proc_macro]
 pub fn generate_data(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     generate_data_impl(input).unwrap_or_else(|e| e.into_compile_error()).into()
 }
 
-fn generate_data_impl(input: proc_macro::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+/// Generates packet structs from protocol.json of the given version: `generate_packets!("1.19.3", skip = [...])`.
+/// Packets from `skip` are not generated, so they can be written by hand.
+/// The invoking crate should depend on `bird-protocol` with `derive` feature
+#[proc_macro]
+pub fn generate_packets(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    syn::parse::<packets::GeneratePacketsInput>(input)
+        .and_then(|input| packets::generate_packets(&find_version(&input.version.value())?, &input.skip))
+        .unwrap_or_else(|e| e.into_compile_error())
+        .into()
+}
+
+fn find_version(version: &str) -> syn::Result<Version> {
+    versions_by_minecraft_version().unwrap()
+        .remove(version)
+        .ok_or_else(|| syn::Error::new(Span::call_site(), format!("Unknown version \"{}\"", version).as_str()))
+}
+
+fn parse_version(input: proc_macro::TokenStream) -> syn::Result<Version> {
     let version = input.into_iter()
         .next()
         .and_then(|tt| match tt {
@@ -26,10 +44,11 @@ fn generate_data_impl(input: proc_macro::TokenStream) -> syn::Result<proc_macro2
         })
         .ok_or_else(|| syn::Error::new(Span::call_site(), "Input should be string literal"))?;
     let version_str = version.to_string();
-    let mut versions = versions_by_minecraft_version().unwrap();
-    let version = versions
-        .remove(&version_str[1..version_str.len()-1].to_owned())
-        .ok_or_else(|| syn::Error::new(Span::call_site(), format!("Unknown version {}", version_str).as_str()))?;
+    find_version(&version_str[1..version_str.len()-1])
+}
+
+fn generate_data_impl(input: proc_macro::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let version = parse_version(input)?;
     let api = Api::new(version);
     let mut result = Vec::new();
     result.push(generate_biomes(&api)?);
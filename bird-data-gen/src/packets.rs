@@ -0,0 +1,267 @@
+use std::collections::HashSet;
+use convert_case::{Case, Casing};
+use minecraft_data_rs::{data::get_version_specific_file, models::version::Version};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use serde_json::{Map, Value};
+use syn::{bracketed, LitStr, Token};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+
+const STATES: [(&str, &str, &str); 4] = [
+    ("handshaking", "Handshake", "H"),
+    ("status", "Status", "S"),
+    ("login", "Login", "L"),
+    ("play", "Play", "P"),
+];
+
+const BOUNDS: [(&str, &str, &str); 2] = [
+    ("toClient", "Client", "S2C"),
+    ("toServer", "Server", "C2S"),
+];
+
+struct GeneratedField {
+    ty: TokenStream,
+    variant: Option<String>,
+    borrowed: bool,
+}
+
+/// Input of `generate_packets!`: the version and names of packets which are written by hand
+pub struct GeneratePacketsInput {
+    pub version: LitStr,
+    pub skip: Vec<Ident>,
+}
+
+impl Parse for GeneratePacketsInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let version = input.parse()?;
+        let mut skip = Vec::new();
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let key: Ident = input.parse()?;
+            if key != "skip" {
+                return Err(syn::Error::new(key.span(), "Only skip is supported"));
+            }
+            input.parse::<Token![=]>()?;
+            let content;
+            bracketed!(content in input);
+            skip = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?.into_iter().collect();
+            input.parse::<Option<Token![,]>>()?;
+        }
+        Ok(Self { version, skip })
+    }
+}
+
+/// Generates `ProtocolAll` structs for every packet of protocol.json which fields can be expressed.
+/// Packets with other fields (switches, bitfields, nested containers and so on) are skipped and should be written by hand
+pub fn generate_packets(version: &Version, skip: &[Ident]) -> syn::Result<TokenStream> {
+    let protocol = get_version_specific_file(version, "protocol")
+        .map_err(|e| syn::Error::new(Span::call_site(), format!("Failed to load protocol: {}", e)))?;
+    let protocol: Value = serde_json::from_str(protocol.as_str())
+        .map_err(|e| syn::Error::new(Span::call_site(), format!("Failed to parse protocol: {}", e)))?;
+    generate_protocol_packets(&protocol, skip)
+}
+
+/// Generates packets of parsed protocol.json. Packets with names from `skip` are not generated,
+/// so they can be written by hand with the same names
+pub fn generate_protocol_packets(protocol: &Value, skip: &[Ident]) -> syn::Result<TokenStream> {
+    let mut generated_names = HashSet::new();
+    let mut packets_ts = Vec::new();
+    for (state_key, state, state_suffix) in STATES {
+        for (bound_key, bound, bound_suffix) in BOUNDS {
+            let types = match protocol.pointer(format!("/{}/{}/types", state_key, bound_key).as_str()) {
+                Some(Value::Object(types)) => types,
+                _ => continue,
+            };
+            let state_ident = Ident::new(state, Span::call_site());
+            let bound_ident = Ident::new(bound, Span::call_site());
+            for (id, name) in packet_mappings(types) {
+                let struct_name = format!("{}{}{}", name.to_case(Case::Pascal), state_suffix, bound_suffix);
+                if skip.iter().any(|skip| *skip == struct_name) {
+                    continue;
+                }
+                let fields = match types.get(format!("packet_{}", name).as_str()) {
+                    Some(Value::Array(packet)) if packet.first() == Some(&Value::String("container".into())) =>
+                        match packet.get(1) {
+                            Some(Value::Array(fields)) => fields,
+                            _ => continue,
+                        },
+                    _ => continue,
+                };
+                let fields = match generate_fields(fields) {
+                    Some(fields) => fields,
+                    None => continue,
+                };
+                if !generated_names.insert(struct_name.clone()) {
+                    return Err(syn::Error::new(
+                        Span::call_site(),
+                        format!("Packet {} is generated more than once, it should be skipped and written by hand", struct_name),
+                    ));
+                }
+                let struct_ident = Ident::new(struct_name.as_str(), Span::call_site());
+                let lifetime = match fields.iter().any(|(_, field)| field.borrowed) {
+                    true => quote! { <'a> },
+                    false => quote! {},
+                };
+                let fields_ts = fields.iter().map(|(field_ident, GeneratedField { ty, variant, .. })| {
+                    let variant = variant.as_ref().map(|variant| quote! { #[bp(variant = #variant)] });
+                    quote! {
+                        #variant
+                        pub #field_ident: #ty
+                    }
+                });
+                packets_ts.push(quote! {
+                    #[derive(
+                        bird_protocol::derive::ProtocolAll, bird_protocol::derive::ProtocolPacket, bird_protocol::derive::IntoOwned,
+                        Clone, PartialEq, Debug,
+                    )]
+                    #[bp(
+                        id = #id,
+                        state = bird_protocol::ProtocolPacketState::#state_ident,
+                        bound = bird_protocol::ProtocolPacketBound::#bound_ident
+                    )]
+                    pub struct #struct_ident #lifetime {
+                        #(#fields_ts,)*
+                    }
+                });
+            }
+        }
+    }
+    Ok(quote! { #(#packets_ts)* })
+}
+
+fn packet_mappings(types: &Map<String, Value>) -> Vec<(i32, String)> {
+    let mappings = types.get("packet")
+        .and_then(|packet| packet.pointer("/1/0/type/1/mappings"))
+        .and_then(|mappings| mappings.as_object());
+    let mut result = Vec::new();
+    for (id, name) in mappings.into_iter().flatten() {
+        let (id, name) = match (i32::from_str_radix(id.trim_start_matches("0x"), 16), name.as_str()) {
+            (Ok(id), Some(name)) => (id, name),
+            _ => continue,
+        };
+        result.push((id, name.to_owned()));
+    }
+    result.sort_by_key(|(id, _)| *id);
+    result
+}
+
+fn generate_fields(fields: &[Value]) -> Option<Vec<(Ident, GeneratedField)>> {
+    let mut result = Vec::new();
+    for field in fields {
+        let name = field.get("name")?.as_str()?.to_case(Case::Snake);
+        let field_ident = match name.as_str() {
+            "type" => Ident::new("ty", Span::call_site()),
+            _ => syn::parse_str(name.as_str()).ok()?,
+        };
+        result.push((field_ident, generate_field(field.get("type")?)?));
+    }
+    Some(result)
+}
+
+fn generate_field(ty: &Value) -> Option<GeneratedField> {
+    let field = |ty, variant, borrowed| Some(GeneratedField { ty, variant, borrowed });
+    match ty {
+        Value::String(ty) => match ty.as_str() {
+            "varint" => field(quote! { i32 }, Some("bird_protocol::VarInt".into()), false),
+            "varlong" => field(quote! { i64 }, Some("bird_protocol::VarLong".into()), false),
+            "bool" => field(quote! { bool }, None, false),
+            "i8" => field(quote! { i8 }, None, false),
+            "u8" => field(quote! { u8 }, None, false),
+            "i16" => field(quote! { i16 }, None, false),
+            "u16" => field(quote! { u16 }, None, false),
+            "i32" => field(quote! { i32 }, None, false),
+            "i64" => field(quote! { i64 }, None, false),
+            "u64" => field(quote! { u64 }, None, false),
+            "f32" => field(quote! { f32 }, None, false),
+            "f64" => field(quote! { f64 }, None, false),
+            "UUID" => field(quote! { bird_protocol::uuid::Uuid }, None, false),
+            "string" => field(quote! { std::borrow::Cow<'a, str> }, None, true),
+            "restBuffer" => field(quote! { std::borrow::Cow<'a, [u8]> }, Some("bird_protocol::RemainingBytesArray".into()), true),
+            _ => None,
+        },
+        Value::Array(ty) => match (ty.first()?.as_str()?, ty.get(1)?) {
+            ("buffer", options) if options.get("countType")?.as_str()? == "varint" => field(
+                quote! { std::borrow::Cow<'a, [u8]> },
+                Some("bird_protocol::LengthProvidedBytesArray<i32, bird_protocol::VarInt>".into()),
+                true,
+            ),
+            ("option", inner) => {
+                let GeneratedField { ty, variant, borrowed } = generate_field(inner)?;
+                // Option uses readable and writable implementations of the inner type
+                if variant.is_some() {
+                    return None;
+                }
+                field(quote! { Option<#ty> }, None, borrowed)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    fn protocol() -> Value {
+        json!({
+            "play": {
+                "toClient": {
+                    "types": {
+                        "packet": ["container", [{
+                            "name": "name",
+                            "type": ["mapper", {
+                                "type": "varint",
+                                "mappings": {
+                                    "0x00": "spawn_entity",
+                                    "0x01": "declare_commands",
+                                    "0x02": "keep_alive",
+                                },
+                            }],
+                        }]],
+                        "packet_spawn_entity": ["container", [
+                            { "name": "entityId", "type": "varint" },
+                            { "name": "objectUUID", "type": "UUID" },
+                            { "name": "type", "type": "varint" },
+                        ]],
+                        "packet_declare_commands": ["container", [
+                            { "name": "nodes", "type": ["array", { "countType": "varint", "type": "command_node" }] },
+                        ]],
+                        "packet_keep_alive": ["container", [
+                            { "name": "keepAliveId", "type": "i64" },
+                            { "name": "message", "type": ["option", "string"] },
+                        ]],
+                    },
+                },
+            },
+        })
+    }
+
+    #[test]
+    fn generate_protocol_packets_test() {
+        let input: GeneratePacketsInput = syn::parse_str(r#""1.19.3", skip = [KeepAlivePS2C]"#).unwrap();
+        assert_eq!(input.version.value(), "1.19.3");
+        let generated = generate_protocol_packets(&protocol(), &input.skip).unwrap().to_string();
+        assert!(generated.contains("pub struct SpawnEntityPS2C {"));
+        assert!(generated.contains("pub ty : i32"));
+        // packets with nested types and skipped packets are written by hand
+        assert!(!generated.contains("DeclareCommandsPS2C"));
+        assert!(!generated.contains("KeepAlivePS2C"));
+        let generated = generate_protocol_packets(&protocol(), &[]).unwrap().to_string();
+        assert!(generated.contains("pub struct KeepAlivePS2C < 'a > {"));
+        assert!(syn::parse_str::<GeneratePacketsInput>(r#""1.19.3", only = [KeepAlivePS2C]"#).is_err());
+    }
+
+    #[test]
+    fn generate_protocol_packets_collision_test() {
+        let mut protocol = protocol();
+        let mappings = protocol.pointer_mut("/play/toClient/types/packet/1/0/type/1/mappings").unwrap();
+        mappings.as_object_mut().unwrap().insert("0x03".into(), "spawnEntity".into());
+        let types = protocol.pointer_mut("/play/toClient/types").unwrap().as_object_mut().unwrap();
+        types.insert("packet_spawnEntity".into(), json!(["container", []]));
+        assert!(generate_protocol_packets(&protocol, &[]).is_err());
+        let skip = [Ident::new("SpawnEntityPS2C", Span::call_site())];
+        assert!(generate_protocol_packets(&protocol, &skip).is_ok());
+    }
+}
@@ -1,5 +1,7 @@
 use bird_data_gen::generate_data;
 
+pub use bird_data_gen::generate_packets;
+
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum WorldDimension {
@@ -8,4 +10,31 @@ pub enum WorldDimension {
     End,
 }
 
-generate_data!("1.19");
\ No newline at end of file
+generate_data!("1.19");
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use bird_protocol::{ProtocolPacket, ProtocolPacketState, ProtocolReadable, ProtocolWritable};
+
+    mod generated {
+        crate::generate_packets!("1.19");
+    }
+
+    #[test]
+    fn generate_packets_test() {
+        use generated::SetProtocolHC2S;
+        assert_eq!(SetProtocolHC2S::ID, 0);
+        assert_eq!(SetProtocolHC2S::STATE, ProtocolPacketState::Handshake);
+        let handshake = SetProtocolHC2S {
+            protocol_version: 759,
+            server_host: Cow::Borrowed("localhost"),
+            server_port: 25565,
+            next_state: 1,
+        };
+        let mut bytes = Vec::new();
+        handshake.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 15);
+        assert_eq!(SetProtocolHC2S::read(&mut bytes.as_slice()).unwrap(), handshake);
+    }
+}
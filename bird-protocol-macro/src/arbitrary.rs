@@ -0,0 +1,218 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{Data, DeriveInput, Fields, Lifetime, Type, Variant};
+use syn::spanned::Spanned;
+use syn::visit_mut::VisitMut;
+use crate::readable::has_const_expression;
+use crate::shared::{create_prepared_fields, create_prepared_variants, derive_where_clause, GhostValue, ObjectAttributes, obligate_lifetime, parse_attributes, PreparedField};
+
+struct ElidedLifetimes;
+
+impl VisitMut for ElidedLifetimes {
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        *lifetime = Lifetime::new("'_", lifetime.span());
+    }
+}
+
+pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
+    let item: DeriveInput = syn::parse(item)?;
+    let DeriveInput {
+        attrs,
+        data,
+        ident,
+        mut generics,
+        ..
+    } = item;
+    let object_attributes: ObjectAttributes = parse_attributes(&attrs, "bp")?;
+    let (lifetime, spec_impl_generics) = obligate_lifetime(&mut generics)?;
+    let lifetime_ident = &lifetime.lifetime;
+    let where_clause = derive_where_clause(&generics, &data, Some(quote! { #lifetime_ident }), |ty, variant| match variant {
+        Some(variant) => quote! { #variant: bird_protocol::arbitrary::ProtocolVariantArbitrary<#lifetime_ident, #ty> },
+        None => quote! { #ty: bird_protocol::arbitrary::ProtocolArbitrary<#lifetime_ident> },
+    })?;
+    let mut registry_ts = None;
+    let function_body = match data {
+        Data::Struct(data_struct) => arbitrary_fields(
+            data_struct.fields,
+            quote! { Self },
+            lifetime_ident,
+            object_attributes.ghost_values.into_iter(),
+        )?,
+        // packet registries have a state, but no id unlike packets
+        Data::Enum(data_enum) if object_attributes.packet_state.is_some() && object_attributes.packet_id.is_none() => {
+            let (function_body, round_trips_ts) = arbitrary_registry(&ident, &generics, data_enum.variants)?;
+            registry_ts = Some(round_trips_ts);
+            function_body
+        }
+        Data::Enum(data_enum) => {
+            let variants = create_prepared_variants(data_enum.variants.into_iter(), &object_attributes)?;
+            let variants_count = variants.len();
+            let mut variant_matches = Vec::new();
+            for (index, (variant, _, variant_attributes)) in variants.into_iter().enumerate() {
+                let Variant {
+                    fields,
+                    ident: variant_ident,
+                    ..
+                } = variant;
+                let variant_fields = arbitrary_fields(
+                    fields,
+                    quote! { Self:: #variant_ident },
+                    lifetime_ident,
+                    object_attributes.ghost_values.iter().cloned().chain(variant_attributes.ghost_values),
+                )?;
+                variant_matches.push(quote! { #index => { #variant_fields } });
+            }
+            quote! {
+                match __u.choose_index(#variants_count)? {
+                    #(#variant_matches,)*
+                    _ => unreachable!(),
+                }
+            }
+        }
+        Data::Union(_) => return Err(syn::Error::new(Span::mixed_site(), "Union is not supported")),
+    };
+    let (_, type_generics, _) = generics.split_for_impl();
+    let (impl_generics, ..) = spec_impl_generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics bird_protocol::arbitrary::ProtocolArbitrary<#lifetime> for #ident #type_generics #where_clause {
+            fn arbitrary(__u: &mut bird_protocol::arbitrary::Unstructured<#lifetime>) -> bird_protocol::arbitrary::ArbitraryResult<Self> {
+                // values are generated as they are written if the protocol version is unknown
+                let __version: std::option::Option<i32> = std::option::Option::None;
+                #function_body
+            }
+        }
+
+        #registry_ts
+    })
+}
+
+/// Generates a packet of a random variant of the registry.
+/// Registries also get functions which check round trips and owned conversions of arbitrary values of every packet
+fn arbitrary_registry(
+    ident: &Ident,
+    generics: &syn::Generics,
+    variants: impl IntoIterator<Item=Variant>,
+) -> syn::Result<(TokenStream, TokenStream)> {
+    let mut variant_matches = Vec::new();
+    let mut packet_types = Vec::new();
+    for (index, variant) in variants.into_iter().enumerate() {
+        let span = variant.span();
+        let variant_ident = variant.ident;
+        match variant.fields {
+            Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => {
+                let mut ty: Type = unnamed.unnamed.into_iter().next().unwrap().ty;
+                variant_matches.push(quote! {
+                    #index => Ok(Self::#variant_ident(bird_protocol::arbitrary::ProtocolArbitrary::arbitrary(__u)?))
+                });
+                // lifetimes are inferred, so packets can borrow the generated data
+                ElidedLifetimes.visit_type_mut(&mut ty);
+                packet_types.push(ty);
+            }
+            _ => return Err(syn::Error::new(span, "Variant should contain only one packet")),
+        }
+    }
+    let variants_count = variant_matches.len();
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    Ok((
+        quote! {
+            match __u.choose_index(#variants_count)? {
+                #(#variant_matches,)*
+                _ => unreachable!(),
+            }
+        },
+        quote! {
+            impl #impl_generics #ident #type_generics #where_clause {
+                /// Checks round trips of arbitrary values of every packet. Every packet should be generated at least once
+                pub fn assert_arbitrary_round_trips(iterations: u64) {
+                    bird_protocol::assert_arbitrary_round_trips!(iterations; #(#packet_types),*);
+                }
+
+                /// Checks that arbitrary values of every packet are converted into owned ones which can be sent to another thread
+                pub fn assert_arbitrary_into_owned(iterations: u64) {
+                    bird_protocol::assert_arbitrary_into_owned!(iterations; #(#packet_types),*);
+                }
+            }
+        },
+    ))
+}
+
+fn arbitrary_fields(
+    fields: Fields,
+    key: TokenStream,
+    lifetime: &impl ToTokens,
+    ghost_values: impl Iterator<Item=GhostValue>,
+) -> syn::Result<TokenStream> {
+    let create_struct_ts = match fields {
+        Fields::Unit => quote! { Ok(#key) },
+        Fields::Unnamed(ref unnamed) => {
+            let mut idents = Vec::new();
+            for i in 0..unnamed.unnamed.len() {
+                idents.push(Ident::new(format!("__{}", i).as_str(), Span::call_site()));
+            }
+            quote! { Ok(#key(#(#idents,)*)) }
+        }
+        Fields::Named(ref named) => {
+            let mut idents = Vec::new();
+            for field in &named.named {
+                idents.push(field.ident.as_ref().unwrap())
+            }
+            quote! { Ok(#key{#(#idents,)*}) }
+        }
+    };
+    let fields = create_prepared_fields(fields, ghost_values)?;
+    // fields which contain lengths of `len_from` arrays get small lengths, so the arrays can be generated
+    let length_idents: Vec<String> = fields.iter()
+        .filter_map(|field| field.len_from.as_ref().map(|len_from| len_from.to_string()))
+        .collect();
+    let mut variables_ts = Vec::new();
+    let mut generated_idents = Vec::new();
+    for PreparedField { ident: field_ident, value: field_value_expr, ty: field_ty, variant: field_variant, versions: field_versions, condition, len_from } in fields {
+        // ghost values are computed while writing
+        if field_value_expr.is_some() {
+            continue;
+        }
+        let field_ty = match field_ty {
+            Some(ty) if has_const_expression(&ty) => quote! { _ },
+            Some(ty) => ty,
+            None => quote! { _ },
+        };
+        let mut arbitrary_ts = match len_from {
+            Some(len_from) => {
+                let length = quote! { bird_protocol::ProtocolLength::into_usize(std::clone::Clone::clone(&#len_from)) };
+                match field_variant {
+                    Some(ref variant) => quote! { bird_protocol::arbitrary::arbitrary_length_from_array::<#field_ty, _, #variant>(#length, __u)? },
+                    None => quote! { bird_protocol::arbitrary::arbitrary_length_from_array_of::<#field_ty, _>(#length, __u)? },
+                }
+            }
+            None if length_idents.contains(&field_ident.to_string()) => quote! {
+                bird_protocol::ProtocolLength::from_usize(bird_protocol::arbitrary::arbitrary_length(__u)?)
+            },
+            None => match field_variant {
+                Some(ref variant) => quote! { <#variant as bird_protocol::arbitrary::ProtocolVariantArbitrary<#lifetime, #field_ty>>::arbitrary_variant(__u)? },
+                None => quote! { <#field_ty as bird_protocol::arbitrary::ProtocolArbitrary<#lifetime>>::arbitrary(__u)? },
+            },
+        };
+        if let Some(condition) = condition {
+            // earlier fields are references in the condition as in the writable derive
+            arbitrary_ts = quote! {
+                if { #(#[allow(unused_variables)] let #generated_idents = &#generated_idents;)* #condition } {
+                    std::option::Option::Some(#arbitrary_ts)
+                } else {
+                    std::option::Option::None
+                }
+            };
+        }
+        variables_ts.push(match field_versions {
+            Some(versions) => {
+                let matches = versions.matches_ts(quote! { __version });
+                quote! { let #field_ident = if #matches { #arbitrary_ts } else { std::default::Default::default() }; }
+            }
+            None => quote! { let #field_ident = #arbitrary_ts; },
+        });
+        generated_idents.push(field_ident);
+    }
+    Ok(quote! {
+        #(#variables_ts;)*
+        #create_struct_ts
+    })
+}
@@ -4,6 +4,9 @@ mod writable;
 mod size;
 mod packet;
 mod nbt;
+mod registry;
+mod owned;
+mod arbitrary;
 
 macro_rules! derive_impl {
     ($func: expr) => {
@@ -34,6 +37,11 @@ pub fn protocol_packet_derive(item: proc_macro::TokenStream) -> proc_macro::Toke
     derive_impl!(packet::impl_derive(item))
 }
 
+#[proc_macro_derive(ProtocolPacketRegistry, attributes(bp))]
+pub fn protocol_packet_registry_derive(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    derive_impl!(registry::impl_derive(item))
+}
+
 #[proc_macro_derive(ProtocolAll, attributes(bp))]
 pub fn protocol_all_derive(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let mut writable: proc_macro::TokenStream = derive_impl!(writable::impl_derive(item.clone()));
@@ -44,6 +52,16 @@ pub fn protocol_all_derive(item: proc_macro::TokenStream) -> proc_macro::TokenSt
     writable
 }
 
+#[proc_macro_derive(IntoOwned)]
+pub fn into_owned_derive(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    derive_impl!(owned::impl_derive(item))
+}
+
+#[proc_macro_derive(ProtocolArbitrary, attributes(bp))]
+pub fn protocol_arbitrary_derive(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    derive_impl!(arbitrary::impl_derive(item))
+}
+
 #[proc_macro_derive(BirdNbt, attributes(bnbt))]
 pub fn bird_nbt_derive(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
     // println!("{}", nbt::impl_derive(item).unwrap());
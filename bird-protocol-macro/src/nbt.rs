@@ -124,7 +124,7 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                                             bird_protocol::anyhow::Error::msg("Bad tag")
                                         ));
                                     }
-                                    #ident.replace(<#variant as bird_protocol::nbt::NbtTagVariant<#lifetime, #ty>>::read_nbt_variant(__cursor)?);
+                                    #ident.replace(<#variant as bird_protocol::nbt::NbtTagVariant<#lifetime, #ty>>::read_nbt_variant_of(__tag, __cursor)?);
                                     bird_protocol::ProtocolResult::Ok(())
                                 }
                             }
@@ -148,7 +148,7 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                                             bird_protocol::anyhow::Error::msg("Bad tag")
                                         ));
                                     }
-                                    __result += <#variant as bird_protocol::nbt::NbtTagVariant<#lifetime, #ty>>::skip_nbt_variant(__cursor, 1)?;
+                                    __result += <#variant as bird_protocol::nbt::NbtTagVariant<#lifetime, #ty>>::skip_nbt_variant_of(__tag, __cursor, 1)?;
                                 }
                             }
                         });
@@ -189,6 +189,8 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                                         __result += 3 + __name.len();
                                         bird_protocol::ProtocolResult::Ok(())
                                     })?;
+                                    // the end tag
+                                    __result += 1;
                                 }
                                 Ok(__result)
                             }
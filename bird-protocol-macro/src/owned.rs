@@ -0,0 +1,91 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{Data, DeriveInput, Fields, GenericParam, Ident, Member, WhereClause};
+use syn::punctuated::Punctuated;
+
+pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
+    let item: DeriveInput = syn::parse(item)?;
+    let DeriveInput {
+        ident,
+        generics,
+        data,
+        ..
+    } = item;
+    let mut where_clause = generics.where_clause.clone().unwrap_or_else(|| WhereClause {
+        where_token: Default::default(),
+        predicates: Punctuated::new(),
+    });
+    // every lifetime becomes 'static and every type parameter becomes its owned type
+    let owned_params = generics.params.iter().map(|param| match param {
+        GenericParam::Lifetime(_) => quote! { 'static },
+        GenericParam::Type(param) => {
+            let param_ident = &param.ident;
+            quote! { <#param_ident as bird_protocol::IntoOwned>::Owned }
+        }
+        GenericParam::Const(param) => param.ident.to_token_stream(),
+    });
+    let owned_ty = quote! { #ident <#(#owned_params),*> };
+    for param in generics.type_params() {
+        let param_ident = &param.ident;
+        where_clause.predicates.push(syn::parse2(quote! { #param_ident: bird_protocol::IntoOwned })?);
+        let bounds: Vec<_> = param.bounds.iter()
+            .filter(|bound| matches!(bound, syn::TypeParamBound::Trait(_)))
+            .collect();
+        if !bounds.is_empty() {
+            where_clause.predicates.push(syn::parse2(quote! {
+                <#param_ident as bird_protocol::IntoOwned>::Owned: #(#bounds)+*
+            })?);
+        }
+    }
+    let body = match data {
+        Data::Struct(data_struct) => {
+            let (pattern, construction) = fields_into_owned(data_struct.fields);
+            quote! {
+                let Self #pattern = self;
+                #ident #construction
+            }
+        }
+        Data::Enum(data_enum) => {
+            let variants = data_enum.variants.into_iter().map(|variant| {
+                let variant_ident = variant.ident;
+                let (pattern, construction) = fields_into_owned(variant.fields);
+                quote! { Self::#variant_ident #pattern => #ident::#variant_ident #construction }
+            });
+            quote! {
+                match self {
+                    #(#variants,)*
+                }
+            }
+        }
+        Data::Union(_) => return Err(syn::Error::new(Span::call_site(), "Unions are not supported")),
+    };
+    let (impl_generics, type_generics, _) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics bird_protocol::IntoOwned for #ident #type_generics #where_clause {
+            type Owned = #owned_ty;
+
+            #[allow(unreachable_code)]
+            fn into_owned(self) -> #owned_ty {
+                #body
+            }
+        }
+    })
+}
+
+/// Returns the pattern which binds every field and the construction of the owned value from them
+fn fields_into_owned(fields: Fields) -> (TokenStream, TokenStream) {
+    let mut pattern = Vec::new();
+    let mut construction = Vec::new();
+    for (index, field) in fields.into_iter().enumerate() {
+        let (member, binding) = match field.ident {
+            Some(field_ident) => (Member::Named(field_ident.clone()), field_ident),
+            None => (Member::Unnamed(index.into()), Ident::new(format!("__{}", index).as_str(), Span::call_site())),
+        };
+        pattern.push(match member {
+            Member::Named(_) => quote! { #binding },
+            Member::Unnamed(_) => quote! { #member: #binding },
+        });
+        construction.push(quote! { #member: bird_protocol::IntoOwned::into_owned(#binding) });
+    }
+    (quote! { { #(#pattern),* } }, quote! { { #(#construction),* } })
+}
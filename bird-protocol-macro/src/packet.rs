@@ -15,12 +15,29 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
     let id = object_attributes.packet_id.ok_or_else(|| syn::Error::new(Span::call_site(), "packet id should be provided"))?;
     let state = object_attributes.packet_state.ok_or_else(|| syn::Error::new(Span::call_site(), "packet state should be provided"))?;
     let bound = object_attributes.packet_bound.ok_or_else(|| syn::Error::new(Span::call_site(), "packet bound should be provided"))?;
+    let version_ids = match object_attributes.packet_versions.is_empty() {
+        true => None,
+        false => {
+            let version_ids = object_attributes.packet_versions.iter().map(|packet_version| {
+                let (since, until) = packet_version.versions.bounds_ts();
+                let version_id = &packet_version.id;
+                quote! {
+                    bird_protocol::version::ProtocolVersionId { since: #since, until: #until, id: (#version_id) as i32 }
+                }
+            });
+            Some(quote! {
+                const VERSION_IDS: &'static [bird_protocol::version::ProtocolVersionId] = &[#(#version_ids,)*];
+            })
+        }
+    };
     let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
     Ok(quote! {
         impl #impl_generics bird_protocol::ProtocolPacket for #ident #type_generics #where_clause {
             const ID: i32 = #id;
             const BOUND: bird_protocol::ProtocolPacketBound = #bound;
             const STATE: bird_protocol::ProtocolPacketState = #state;
+
+            #version_ids
         }
     })
 }
\ No newline at end of file
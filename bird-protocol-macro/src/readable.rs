@@ -1,7 +1,7 @@
-use proc_macro2::{Ident, Span, TokenStream};
+use proc_macro2::{Delimiter, Ident, Span, TokenStream, TokenTree};
 use quote::{quote, ToTokens};
 use syn::{Data, DeriveInput, Fields, Variant};
-use crate::shared::{create_prepared_fields, create_prepared_variants, GhostValue, ObjectAttributes, obligate_lifetime, parse_attributes};
+use crate::shared::{create_prepared_fields, create_prepared_variants, derive_where_clause, GhostValue, ObjectAttributes, obligate_lifetime, parse_attributes, PreparedField};
 use crate::size::enum_key_size;
 
 pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
@@ -15,11 +15,24 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
     } = item;
     let object_attributes: ObjectAttributes = parse_attributes(&attrs, "bp")?;
     let (lifetime, spec_impl_generics) = obligate_lifetime(&mut generics)?;
+    let lifetime_ident = &lifetime.lifetime;
+    let where_clause = derive_where_clause(&generics, &data, Some(quote! { #lifetime_ident }), |ty, variant| match variant {
+        Some(variant) => quote! { #variant: bird_protocol::ProtocolVariantReadable<#lifetime_ident, #ty> },
+        None => quote! { #ty: bird_protocol::ProtocolReadable<#lifetime_ident> },
+    })?;
     let function_body = match data {
         Data::Struct(data_struct) => {
-            let read = read_fields(data_struct.fields, quote! { Self }, &lifetime, object_attributes.ghost_values.into_iter())?;
+            let read = read_fields(
+                data_struct.fields,
+                quote! { Self },
+                &ident,
+                None,
+                &lifetime,
+                object_attributes.ghost_values.into_iter(),
+            )?;
             quote! {
                 let __rcursor = __cursor;
+                let __start = bird_protocol::ProtocolCursor::remaining_bytes(&*__rcursor);
                 #read
             }
         }
@@ -32,12 +45,14 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
             for (variant, variant_value, variant_attributes) in variants {
                 let Variant {
                     fields,
-                    ident,
+                    ident: variant_ident,
                     ..
                 } = variant;
                 let variant_fields = read_fields(
                     fields,
-                    quote! { Self:: #ident },
+                    quote! { Self:: #variant_ident },
+                    &ident,
+                    Some(&variant_ident),
                     &lifetime,
                     object_attributes.ghost_values.iter().cloned().chain(variant_attributes.ghost_values.into_iter()),
                 )?;
@@ -49,45 +64,81 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                 })
             }
             let key_read_ts = read_ts(Some(&key_ty), None::<&TokenStream>, &lifetime, object_attributes.key_variant.as_ref());
+            let key_debug_ts = dissect_debug_ts(quote! { __key });
+            // the offset of the key is the number of bytes which precede it
+            let read_key_ts = |key_offset: TokenStream| quote! {
+                {
+                    bird_protocol::ProtocolCursor::enter_field(&mut *__rcursor, std::stringify!(#ident), "key");
+                    let __key = #key_read_ts.map_err(|__err| __err.with_field(std::stringify!(#ident), "key", #key_offset))?;
+                    bird_protocol::ProtocolCursor::leave_field(&mut *__rcursor, #key_debug_ts);
+                    __key
+                }
+            };
             let rcursor = match object_attributes.key_reverse.0 {
                 true => {
                     let (min_key, max_key) = enum_key_size(&object_attributes)?;
+                    let read_key_ts = read_key_ts(quote! { __RCSIZE });
                     quote! {
                         const __RCSIZE: usize = {
                             std::assert!(
                                 <#ident as bird_protocol::ProtocolSize>::SIZE.start - #min_key ==
                                 <#ident as bird_protocol::ProtocolSize>::SIZE.end - #max_key
                             );
-                            <#ident as bird_protocol::ProtocolSize>::SIZE.start as usize
+                            (<#ident as bird_protocol::ProtocolSize>::SIZE.start - #min_key) as usize
                         };
                         let __rcursor = &mut __cursor.take_bytes(__RCSIZE)?;
+                        let __start = bird_protocol::ProtocolCursor::remaining_bytes(&*__rcursor);
+                        // the key follows the fields
+                        let __key = {
+                            let __rcursor = &mut *__cursor;
+                            #read_key_ts
+                        };
                     }
                 },
-                false => quote! { let __rcursor = __cursor; },
+                false => {
+                    let read_key_ts = read_key_ts(quote! { 0 });
+                    quote! {
+                        let __rcursor = __cursor;
+                        // offsets of the fields include the key
+                        let __start = bird_protocol::ProtocolCursor::remaining_bytes(&*__rcursor);
+                        let __key = #read_key_ts;
+                    }
+                }
             };
             quote! {
                 #(#const_variant_values;)*
                 #rcursor
-                match #key_read_ts {
+                match __key {
                     #(#variant_matches,)*
-                    _ => bird_protocol::ProtocolResult::Err(bird_protocol::ProtocolError::Any(bird_protocol::anyhow::Error::msg("Bad value of key"))),
+                    __key => bird_protocol::ProtocolResult::Err(bird_protocol::ProtocolError::BadEnumKey {
+                        ty: std::stringify!(#ident),
+                        key: std::format!("{:?}", __key),
+                    }),
                 }
             }
         }
         Data::Union(_) => return Err(syn::Error::new(Span::mixed_site(), "Union is not supported")),
     };
-    let (_, type_generics, where_clause) = generics.split_for_impl();
+    let (_, type_generics, _) = generics.split_for_impl();
     let (impl_generics, ..) = spec_impl_generics.split_for_impl();
     Ok(quote! {
         impl #impl_generics bird_protocol::ProtocolReadable<#lifetime> for #ident #type_generics #where_clause {
             fn read<C: bird_protocol::ProtocolCursor<#lifetime>>(__cursor: &mut C) -> bird_protocol::ProtocolResult<Self> {
+                let __version = bird_protocol::ProtocolCursor::protocol_version(&*__cursor);
                 #function_body
             }
         }
     })
 }
 
-fn read_fields(fields: Fields, key: TokenStream, lifetime: &impl ToTokens, ghost_values: impl Iterator<Item=GhostValue>) -> syn::Result<TokenStream> {
+fn read_fields(
+    fields: Fields,
+    key: TokenStream,
+    ty_ident: &Ident,
+    variant_ident: Option<&Ident>,
+    lifetime: &impl ToTokens,
+    ghost_values: impl Iterator<Item=GhostValue>,
+) -> syn::Result<TokenStream> {
     let create_struct_ts = match fields {
         Fields::Unit => quote! { Ok(#key) },
         Fields::Unnamed(ref unnamed) => {
@@ -107,9 +158,64 @@ fn read_fields(fields: Fields, key: TokenStream, lifetime: &impl ToTokens, ghost
     };
     let fields = create_prepared_fields(fields, ghost_values)?;
     let mut variables_ts = Vec::new();
-    for (field_ident, field_value_expr, field_ty, field_variant) in fields {
-        let read_ts = read_ts(field_ty.as_ref(), field_value_expr.as_ref(), lifetime, field_variant.as_ref());
-        variables_ts.push(quote! { let #field_ident = #read_ts; });
+    let mut read_idents = Vec::new();
+    for PreparedField { ident: field_ident, value: field_value_expr, ty: field_ty, variant: field_variant, versions: field_versions, condition, len_from } in fields {
+        // const expressions of a type in the function body would depend on the generics of the function,
+        // so such types are inferred from the struct
+        let field_ty = match (&field_value_expr, field_ty) {
+            (None, Some(ty)) if has_const_expression(&ty) => Some(quote! { _ }),
+            (_, field_ty) => field_ty,
+        };
+        let read_ts = match len_from {
+            Some(len_from) => {
+                let field_ty = field_ty.as_ref().unwrap();
+                let length = quote! { bird_protocol::ProtocolLength::into_usize(std::clone::Clone::clone(&#len_from)) };
+                match field_variant {
+                    Some(ref variant) => quote! { <#field_ty as bird_protocol::ProtocolLengthFromArray<_, #variant>>::read_with_length(#length, __rcursor) },
+                    None => quote! { bird_protocol::__private::read_length_from_array::<#field_ty, _, _>(#length, __rcursor) },
+                }
+            }
+            None => read_ts(field_ty.as_ref(), field_value_expr.as_ref(), lifetime, field_variant.as_ref()),
+        };
+        let field_name = field_ident.to_string();
+        let field_path = match variant_ident {
+            Some(variant_ident) => format!("{}.{}", variant_ident, field_name.trim_start_matches("__")),
+            None => field_name.trim_start_matches("__").to_string(),
+        };
+        // types which are inferred can not be printed, because the value is used before the inference
+        let debug_ts = match field_ty {
+            Some(ref ty) if ty.to_string() == "_" => quote! { std::option::Option::None },
+            _ => dissect_debug_ts(quote! { __value }),
+        };
+        let mut read_ts = quote! {
+            {
+                let __offset = __start - bird_protocol::ProtocolCursor::remaining_bytes(&*__rcursor);
+                bird_protocol::ProtocolCursor::enter_field(&mut *__rcursor, std::stringify!(#ty_ident), #field_path);
+                let __value = #read_ts.map_err(|__err| __err.with_field(std::stringify!(#ty_ident), #field_path, __offset))?;
+                bird_protocol::ProtocolCursor::leave_field(&mut *__rcursor, #debug_ts);
+                __value
+            }
+        };
+        if let Some(condition) = condition {
+            // earlier fields are references in the condition as in the writable derive
+            read_ts = quote! {
+                if { #(#[allow(unused_variables)] let #read_idents = &#read_idents;)* #condition } {
+                    std::option::Option::Some(#read_ts)
+                } else {
+                    std::option::Option::None
+                }
+            };
+        }
+        variables_ts.push(match field_versions {
+            Some(versions) => {
+                let matches = versions.matches_ts(quote! { __version });
+                quote! { let #field_ident = if #matches { #read_ts } else { std::default::Default::default() }; }
+            }
+            None => quote! { let #field_ident = #read_ts; },
+        });
+        if field_value_expr.is_none() {
+            read_idents.push(field_ident);
+        }
     }
     Ok(quote! {
         #(#variables_ts;)*
@@ -120,12 +226,30 @@ fn read_fields(fields: Fields, key: TokenStream, lifetime: &impl ToTokens, ghost
 fn read_ts(ty: Option<&impl ToTokens>, val: Option<&impl ToTokens>, lifetime: &impl ToTokens, variant: Option<&impl ToTokens>) -> TokenStream {
     match variant {
         Some(variant) => match ty {
-            Some(ty) => quote! { <#variant as bird_protocol::ProtocolVariantReadable<#lifetime, #ty>>::read_variant(__rcursor)? },
-            None => quote! { bird_protocol::__private::read_of_variant_val::<#lifetime, _, #variant, _>(&#val, __rcursor)? },
+            Some(ty) => quote! { <#variant as bird_protocol::ProtocolVariantReadable<#lifetime, #ty>>::read_variant(__rcursor) },
+            None => quote! { bird_protocol::__private::read_of_variant_val::<#lifetime, _, #variant, _>(&#val, __rcursor) },
         }
         None => match ty {
-            Some(ty) => quote! { <#ty as bird_protocol::ProtocolReadable<#lifetime>>::read(__rcursor)? },
-            None => quote! { bird_protocol::__private::read_of_val::<#lifetime, _, _>(&#val, __rcursor)? },
+            Some(ty) => quote! { <#ty as bird_protocol::ProtocolReadable<#lifetime>>::read(__rcursor) },
+            None => quote! { bird_protocol::__private::read_of_val::<#lifetime, _, _>(&#val, __rcursor) },
         }
     }
-}
\ No newline at end of file
+}
+
+/// Printable value for the dissector if the type of the value implements `Debug`
+fn dissect_debug_ts(value: TokenStream) -> TokenStream {
+    quote! {
+        {
+            #[allow(unused_imports)]
+            use bird_protocol::__private::{DissectDebug, DissectNotDebug};
+            (&bird_protocol::__private::DissectValue(&#value)).dissect_debug()
+        }
+    }
+}
+
+pub fn has_const_expression(ty: &TokenStream) -> bool {
+    ty.clone().into_iter().any(|tt| match tt {
+        TokenTree::Group(ref group) => group.delimiter() == Delimiter::Brace || has_const_expression(&group.stream()),
+        _ => false,
+    })
+}
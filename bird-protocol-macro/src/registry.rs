@@ -0,0 +1,132 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{Data, DeriveInput, Fields, Lifetime, Type};
+use syn::spanned::Spanned;
+use syn::visit_mut::VisitMut;
+use crate::shared::{ObjectAttributes, obligate_lifetime, parse_attributes};
+
+struct StaticLifetimes;
+
+impl VisitMut for StaticLifetimes {
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        *lifetime = Lifetime::new("'static", lifetime.span());
+    }
+}
+
+pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
+    let item: DeriveInput = syn::parse(item)?;
+    let DeriveInput {
+        attrs,
+        data,
+        ident,
+        mut generics,
+        ..
+    } = item;
+    let object_attributes: ObjectAttributes = parse_attributes(&attrs, "bp")?;
+    let state = object_attributes.packet_state.ok_or_else(|| syn::Error::new(Span::call_site(), "packet state should be provided"))?;
+    let bound = object_attributes.packet_bound.ok_or_else(|| syn::Error::new(Span::call_site(), "packet bound should be provided"))?;
+    let data_enum = match data {
+        Data::Enum(data_enum) => data_enum,
+        _ => return Err(syn::Error::new(Span::call_site(), "Only enums are supported")),
+    };
+    let mut packets = Vec::new();
+    for variant in data_enum.variants {
+        let span = variant.span();
+        match variant.fields {
+            Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 =>
+                packets.push((variant.ident, unnamed.unnamed.into_iter().next().unwrap().ty)),
+            _ => return Err(syn::Error::new(span, "Variant should contain only one packet")),
+        }
+    }
+    let (lifetime, spec_impl_generics) = obligate_lifetime(&mut generics)?;
+    let (from_impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    let (impl_generics, ..) = spec_impl_generics.split_for_impl();
+    let enum_name = ident.to_string();
+    let static_packet_types: Vec<Type> = packets.iter()
+        .map(|(_, ty)| {
+            let mut ty = ty.clone();
+            StaticLifetimes.visit_type_mut(&mut ty);
+            ty
+        })
+        .collect();
+    let mut checks = Vec::new();
+    for (index, (ty, (_, original_ty))) in static_packet_types.iter().zip(packets.iter()).enumerate() {
+        let ty_name = original_ty.to_token_stream().to_string().replace(' ', "");
+        let duplicate_message = format!("Id of packet {} is used more than once in {}", ty_name, enum_name);
+        let state_message = format!("Packet {} has another state than {}", ty_name, enum_name);
+        let bound_message = format!("Packet {} has another bound than {}", ty_name, enum_name);
+        checks.push(quote! {
+            std::assert!(
+                bird_protocol::version::packet_id_is_unique(__IDS, #index),
+                #duplicate_message
+            );
+            std::assert!(
+                <#ty as bird_protocol::ProtocolPacket>::STATE as u8 == (#state) as u8,
+                #state_message
+            );
+            std::assert!(
+                <#ty as bird_protocol::ProtocolPacket>::BOUND as u8 == (#bound) as u8,
+                #bound_message
+            );
+        });
+    }
+    let variant_idents: Vec<_> = packets.iter().map(|(variant_ident, _)| variant_ident).collect();
+    let packet_types: Vec<_> = packets.iter().map(|(_, ty)| ty).collect();
+    Ok(quote! {
+        const _: () = {
+            const __IDS: &[(i32, &[bird_protocol::version::ProtocolVersionId])] = &[#((
+                <#static_packet_types as bird_protocol::ProtocolPacket>::ID,
+                <#static_packet_types as bird_protocol::ProtocolPacket>::VERSION_IDS,
+            ),)*];
+            #(#checks)*
+        };
+
+        #(
+            impl #from_impl_generics std::convert::From<#packet_types> for #ident #type_generics #where_clause {
+                fn from(packet: #packet_types) -> Self {
+                    Self::#variant_idents(packet)
+                }
+            }
+        )*
+
+        impl #impl_generics bird_protocol::ProtocolPacketRegistry<#lifetime> for #ident #type_generics #where_clause {
+            const STATE: bird_protocol::ProtocolPacketState = #state;
+            const BOUND: bird_protocol::ProtocolPacketBound = #bound;
+
+            fn packet_id(&self) -> i32 {
+                match *self {
+                    #(Self::#variant_idents(_) => <#packet_types as bird_protocol::ProtocolPacket>::ID,)*
+                }
+            }
+
+            fn packet_id_for_version(&self, version: std::option::Option<i32>) -> i32 {
+                match *self {
+                    #(Self::#variant_idents(_) => <#packet_types as bird_protocol::ProtocolPacket>::id_for_version(version),)*
+                }
+            }
+
+            fn read_by_id<C: bird_protocol::ProtocolCursor<#lifetime>>(id: i32, cursor: &mut C) -> bird_protocol::ProtocolResult<Self> {
+                let __version = cursor.protocol_version();
+                match id {
+                    #(__id if __id == <#packet_types as bird_protocol::ProtocolPacket>::id_for_version(__version) =>
+                        Ok(Self::#variant_idents(<#packet_types as bird_protocol::ProtocolReadable<#lifetime>>::read(cursor)?)),)*
+                    _ => bird_protocol::ProtocolResult::Err(bird_protocol::ProtocolError::Any(
+                        bird_protocol::anyhow::Error::msg(std::format!("Bad packet id {} for {}", id, #enum_name))
+                    )),
+                }
+            }
+
+            fn write_packet<W: bird_protocol::ProtocolWriter>(&self, writer: &mut W) -> bird_protocol::anyhow::Result<()> {
+                match *self {
+                    #(Self::#variant_idents(ref packet) => bird_protocol::ProtocolWritable::write(packet, writer),)*
+                }
+            }
+
+            fn packet_encoded_len(&self, version: std::option::Option<i32>) -> usize {
+                match *self {
+                    #(Self::#variant_idents(ref packet) => bird_protocol::ProtocolWritable::encoded_len_for_version(packet, version),)*
+                }
+            }
+        }
+    })
+}
@@ -3,7 +3,8 @@ use std::str::FromStr;
 use either::Either;
 use proc_macro2::{Ident, Span, TokenStream, TokenTree};
 use quote::{quote, ToTokens};
-use syn::{Expr, ExprPath, ExprTuple, Fields, GenericParam, Generics, Lifetime, LifetimeDef, Lit, Token, Variant};
+use syn::{Data, Expr, ExprPath, ExprTuple, Field, Fields, GenericArgument, GenericParam, Generics, Lifetime, LifetimeDef, Lit, PathArguments, Token, Type, Variant, WhereClause};
+use syn::ext::IdentExt;
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
@@ -16,6 +17,7 @@ pub struct ObjectAttributes {
     pub packet_id: Option<TokenStream>,
     pub packet_bound: Option<TokenStream>,
     pub packet_state: Option<TokenStream>,
+    pub packet_versions: Vec<PacketVersion>,
     pub ghost_values: Vec<GhostValue>,
 }
 
@@ -29,6 +31,7 @@ impl Default for ObjectAttributes {
             packet_id: None,
             packet_bound: None,
             packet_state: None,
+            packet_versions: vec![],
             ghost_values: vec![]
         }
     }
@@ -59,6 +62,64 @@ pub enum GhostValueOrder {
 pub struct FieldAttributes {
     pub order: Option<(u32, Span)>,
     pub variant: Option<TokenStream>,
+    pub versions: Option<ProtocolVersions>,
+    pub max_len: Option<TokenStream>,
+    /// Expression of earlier fields (as references) which tells whether the `Option` field is present
+    pub condition: Option<TokenStream>,
+    /// Earlier field which contains the length of the array field
+    pub len_from: Option<TokenStream>,
+}
+
+impl FieldAttributes {
+    /// Variant which is used to read and write the field of the given type
+    pub fn field_variant(&self, ty: &TokenStream) -> Option<TokenStream> {
+        match self.max_len {
+            Some(ref max_len) => {
+                let variant = self.variant.as_ref().unwrap_or(ty);
+                Some(quote! { bird_protocol::LengthLimited<#variant, { (#max_len) as usize }> })
+            }
+            None => self.variant.clone(),
+        }
+    }
+}
+
+/// Range of protocol versions (both inclusive) in which a field or a packet id is present
+#[derive(Clone)]
+pub struct ProtocolVersions {
+    pub since: Option<TokenStream>,
+    pub until: Option<TokenStream>,
+}
+
+pub struct PacketVersion {
+    pub versions: ProtocolVersions,
+    pub id: TokenStream,
+}
+
+impl ProtocolVersions {
+    fn remove_from(attributes: &mut Attributes) -> syn::Result<Option<Self>> {
+        let since = attributes.remove_ts_attribute(&"since".into())?;
+        let until = attributes.remove_ts_attribute(&"until".into())?;
+        Ok(match since.is_none() && until.is_none() {
+            true => None,
+            false => Some(Self { since, until }),
+        })
+    }
+
+    pub fn bounds_ts(&self) -> (TokenStream, TokenStream) {
+        (option_ts(self.since.as_ref()), option_ts(self.until.as_ref()))
+    }
+
+    pub fn matches_ts(&self, version: impl ToTokens) -> TokenStream {
+        let (since, until) = self.bounds_ts();
+        quote! { bird_protocol::version::protocol_version_matches(#version, #since, #until) }
+    }
+}
+
+fn option_ts(value: Option<&TokenStream>) -> TokenStream {
+    match value {
+        Some(value) => quote! { std::option::Option::Some((#value) as i32) },
+        None => quote! { std::option::Option::None },
+    }
 }
 
 pub struct Attributes {
@@ -149,6 +210,34 @@ impl Attributes {
         }
     }
 
+    pub fn remove_packet_versions(&mut self, name: &String) -> syn::Result<Vec<PacketVersion>> {
+        struct PacketVersionsParse(Punctuated<PacketVersion, Token![,]>);
+
+        impl Parse for PacketVersionsParse {
+            fn parse(input: ParseStream) -> syn::Result<Self> {
+                let mut punctuated = Punctuated::new();
+                while !input.is_empty() {
+                    let expr_tuple: ExprTuple = input.parse()?;
+                    punctuated.push(syn::parse2(expr_tuple.elems.into_token_stream())?);
+                    if input.is_empty() {
+                        break;
+                    }
+                    punctuated.push_punct(input.parse()?);
+                }
+                Ok(Self(punctuated))
+            }
+        }
+
+        match self.remove_attribute(name) {
+            Some(Expr::Array(expr_array)) => {
+                let packet_versions: PacketVersionsParse = syn::parse2(expr_array.elems.into_token_stream())?;
+                Ok(packet_versions.0.into_iter().collect())
+            },
+            Some(it) => Err(syn::Error::new(it.span(), "Must be array of tuples")),
+            None => Ok(Vec::new()),
+        }
+    }
+
     pub fn remove_boolean_value(&mut self, name: &String, default_value: bool) -> syn::Result<(bool, Span)> {
         let attr = self.remove_attribute(name);
         match attr {
@@ -233,7 +322,7 @@ impl Parse for Attributes {
                         };
                     }
                     false => {
-                        current_expr_assign_key.replace(input.parse()?);
+                        current_expr_assign_key.replace(input.call(Ident::parse_any)?);
                         let _: Token![=] = input.parse()?;
                     }
                 }
@@ -284,6 +373,18 @@ impl Parse for GhostValue {
     }
 }
 
+impl Parse for PacketVersion {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut attributes: Attributes = input.parse()?;
+        Ok(Self {
+            id: attributes.remove_ts_attribute(&"id".into())?
+                .ok_or_else(|| syn::Error::new(input.span(), "Id must be provided"))?,
+            versions: ProtocolVersions::remove_from(&mut attributes)?
+                .ok_or_else(|| syn::Error::new(input.span(), "Since or until must be provided"))?,
+        })
+    }
+}
+
 impl Parse for ObjectAttributes {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let mut attributes: Attributes = input.parse()?;
@@ -295,6 +396,7 @@ impl Parse for ObjectAttributes {
             packet_id: attributes.remove_ts_attribute(&"id".into())?,
             packet_bound: attributes.remove_ts_attribute(&"bound".into())?,
             packet_state: attributes.remove_ts_attribute(&"state".into())?,
+            packet_versions: attributes.remove_packet_versions(&"versions".into())?,
             ghost_values: attributes.remove_ghost_values(&"ghost".into())?,
         })
     }
@@ -316,6 +418,13 @@ impl Parse for FieldAttributes {
         Ok(Self {
             order: attributes.remove_str_parse_attribute(&"order".into())?,
             variant: attributes.remove_ts_attribute(&"variant".into())?,
+            versions: ProtocolVersions::remove_from(&mut attributes)?,
+            max_len: attributes.remove_ts_attribute(&"max_len".into())?,
+            condition: match (attributes.remove_ts_attribute(&"if".into())?, attributes.remove_ts_attribute(&"flag".into())?) {
+                (Some(_), Some(flag)) => return Err(syn::Error::new(flag.span(), "If and flag can not be used together")),
+                (condition, flag) => condition.or_else(|| flag.map(|flag| quote! { #flag() })),
+            },
+            len_from: attributes.remove_ts_attribute(&"len_from".into())?,
         })
     }
 }
@@ -347,7 +456,87 @@ pub fn parse_attributes<A: Parse + Default>(attrs: &Vec<syn::Attribute>, attr_na
         .unwrap_or_else(|| Ok(A::default()))
 }
 
-pub fn create_prepared_fields(fields: Fields, ghost_values: impl Iterator<Item=GhostValue>) -> syn::Result<Vec<(TokenStream, Option<TokenStream>, Option<TokenStream>, Option<TokenStream>)>> {
+/// Field (or ghost value) in the order of reading and writing
+pub struct PreparedField {
+    pub ident: TokenStream,
+    pub value: Option<TokenStream>,
+    /// Type of the field. For conditional fields it is the type inside `Option`
+    pub ty: Option<TokenStream>,
+    pub variant: Option<TokenStream>,
+    pub versions: Option<ProtocolVersions>,
+    pub condition: Option<TokenStream>,
+    pub len_from: Option<TokenStream>,
+}
+
+/// Type inside `Option` which is read and written when the condition of the field is true
+pub fn option_inner_ty(ty: &Type) -> syn::Result<&Type> {
+    if let Type::Path(type_path) = ty {
+        if let Some(segment) = type_path.path.segments.last() {
+            if let PathArguments::AngleBracketed(ref arguments) = segment.arguments {
+                if let (true, Some(GenericArgument::Type(inner))) = (segment.ident == "Option", arguments.args.first()) {
+                    return Ok(inner);
+                }
+            }
+        }
+    }
+    Err(syn::Error::new(ty.span(), "Field with condition must be Option"))
+}
+
+/// Type and variant which are used to read, write and size the field
+pub fn field_ty_and_variant(field: &Field, field_attributes: &FieldAttributes) -> syn::Result<(TokenStream, Option<TokenStream>)> {
+    let ty = match field_attributes.condition {
+        Some(_) => option_inner_ty(&field.ty)?.into_token_stream(),
+        None => field.ty.to_token_stream(),
+    };
+    let variant = field_attributes.field_variant(&ty);
+    Ok((ty, variant))
+}
+
+/// Where clause of the derived impl. Every type parameter gets `param_bound`
+/// and every field which type (or variant) uses type parameters gets the bound created by `field_bound`
+pub fn derive_where_clause(
+    generics: &Generics,
+    data: &Data,
+    param_bound: Option<TokenStream>,
+    field_bound: impl Fn(&TokenStream, Option<&TokenStream>) -> TokenStream,
+) -> syn::Result<WhereClause> {
+    let mut where_clause = generics.where_clause.clone().unwrap_or_else(|| WhereClause {
+        where_token: Default::default(),
+        predicates: Punctuated::new(),
+    });
+    let params: Vec<&Ident> = generics.type_params().map(|param| &param.ident).collect();
+    if let Some(param_bound) = param_bound {
+        for param in &params {
+            where_clause.predicates.push(syn::parse2(quote! { #param: #param_bound })?);
+        }
+    }
+    let fields: Vec<&Field> = match data {
+        Data::Struct(data_struct) => data_struct.fields.iter().collect(),
+        Data::Enum(data_enum) => data_enum.variants.iter().flat_map(|variant| variant.fields.iter()).collect(),
+        Data::Union(_) => Vec::new(),
+    };
+    for field in fields {
+        let field_attributes: FieldAttributes = parse_attributes(&field.attrs, "bp")?;
+        if field_attributes.len_from.is_some() {
+            continue;
+        }
+        let (ty, variant) = field_ty_and_variant(field, &field_attributes)?;
+        if uses_idents(&ty, &params) || matches!(variant, Some(ref variant) if uses_idents(variant, &params)) {
+            where_clause.predicates.push(syn::parse2(field_bound(&ty, variant.as_ref()))?);
+        }
+    }
+    Ok(where_clause)
+}
+
+fn uses_idents(ts: &TokenStream, idents: &[&Ident]) -> bool {
+    ts.clone().into_iter().any(|tt| match tt {
+        TokenTree::Ident(ref ident) => idents.contains(&ident),
+        TokenTree::Group(ref group) => uses_idents(&group.stream(), idents),
+        _ => false,
+    })
+}
+
+pub fn create_prepared_fields(fields: Fields, ghost_values: impl Iterator<Item=GhostValue>) -> syn::Result<Vec<PreparedField>> {
     let mut counter = 0;
     let mut begin = Vec::new();
     let mut end = Vec::new();
@@ -359,7 +548,16 @@ pub fn create_prepared_fields(fields: Fields, ghost_values: impl Iterator<Item=G
             counter += 1;
         }
         let field_attributes: FieldAttributes = parse_attributes(&field.attrs, "bp")?;
-        let to_insert = (field.ident.unwrap().into_token_stream(), None, Some(field.ty.into_token_stream()), field_attributes.variant);
+        let (ty, variant) = field_ty_and_variant(&field, &field_attributes)?;
+        let to_insert = PreparedField {
+            ident: field.ident.unwrap().into_token_stream(),
+            value: None,
+            ty: Some(ty),
+            variant,
+            versions: field_attributes.versions,
+            condition: field_attributes.condition,
+            len_from: field_attributes.len_from,
+        };
         match field_attributes.order {
             Some((order, span)) => if let Some(_) = specific_ordered_fields.insert(order, to_insert) {
                 return Err(syn::Error::new(span, "Repeated order value"));
@@ -368,7 +566,15 @@ pub fn create_prepared_fields(fields: Fields, ghost_values: impl Iterator<Item=G
         }
     }
     for ghost_value in ghost_values {
-        let to_insert = (quote! { _ }, Some(ghost_value.value), ghost_value.ty, ghost_value.variant);
+        let to_insert = PreparedField {
+            ident: quote! { _ },
+            value: Some(ghost_value.value),
+            ty: ghost_value.ty,
+            variant: ghost_value.variant,
+            versions: None,
+            condition: None,
+            len_from: None,
+        };
         match ghost_value.order {
             GhostValueOrder::Begin => begin.push(to_insert),
             GhostValueOrder::End => end.push(to_insert),
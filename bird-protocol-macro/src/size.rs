@@ -1,7 +1,7 @@
 use proc_macro2::{Span, TokenStream};
 use quote::{quote, ToTokens};
 use syn::{Data, DeriveInput, Fields};
-use crate::shared::{FieldAttributes, GhostValue, ObjectAttributes, parse_attributes, VariantAttributes};
+use crate::shared::{derive_where_clause, field_ty_and_variant, FieldAttributes, GhostValue, ObjectAttributes, parse_attributes, VariantAttributes};
 
 pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
     let item: DeriveInput = syn::parse(item)?;
@@ -13,6 +13,10 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
         ..
     } = item;
     let object_attributes: ObjectAttributes = parse_attributes(&attrs, "bp")?;
+    let where_clause = derive_where_clause(&generics, &data, None, |ty, variant| {
+        let ty = variant.unwrap_or(ty);
+        quote! { #ty: bird_protocol::ProtocolSize }
+    })?;
     let size = match data {
         Data::Struct(data_struct) => {
             let (min, max) = fields_size(data_struct.fields, object_attributes.ghost_values.into_iter())?;
@@ -45,7 +49,7 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
         }
         Data::Union(_) => return Err(syn::Error::new(Span::mixed_site(), "Union type is not supported")),
     };
-    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    let (impl_generics, type_generics, _) = generics.split_for_impl();
     Ok(quote! {
         impl #impl_generics bird_protocol::ProtocolSize for #ident #type_generics #where_clause {
             const SIZE: std::ops::Range<u32> = #size;
@@ -64,6 +68,10 @@ pub fn fields_size(fields: Fields, ghost_values: impl Iterator<Item=GhostValue>)
     enum Size {
         Ty(TokenStream),
         Val(TokenStream),
+        // field is not present in some protocol versions or its condition is false
+        Versioned(TokenStream),
+        // length of the array is stored in another field
+        Unbounded,
     }
     let mut min_size_types = Vec::new();
     let mut max_size_types = Vec::new();
@@ -73,9 +81,19 @@ pub fn fields_size(fields: Fields, ghost_values: impl Iterator<Item=GhostValue>)
         fields_with_attrs.push((field, field_attributes));
     }
     for ty in fields_with_attrs.into_iter()
-        .map(|(field, field_attributes)|
-            Size::Ty(field_attributes.variant.unwrap_or_else(|| field.ty.into_token_stream()))
-        )
+        .map(|(field, field_attributes)| {
+            let (ty, variant) = field_ty_and_variant(&field, &field_attributes)?;
+            let ty = variant.unwrap_or(ty);
+            Ok(match field_attributes.len_from {
+                Some(_) => Size::Unbounded,
+                None => match field_attributes.versions.is_some() || field_attributes.condition.is_some() {
+                    true => Size::Versioned(ty),
+                    false => Size::Ty(ty),
+                },
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?
+        .into_iter()
         .chain(ghost_values.into_iter().map(|ghost_value| ghost_value.variant
             .or(ghost_value.ty)
             .map(|v| Size::Ty(v))
@@ -93,6 +111,14 @@ pub fn fields_size(fields: Fields, ghost_values: impl Iterator<Item=GhostValue>)
                 min_size_types.push(quote! { #val.start });
                 max_size_types.push(quote! { #val.end });
             }
+            Size::Versioned(ty) => {
+                min_size_types.push(quote! { 0 });
+                max_size_types.push(max_size_ts(&ty));
+            }
+            Size::Unbounded => {
+                min_size_types.push(quote! { 0 });
+                max_size_types.push(quote! { u32::MAX });
+            }
         }
     }
     Ok((
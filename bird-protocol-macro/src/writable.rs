@@ -1,7 +1,7 @@
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, ToTokens};
 use syn::{Data, DeriveInput, Fields, Variant};
-use crate::shared::{create_prepared_fields, create_prepared_variants, GhostValue, ObjectAttributes, parse_attributes};
+use crate::shared::{create_prepared_fields, create_prepared_variants, derive_where_clause, GhostValue, ObjectAttributes, parse_attributes, PreparedField};
 
 pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
     let item: DeriveInput = syn::parse(item)?;
@@ -13,16 +13,24 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
         ..
     } = item;
     let object_attributes: ObjectAttributes = parse_attributes(&attrs, "bp")?;
-    let function_body = match data {
+    let where_clause = derive_where_clause(&generics, &data, None, |ty, variant| match variant {
+        Some(variant) => quote! { #variant: bird_protocol::ProtocolVariantWritable<#ty> },
+        None => quote! { #ty: bird_protocol::ProtocolWritable },
+    })?;
+    let (function_body, encoded_len_body) = match data {
         Data::Struct(data_struct) => {
             let write_match = write_match(quote! { Self }, &data_struct.fields)?;
-            let write_fields = write_fields(data_struct.fields, object_attributes.ghost_values.into_iter())?;
-            quote! { #write_match => { #write_fields }, }
+            let (write_fields, fields_len) = write_fields(data_struct.fields, object_attributes.ghost_values.into_iter())?;
+            (
+                quote! { #write_match => { #write_fields }, },
+                quote! { #write_match => { #fields_len }, },
+            )
         }
         Data::Enum(data_enum) => {
             let key_ty = object_attributes.key_ty.as_ref().ok_or_else(|| syn::Error::new(Span::call_site(), "You should provide key_ty for enum object"))?;
             let variants = create_prepared_variants(data_enum.variants.into_iter(), &object_attributes)?;
             let mut variant_matches = Vec::new();
+            let mut variant_len_matches = Vec::new();
             for (variant, variant_value, variant_attributes) in variants {
                 let Variant {
                     fields,
@@ -31,7 +39,8 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                 } = variant;
                 let write_match = write_match(quote! { Self::#ident }, &fields)?;
                 let write_key = write_ts(&quote! { (#variant_value) }, key_ty, object_attributes.key_variant.as_ref());
-                let write_fields = write_fields(
+                let key_len = encoded_len_ts(&quote! { (#variant_value) }, key_ty, object_attributes.key_variant.as_ref());
+                let (write_fields, fields_len) = write_fields(
                     fields,
                     object_attributes.ghost_values.iter().cloned().chain(variant_attributes.ghost_values.into_iter()),
                 )?;
@@ -39,15 +48,22 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                     false => quote! { #write_match => { #write_key; #write_fields } },
                     true => quote! { #write_match => { #write_fields #write_key; } }
                 });
+                variant_len_matches.push(quote! { #write_match => { __length += #key_len; #fields_len } });
             }
-            quote! {
-                #(#variant_matches,)*
-                _ => unreachable!()
-            }
+            (
+                quote! {
+                    #(#variant_matches,)*
+                    _ => unreachable!()
+                },
+                quote! {
+                    #(#variant_len_matches,)*
+                    _ => unreachable!()
+                },
+            )
         }
         Data::Union(_) => return Err(syn::Error::new(Span::mixed_site(), "Union is not supported")),
     };
-    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    let (impl_generics, type_generics, _) = generics.split_for_impl();
     Ok(quote! {
         impl #impl_generics bird_protocol::ProtocolWritable for #ident #type_generics #where_clause {
             fn write<W: bird_protocol::ProtocolWriter>(&self, __writer: &mut W) -> bird_protocol::anyhow::Result<()> {
@@ -56,6 +72,15 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                 }
                 bird_protocol::anyhow::Result::Ok(())
             }
+
+            #[allow(unreachable_code)]
+            fn encoded_len_for_version(&self, __version: std::option::Option<i32>) -> usize {
+                let mut __length = 0usize;
+                match self {
+                    #encoded_len_body
+                }
+                __length
+            }
         }
     })
 }
@@ -80,14 +105,65 @@ pub fn write_match(key: impl ToTokens, fields: &Fields) -> syn::Result<TokenStre
     })
 }
 
-pub fn write_fields(fields: Fields, ghost_values: impl Iterator<Item = GhostValue>) -> syn::Result<TokenStream> {
+/// Writes of the fields and the addition of their encoded lengths to `__length`
+pub fn write_fields(fields: Fields, ghost_values: impl Iterator<Item = GhostValue>) -> syn::Result<(TokenStream, TokenStream)> {
     let fields = create_prepared_fields(fields, ghost_values)?;
     let mut writes_ts = Vec::new();
-    for (field_ident, field_value_expr, field_ty, field_variant) in fields {
-        let write_ts = write_ts(&field_value_expr.unwrap_or(field_ident), &field_ty.unwrap_or_else(|| quote! { _ }), field_variant.as_ref());
-        writes_ts.push(write_ts)
+    let mut lengths_ts = Vec::new();
+    for PreparedField { ident: field_ident, value: field_value_expr, ty: field_ty, variant: field_variant, versions: field_versions, condition, len_from } in fields {
+        let field_name = field_ident.to_string();
+        let field_ty = field_ty.unwrap_or_else(|| quote! { _ });
+        let (write, field_ty) = match condition {
+            Some(_) => (quote! { (*__value) }, quote! { _ }),
+            None => (field_value_expr.unwrap_or_else(|| field_ident.clone()), field_ty),
+        };
+        let (mut write_ts, mut length_ts) = match len_from {
+            Some(len_from) => {
+                let length = quote! { bird_protocol::ProtocolLength::into_usize(std::clone::Clone::clone(#len_from)) };
+                match field_variant {
+                    Some(ref variant) => (
+                        quote! { <#field_ty as bird_protocol::ProtocolLengthFromArray<_, #variant>>::write_with_length(&#write, #length, __writer)? },
+                        quote! { <#field_ty as bird_protocol::ProtocolLengthFromArray<_, #variant>>::encoded_len_without_length(&#write, __version) },
+                    ),
+                    None => (
+                        quote! { bird_protocol::__private::write_length_from_array::<#field_ty, _, _>(&#write, #length, __writer)? },
+                        quote! { bird_protocol::__private::length_from_array_encoded_len::<#field_ty, _>(&#write, __version) },
+                    ),
+                }
+            }
+            None => (write_ts(&write, &field_ty, field_variant.as_ref()), encoded_len_ts(&write, &field_ty, field_variant.as_ref())),
+        };
+        if let Some(condition) = condition {
+            write_ts = quote! {
+                match (#condition, #field_ident) {
+                    (true, std::option::Option::Some(__value)) => { #write_ts; }
+                    (false, std::option::Option::None) => {}
+                    _ => return bird_protocol::anyhow::Result::Err(bird_protocol::anyhow::Error::msg(
+                        std::format!("Presence of {} does not match its condition", #field_name)
+                    )),
+                }
+            };
+            length_ts = quote! {
+                match (#condition, #field_ident) {
+                    (true, std::option::Option::Some(__value)) => #length_ts,
+                    _ => 0,
+                }
+            };
+        }
+        match field_versions {
+            Some(versions) => {
+                let matches = versions.matches_ts(quote! { bird_protocol::ProtocolWriter::protocol_version(&*__writer) });
+                writes_ts.push(quote! { if #matches { #write_ts; } });
+                let matches = versions.matches_ts(quote! { __version });
+                lengths_ts.push(quote! { if #matches { __length += #length_ts; } });
+            }
+            None => {
+                writes_ts.push(write_ts);
+                lengths_ts.push(quote! { __length += #length_ts; });
+            }
+        }
     }
-    Ok(quote! { #(#writes_ts;)* })
+    Ok((quote! { #(#writes_ts;)* }, quote! { #(#lengths_ts)* }))
 }
 
 pub fn write_ts(write: &impl ToTokens, ty: &impl ToTokens, variant: Option<&impl ToTokens>) -> TokenStream {
@@ -95,4 +171,11 @@ pub fn write_ts(write: &impl ToTokens, ty: &impl ToTokens, variant: Option<&impl
         Some(variant) => quote! { <#variant as bird_protocol::ProtocolVariantWritable<#ty>>::write_variant(&#write, __writer)? },
         None => quote! { <#ty as bird_protocol::ProtocolWritable>::write(&#write, __writer)? },
     }
+}
+
+pub fn encoded_len_ts(write: &impl ToTokens, ty: &impl ToTokens, variant: Option<&impl ToTokens>) -> TokenStream {
+    match variant {
+        Some(variant) => quote! { <#variant as bird_protocol::ProtocolVariantWritable<#ty>>::encoded_len_variant(&#write, __version) },
+        None => quote! { <#ty as bird_protocol::ProtocolWritable>::encoded_len_for_version(&#write, __version) },
+    }
 }
\ No newline at end of file
@@ -0,0 +1,508 @@
+use std::borrow::Cow;
+use std::mem::size_of;
+use bird_chat::component::{Component, ComponentType};
+use bird_chat::identifier::Identifier;
+use uuid::Uuid;
+
+pub use ::arbitrary::{Error as ArbitraryError, Result as ArbitraryResult, Unstructured};
+
+use crate::*;
+use crate::impls::{byte_array_into_t_array, DEFAULT_LIMIT};
+
+/// The biggest count of elements in generated arrays which length is not constant
+pub const MAX_ARBITRARY_LENGTH: usize = 8;
+
+/// Object which arbitrary values can be generated for. Every generated value should be writable
+pub trait ProtocolArbitrary<'a>: Sized {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self>;
+}
+
+/// Variant which generates arbitrary values of `V` which it can write
+pub trait ProtocolVariantArbitrary<'a, V> {
+    fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<V>;
+}
+
+/// Length determiner which knows the count of elements in generated arrays
+pub trait ProtocolLengthArbitrary {
+    fn arbitrary_length(u: &mut Unstructured) -> ArbitraryResult<usize>;
+}
+
+impl<'a, T: ProtocolArbitrary<'a>> ProtocolVariantArbitrary<'a, T> for T {
+    fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<T> {
+        T::arbitrary(u)
+    }
+}
+
+macro_rules! arbitrary_delegate_impl {
+    ($($ty: ty$(,)*)*) => {
+        $(
+            impl<'a> ProtocolArbitrary<'a> for $ty {
+                fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+                    u.arbitrary()
+                }
+            }
+        )*
+    }
+}
+
+/// Passes types which values are generated by [`Unstructured::arbitrary`] to `$callback`,
+/// so the tests check the same types
+macro_rules! arbitrary_delegate_types {
+    ($callback: ident) => {
+        $callback!(bool, i8, u8, i16, u16, i32, u32, i64, u64, i128, u128);
+    }
+}
+
+arbitrary_delegate_types!(arbitrary_delegate_impl);
+
+macro_rules! arbitrary_float_impl {
+    ($($ty: ty$(,)*)*) => {
+        $(
+            impl<'a> ProtocolArbitrary<'a> for $ty {
+                fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+                    // NaN is not equal to itself, so read values could not be compared with generated ones
+                    let value: $ty = u.arbitrary()?;
+                    Ok(match value.is_nan() {
+                        true => 0.0,
+                        false => value,
+                    })
+                }
+            }
+        )*
+    }
+}
+
+arbitrary_float_impl!(f32, f64);
+
+macro_rules! variant_arbitrary_delegate_impl {
+    ($($variant: ty = ($($ty: ty$(,)*)*)$(,)*)*) => {
+        $($(
+            impl<'a> ProtocolVariantArbitrary<'a, $ty> for $variant {
+                fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<$ty> {
+                    u.arbitrary()
+                }
+            }
+        )*)*
+    }
+}
+
+variant_arbitrary_delegate_impl!(
+    VarInt = (bool, i8, u8, i16, u16, i32, u32),
+    VarLong = (bool, i8, u8, i16, u16, i32, u32, i64, u64),
+);
+
+impl<'a, T: ProtocolArbitrary<'a>> ProtocolArbitrary<'a> for Option<T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        Ok(match u.arbitrary()? {
+            true => Some(T::arbitrary(u)?),
+            false => None,
+        })
+    }
+}
+
+impl<'a> ProtocolArbitrary<'a> for &'a str {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        let value: &'a str = u.arbitrary()?;
+        match value.len() <= DEFAULT_LIMIT {
+            true => Ok(value),
+            false => Err(ArbitraryError::IncorrectFormat),
+        }
+    }
+}
+
+impl<'a> ProtocolArbitrary<'a> for String {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        <&'a str>::arbitrary(u).map(|value| value.into())
+    }
+}
+
+impl<'a> ProtocolArbitrary<'a> for Cow<'a, str> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        <&'a str>::arbitrary(u).map(Cow::Borrowed)
+    }
+}
+
+impl<'a> ProtocolArbitrary<'a> for Uuid {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        u.arbitrary().map(Uuid::from_bytes)
+    }
+}
+
+fn arbitrary_identifier_part(u: &mut Unstructured, path: bool) -> ArbitraryResult<String> {
+    const CHARACTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789_-./";
+    let characters = match path {
+        true => CHARACTERS,
+        false => &CHARACTERS[..CHARACTERS.len() - 1],
+    };
+    let length = u.int_in_range(1..=16)?;
+    (0..length).map(|_| u.choose(characters).map(|character| *character as char)).collect()
+}
+
+impl<'a> ProtocolArbitrary<'a> for Identifier<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        let key = arbitrary_identifier_part(u, false)?;
+        let value = arbitrary_identifier_part(u, true)?;
+        Identifier::new_partial(Cow::Owned(key), Cow::Owned(value)).ok_or(ArbitraryError::IncorrectFormat)
+    }
+}
+
+/// Plain text component
+impl<'a> ProtocolArbitrary<'a> for Component<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        Ok(Component {
+            bold: None,
+            italic: None,
+            underlined: None,
+            strikethrough: None,
+            obfuscated: None,
+            font: None,
+            color: None,
+            insertion: None,
+            click_event: None,
+            extra: Cow::Borrowed(&[]),
+            hover_event: None,
+            ty: Some(ComponentType::Text { text: Cow::arbitrary(u)? }),
+        })
+    }
+}
+
+impl<'a, T: ProtocolArbitrary<'a>> ProtocolVariantArbitrary<'a, T> for Json {
+    fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<T> {
+        T::arbitrary(u)
+    }
+}
+
+impl<'a> ProtocolVariantArbitrary<'a, f32> for Angle {
+    fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<f32> {
+        Ok((u8::arbitrary(u)? as f32) * std::f32::consts::PI / 256.0)
+    }
+}
+
+macro_rules! fixed_point_number_arbitrary_impl {
+    ($($ty: ty$(,)*)*) => {
+        $(
+            impl<'a, const N: u8> ProtocolVariantArbitrary<'a, f32> for FixedPointNumber<$ty, N> {
+                fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<f32> {
+                    Ok((<$ty>::arbitrary(u)? as f32) / (1 << N) as f32)
+                }
+            }
+
+            impl<'a, const N: u8> ProtocolVariantArbitrary<'a, f64> for FixedPointNumber<$ty, N> {
+                fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<f64> {
+                    Ok((<$ty>::arbitrary(u)? as f64) / (1 << N) as f64)
+                }
+            }
+        )*
+    }
+}
+
+fixed_point_number_arbitrary_impl!(i16, u16, i32, u32, i64, u64);
+
+/// Nbt compound without name which contains arbitrary int tags
+fn arbitrary_nbt_compound(u: &mut Unstructured) -> ArbitraryResult<Vec<u8>> {
+    let mut bytes = vec![10, 0, 0];
+    for index in 0..u.int_in_range(0..=MAX_ARBITRARY_LENGTH)? {
+        let name = index.to_string();
+        bytes.push(3);
+        bytes.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.extend_from_slice(&i32::arbitrary(u)?.to_be_bytes());
+    }
+    bytes.push(0);
+    Ok(bytes)
+}
+
+impl<'a> ProtocolVariantArbitrary<'a, &'a [u8]> for NbtBytes {
+    fn arbitrary_variant(_u: &mut Unstructured<'a>) -> ArbitraryResult<&'a [u8]> {
+        Ok(&[10, 0, 0, 0])
+    }
+}
+
+impl<'a> ProtocolVariantArbitrary<'a, Vec<u8>> for NbtBytes {
+    fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<Vec<u8>> {
+        arbitrary_nbt_compound(u)
+    }
+}
+
+impl<'a> ProtocolVariantArbitrary<'a, Cow<'a, [u8]>> for NbtBytes {
+    fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<Cow<'a, [u8]>> {
+        arbitrary_nbt_compound(u).map(Cow::Owned)
+    }
+}
+
+impl<L, LV> ProtocolLengthArbitrary for ProtocolLengthProvidedDeterminer<L, LV> {
+    fn arbitrary_length(u: &mut Unstructured) -> ArbitraryResult<usize> {
+        u.int_in_range(0..=MAX_ARBITRARY_LENGTH)
+    }
+}
+
+impl ProtocolLengthArbitrary for ProtocolLengthRemainingDeterminer {
+    fn arbitrary_length(u: &mut Unstructured) -> ArbitraryResult<usize> {
+        u.int_in_range(0..=MAX_ARBITRARY_LENGTH)
+    }
+}
+
+impl<const N: usize> ProtocolLengthArbitrary for ProtocolLengthConstDeterminer<N> {
+    fn arbitrary_length(_u: &mut Unstructured) -> ArbitraryResult<usize> {
+        Ok(N)
+    }
+}
+
+/// Arbitrary length of arrays which length is stored in another field
+pub fn arbitrary_length(u: &mut Unstructured) -> ArbitraryResult<usize> {
+    u.int_in_range(0..=MAX_ARBITRARY_LENGTH)
+}
+
+fn arbitrary_elements<'a, V, VV: ProtocolVariantArbitrary<'a, V>, A: FromIterator<V>>(
+    length: usize,
+    u: &mut Unstructured<'a>,
+) -> ArbitraryResult<A> {
+    (0..length).map(|_| VV::arbitrary_variant(u)).collect()
+}
+
+/// Arbitrary `len_from` array of the given length
+pub fn arbitrary_length_from_array<'a, A, V, VV>(length: usize, u: &mut Unstructured<'a>) -> ArbitraryResult<A>
+    where A: ProtocolLengthFromArray<V, VV> + FromIterator<V>, VV: ProtocolVariantArbitrary<'a, V> {
+    match length <= MAX_ARBITRARY_LENGTH {
+        true => arbitrary_elements::<V, VV, A>(length, u),
+        false => Err(ArbitraryError::IncorrectFormat),
+    }
+}
+
+/// Arbitrary `len_from` array of elements without variant
+pub fn arbitrary_length_from_array_of<'a, A, V>(length: usize, u: &mut Unstructured<'a>) -> ArbitraryResult<A>
+    where A: ProtocolLengthFromArray<V, V> + FromIterator<V>, V: ProtocolArbitrary<'a> {
+    arbitrary_length_from_array::<A, V, V>(length, u)
+}
+
+impl<'a, V: ProtocolRaw, VV, T: ProtocolLengthArbitrary> ProtocolVariantArbitrary<'a, &'a [V]> for LengthFunctionRawArray<V, VV, T> {
+    fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<&'a [V]> {
+        let length = T::arbitrary_length(u)?;
+        Ok(byte_array_into_t_array(u.bytes(length * size_of::<V>())?))
+    }
+}
+
+impl<'a, V: ProtocolArbitrary<'a>, VV, T: ProtocolLengthArbitrary> ProtocolVariantArbitrary<'a, Vec<V>> for LengthFunctionRawArray<V, VV, T> {
+    fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<Vec<V>> {
+        arbitrary_elements::<V, V, _>(T::arbitrary_length(u)?, u)
+    }
+}
+
+impl<'a, V: ProtocolArbitrary<'a> + Clone, VV, T: ProtocolLengthArbitrary> ProtocolVariantArbitrary<'a, Cow<'a, [V]>> for LengthFunctionRawArray<V, VV, T> {
+    fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<Cow<'a, [V]>> {
+        arbitrary_elements::<V, V, _>(T::arbitrary_length(u)?, u)
+    }
+}
+
+impl<'a, V, VV: ProtocolVariantArbitrary<'a, V>, T: ProtocolLengthArbitrary> ProtocolVariantArbitrary<'a, Vec<V>> for LengthFunctionArray<V, VV, T> {
+    fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<Vec<V>> {
+        arbitrary_elements::<V, VV, _>(T::arbitrary_length(u)?, u)
+    }
+}
+
+impl<'a, V: Clone, VV: ProtocolVariantArbitrary<'a, V>, T: ProtocolLengthArbitrary> ProtocolVariantArbitrary<'a, Cow<'a, [V]>> for LengthFunctionArray<V, VV, T> {
+    fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<Cow<'a, [V]>> {
+        arbitrary_elements::<V, VV, _>(T::arbitrary_length(u)?, u)
+    }
+}
+
+impl<'a, T: ProtocolArbitrary<'a>, const LENGTH: usize> ProtocolVariantArbitrary<'a, [T; LENGTH]> for ConstLengthArray<T, LENGTH> {
+    fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<[T; LENGTH]> {
+        let elements: Vec<T> = arbitrary_elements::<T, T, _>(LENGTH, u)?;
+        elements.try_into().map_err(|_| ArbitraryError::IncorrectFormat)
+    }
+}
+
+impl<'a, T: ProtocolRaw, const LENGTH: usize> ProtocolVariantArbitrary<'a, &'a [T; LENGTH]> for ConstLengthRawArray<T, LENGTH> {
+    fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<&'a [T; LENGTH]> {
+        byte_array_into_t_array(u.bytes(LENGTH * size_of::<T>())?)
+            .try_into()
+            .map_err(|_| ArbitraryError::IncorrectFormat)
+    }
+}
+
+impl<'a, T: ProtocolRaw + Clone, const LENGTH: usize> ProtocolVariantArbitrary<'a, Cow<'a, [T; LENGTH]>> for ConstLengthRawArray<T, LENGTH> {
+    fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<Cow<'a, [T; LENGTH]>> {
+        <Self as ProtocolVariantArbitrary<'a, &'a [T; LENGTH]>>::arbitrary_variant(u).map(Cow::Borrowed)
+    }
+}
+
+impl<'a, V, VV: ProtocolVariantArbitrary<'a, V>> ProtocolVariantArbitrary<'a, Option<V>> for ProtocolVariantOption<V, VV> {
+    fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<Option<V>> {
+        Ok(match u.arbitrary()? {
+            true => Some(VV::arbitrary_variant(u)?),
+            false => None,
+        })
+    }
+}
+
+impl<'a, T: ProtocolArbitrary<'a>, const S: usize> ProtocolVariantArbitrary<'a, Option<T>> for ProtocolSizeOption<T, S> {
+    fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<Option<T>> {
+        Option::arbitrary(u)
+    }
+}
+
+/// Values which are longer than the limit are rejected
+impl<'a, V, VV: ProtocolVariantArbitrary<'a, V> + ProtocolLengthLimit<V>, const MAX: usize> ProtocolVariantArbitrary<'a, V> for LengthLimited<VV, MAX> {
+    fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<V> {
+        let value = VV::arbitrary_variant(u)?;
+        match VV::length_of(&value) <= MAX {
+            true => Ok(value),
+            false => Err(ArbitraryError::IncorrectFormat),
+        }
+    }
+}
+
+#[cfg(feature = "euclid")]
+mod euclid_impls {
+    use euclid::{Vector2D, Vector3D};
+    use super::*;
+
+    impl<'a, T: ProtocolArbitrary<'a>, U> ProtocolArbitrary<'a> for Vector3D<T, U> {
+        fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+            Ok(Vector3D::new(T::arbitrary(u)?, T::arbitrary(u)?, T::arbitrary(u)?))
+        }
+    }
+
+    impl<'a, T: ProtocolArbitrary<'a>, U> ProtocolArbitrary<'a> for Vector2D<T, U> {
+        fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+            Ok(Vector2D::new(T::arbitrary(u)?, T::arbitrary(u)?))
+        }
+    }
+
+    /// Coordinates which fit in `xz_bits` and `y_bits` signed bits
+    fn arbitrary_packed_position<U>(u: &mut Unstructured, xz_bits: u32, y_bits: u32) -> ArbitraryResult<Vector3D<i32, U>> {
+        let xz_range = -(1 << (xz_bits - 1))..=(1 << (xz_bits - 1)) - 1;
+        let y_range = -(1 << (y_bits - 1))..=(1 << (y_bits - 1)) - 1;
+        Ok(Vector3D::new(
+            u.int_in_range(xz_range.clone())?,
+            u.int_in_range(y_range)?,
+            u.int_in_range(xz_range)?,
+        ))
+    }
+
+    impl<'a, U> ProtocolVariantArbitrary<'a, Vector3D<i32, U>> for BlockPosition {
+        fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<Vector3D<i32, U>> {
+            arbitrary_packed_position(u, 26, 12)
+        }
+    }
+
+    impl<'a, U> ProtocolVariantArbitrary<'a, Vector3D<i32, U>> for SectionPosition {
+        fn arbitrary_variant(u: &mut Unstructured<'a>) -> ArbitraryResult<Vector3D<i32, U>> {
+            arbitrary_packed_position(u, 22, 20)
+        }
+    }
+}
+
+/// Bytes of a xorshift generator, so the same values are generated on every run
+pub fn arbitrary_data(seed: u64, length: usize) -> Vec<u8> {
+    let mut state = seed.wrapping_mul(0x9E3779B97F4A7C15) | 1;
+    (0..length).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state >> 32) as u8
+    }).collect()
+}
+
+/// Generates a value of `T` from `data`, writes it into `buffer`, reads it back and writes it again.
+/// Panics if the read value or the written bytes differ, the bytes are not read completely
+/// or their length is not in `T::SIZE`.
+/// Returns false if `data` is not enough to generate a value
+pub fn assert_arbitrary_round_trip<'a, T>(data: &'a [u8], buffer: &'a mut Vec<u8>) -> bool
+    where T: ProtocolArbitrary<'a> + ProtocolReadable<'a> + ProtocolWritable + PartialEq + std::fmt::Debug {
+    let value = match T::arbitrary(&mut Unstructured::new(data)) {
+        Ok(value) => value,
+        Err(_) => return false,
+    };
+    let ty = std::any::type_name::<T>();
+    if let Err(err) = value.write(buffer) {
+        panic!("{} {:?} can not be written: {}", ty, value, err);
+    }
+    let written: &'a Vec<u8> = buffer;
+    let length = written.len();
+    assert_eq!(value.encoded_len(), length, "Encoded length of {} {:?}", ty, value);
+    assert!(
+        T::SIZE.start as usize <= length && length <= T::SIZE.end as usize,
+        "Length {} of {} {:?} is out of {:?}", length, ty, value, T::SIZE,
+    );
+    let mut cursor = written.as_slice();
+    let read = match T::read(&mut cursor) {
+        Ok(read) => read,
+        Err(err) => panic!("{} {:?} can not be read from {:?}: {}", ty, value, written, err),
+    };
+    assert!(cursor.is_empty(), "{} bytes of {} {:?} are not read", cursor.len(), ty, value);
+    assert_eq!(value, read, "{} is read from {:?} as another value", ty, written);
+    let mut rewritten = Vec::new();
+    read.write(&mut rewritten).unwrap();
+    assert_eq!(written, &rewritten, "{} {:?} is read as {:?}", ty, value, read);
+    true
+}
+
+/// Generates a value of `T` from `data` and pushes its owned form into `owned` with the debug form of the value,
+/// so the owned value can outlive `data`. Returns false if `data` is not enough to generate a value
+pub fn assert_arbitrary_into_owned<'a, T>(data: &'a [u8], owned: &mut Vec<(String, T::Owned)>) -> bool
+    where T: ProtocolArbitrary<'a> + IntoOwned + std::fmt::Debug {
+    match T::arbitrary(&mut Unstructured::new(data)) {
+        Ok(value) => {
+            owned.push((format!("{:?}", value), value.into_owned()));
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Checks round trips of arbitrary values of every type. Every type should be generated at least once
+#[macro_export]
+macro_rules! assert_arbitrary_round_trips {
+    ($iterations: expr; $($ty: ty),* $(,)?) => {
+        $(
+            let mut generated = 0;
+            for seed in 0..$iterations {
+                let data = $crate::arbitrary::arbitrary_data(seed, 1024);
+                let mut buffer = std::vec::Vec::new();
+                if $crate::arbitrary::assert_arbitrary_round_trip::<$ty>(&data, &mut buffer) {
+                    generated += 1;
+                }
+            }
+            assert!(generated > 0, "No value of {} was generated", std::stringify!($ty));
+        )*
+    }
+}
+
+/// Checks owned conversions of arbitrary values of every type. Owned values are moved to another thread,
+/// so they outlive the data they were generated from. Every type should be generated at least once
+#[macro_export]
+macro_rules! assert_arbitrary_into_owned {
+    ($iterations: expr; $($ty: ty),* $(,)?) => {
+        $(
+            let mut owned = std::vec::Vec::new();
+            for seed in 0..$iterations {
+                let data = $crate::arbitrary::arbitrary_data(seed, 1024);
+                $crate::arbitrary::assert_arbitrary_into_owned::<$ty>(&data, &mut owned);
+            }
+            assert!(!owned.is_empty(), "No value of {} was generated", std::stringify!($ty));
+            let owned = std::thread::spawn(move || owned).join().unwrap();
+            for (expected, value) in &owned {
+                assert_eq!(expected, &format!("{:?}", value), "Owned {} differs", std::stringify!($ty));
+            }
+        )*
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! assert_round_trips {
+        ($($ty: ty),*) => {
+            crate::assert_arbitrary_round_trips!(64; $($ty),*);
+        }
+    }
+
+    #[test]
+    fn arbitrary_round_trip_test() {
+        arbitrary_delegate_types!(assert_round_trips);
+        assert_round_trips!(f32, f64, Uuid, Cow<str>, Identifier, Component);
+    }
+}
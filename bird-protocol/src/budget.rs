@@ -0,0 +1,151 @@
+use crate::*;
+
+/// The biggest allocation vanilla can cause (the biggest decompressed packet)
+pub const DEFAULT_MAX_ALLOCATION: usize = 8388608;
+
+/// The biggest frame length, so there can not be more non-zero sized elements
+pub const DEFAULT_MAX_ARRAY_LENGTH: usize = 2097151;
+
+/// Limits of allocations which can be caused by decoded bytes
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ProtocolBudget {
+    /// Bytes which still can be allocated
+    pub remaining_allocation: usize,
+    /// Elements which can be in one array
+    pub max_array_length: usize,
+}
+
+impl Default for ProtocolBudget {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_ALLOCATION, DEFAULT_MAX_ARRAY_LENGTH)
+    }
+}
+
+impl ProtocolBudget {
+    pub const fn new(max_allocation: usize, max_array_length: usize) -> Self {
+        Self { remaining_allocation: max_allocation, max_array_length }
+    }
+
+    /// Takes `count` elements with size of `element_size` from the budget
+    pub fn reserve(&mut self, count: usize, element_size: usize) -> ProtocolResult<()> {
+        if count > self.max_array_length {
+            return Err(ProtocolError::ArrayTooLong { length: count, limit: self.max_array_length });
+        }
+        let requested = count.saturating_mul(element_size);
+        if requested > self.remaining_allocation {
+            return Err(ProtocolError::AllocationBudgetExceeded { requested, remaining: self.remaining_allocation });
+        }
+        self.remaining_allocation -= requested;
+        Ok(())
+    }
+}
+
+/// Cursor which checks allocations caused by read arrays against the budget
+pub struct ProtocolBudgetCursor<C> {
+    pub cursor: C,
+    pub budget: ProtocolBudget,
+}
+
+impl<C> ProtocolBudgetCursor<C> {
+    pub const fn new(cursor: C, budget: ProtocolBudget) -> Self {
+        Self { cursor, budget }
+    }
+
+    pub fn into_inner(self) -> C {
+        self.cursor
+    }
+}
+
+impl<'a, C: ProtocolCursor<'a>> ProtocolCursor<'a> for ProtocolBudgetCursor<C> {
+    fn take_byte(&mut self) -> ProtocolResult<u8> {
+        self.cursor.take_byte()
+    }
+
+    fn take_bytes(&mut self, length: usize) -> ProtocolResult<&'a [u8]> {
+        self.cursor.take_bytes(length)
+    }
+
+    fn remaining_bytes(&self) -> usize {
+        self.cursor.remaining_bytes()
+    }
+
+    fn take_cursor(&self) -> Self {
+        Self::new(self.cursor.take_cursor(), self.budget)
+    }
+
+    fn has_bytes(&self, length: usize) -> bool {
+        self.cursor.has_bytes(length)
+    }
+
+    fn protocol_version(&self) -> Option<i32> {
+        self.cursor.protocol_version()
+    }
+
+    fn reserve_allocation(&mut self, count: usize, element_size: usize) -> ProtocolResult<()> {
+        self.cursor.reserve_allocation(count, element_size)?;
+        self.budget.reserve(count, element_size)
+    }
+
+    fn enter_field(&mut self, ty: &'static str, path: &'static str) {
+        self.cursor.enter_field(ty, path)
+    }
+
+    fn leave_field(&mut self, value: Option<&dyn std::fmt::Debug>) {
+        self.cursor.leave_field(value)
+    }
+}
+
+/// Converts the read length of an array into the count of bytes. Negative lengths and overflows are errors
+pub fn array_byte_length<L: TryInto<usize> + Copy + std::fmt::Display>(length: L, element_size: usize) -> ProtocolResult<usize> {
+    length.try_into()
+        .ok()
+        .and_then(|length: usize| length.checked_mul(element_size))
+        .ok_or_else(|| anyhow::Error::msg(format!("Bad array length {}", length)).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_test() {
+        let mut budget = ProtocolBudget::new(16, 4);
+        budget.reserve(2, 4).unwrap();
+        assert_eq!(budget.remaining_allocation, 8);
+        assert!(matches!(budget.reserve(5, 1), Err(ProtocolError::ArrayTooLong { length: 5, limit: 4 })));
+        assert!(matches!(
+            budget.reserve(3, 4),
+            Err(ProtocolError::AllocationBudgetExceeded { requested: 12, remaining: 8 })
+        ));
+        assert!(budget.reserve(usize::MAX, usize::MAX).is_err());
+        assert_eq!(array_byte_length(3i32, 8).unwrap(), 24);
+        assert!(array_byte_length(-1i32, 8).is_err());
+        assert!(array_byte_length(u64::MAX, 8).is_err());
+    }
+
+    #[test]
+    fn budget_cursor_test() {
+        // VarInt length of 3 and three bytes
+        let bytes = [3u8, 1, 2, 3];
+        let mut cursor = ProtocolBudgetCursor::new(bytes.as_slice(), ProtocolBudget::new(2, 16));
+        let result: ProtocolResult<Vec<u8>> = LengthProvidedArray::<i32, VarInt, u8, u8>::read_variant(&mut cursor);
+        assert!(matches!(result, Err(ProtocolError::AllocationBudgetExceeded { requested: 3, remaining: 2 })));
+        let mut cursor = ProtocolBudgetCursor::new(bytes.as_slice(), ProtocolBudget::default());
+        let result: Vec<u8> = LengthProvidedArray::<i32, VarInt, u8, u8>::read_variant(&mut cursor).unwrap();
+        assert_eq!(result, vec![1, 2, 3]);
+        assert_eq!(cursor.budget.remaining_allocation, DEFAULT_MAX_ALLOCATION - 3);
+        let mut cursor = ProtocolBudgetCursor::new(bytes.as_slice(), ProtocolBudget::new(16, 2));
+        let result: ProtocolResult<Vec<u8>> = LengthProvidedRawArray::<i32, VarInt, u8, u8>::read_variant(&mut cursor);
+        assert!(matches!(result, Err(ProtocolError::ArrayTooLong { length: 3, limit: 2 })));
+    }
+
+    #[test]
+    fn default_reserve_allocation_test() {
+        let mut cursor = [1u8, 2].as_slice();
+        cursor.reserve_allocation(2, 1024).unwrap();
+        assert!(matches!(cursor.reserve_allocation(3, 1), Err(ProtocolError::End)));
+        let mut cursor = ProtocolBudgetCursor::new([1u8, 2].as_slice(), ProtocolBudget::default());
+        assert!(matches!(cursor.reserve_allocation(3, 1), Err(ProtocolError::End)));
+        assert_eq!(cursor.budget.remaining_allocation, DEFAULT_MAX_ALLOCATION);
+    }
+}
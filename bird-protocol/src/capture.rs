@@ -0,0 +1,311 @@
+//! Capture file of the frames of a connection.
+//!
+//! The file starts with [`CAPTURE_MAGIC`], the version of the format (u8) and the protocol version
+//! of the connection (bool and i32). Every record is a timestamp in microseconds since the unix epoch (u64),
+//! bound (u8), state (u8), compressed flag (bool), payload length (u32) and the payload.
+//! All numbers are big endian as in the protocol
+use std::borrow::Cow;
+use std::io::{Read, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::*;
+use crate::frame::ProtocolFrameResult;
+use crate::version::ProtocolVersionedCursor;
+#[cfg(feature = "compression")]
+use crate::compression::ProtocolCompression;
+
+pub const CAPTURE_MAGIC: [u8; 4] = *b"BPCF";
+
+pub const CAPTURE_FORMAT_VERSION: u8 = 1;
+
+/// Length of a record without the payload
+const CAPTURE_RECORD_HEADER_LENGTH: usize = 15;
+
+/// Frame of a connection
+#[derive(Clone, PartialEq, Debug)]
+pub struct ProtocolCaptureRecord {
+    /// Microseconds since the unix epoch
+    pub timestamp: u64,
+    /// Client bound frames were sent to the client and server bound frames were sent to the server
+    pub bound: ProtocolPacketBound,
+    pub state: ProtocolPacketState,
+    /// Whether the payload is in the compressed packet format
+    pub compressed: bool,
+    /// Payload of the frame as it was sent, but decrypted
+    pub payload: Vec<u8>,
+}
+
+impl ProtocolCaptureRecord {
+    /// Record with the current time
+    pub fn new(bound: ProtocolPacketBound, state: ProtocolPacketState, compressed: bool, payload: Vec<u8>) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_micros() as u64)
+            .unwrap_or(0);
+        Self { timestamp, bound, state, compressed, payload }
+    }
+
+    /// Packet id and packet body
+    #[cfg(feature = "compression")]
+    pub fn packet_payload(&self) -> ProtocolResult<Cow<'_, [u8]>> {
+        match self.compressed {
+            true => ProtocolCompression::new(0).decompress(&self.payload),
+            false => Ok(Cow::Borrowed(&self.payload)),
+        }
+    }
+
+    /// Packet id and packet body
+    #[cfg(not(feature = "compression"))]
+    pub fn packet_payload(&self) -> ProtocolResult<Cow<'_, [u8]>> {
+        match self.compressed {
+            true => Err(ProtocolError::Any(anyhow::Error::msg("Compressed records can not be read without compression feature"))),
+            false => Ok(Cow::Borrowed(&self.payload)),
+        }
+    }
+
+    /// Reads the packet of the registry. The record should be decompressed
+    pub fn read_packet<'a, R: ProtocolPacketRegistry<'a>>(&'a self, version: Option<i32>) -> ProtocolResult<R> {
+        if self.compressed {
+            return Err(ProtocolError::Any(anyhow::Error::msg("Compressed record should be decompressed before reading")));
+        }
+        if self.state != R::STATE || self.bound != R::BOUND {
+            return Err(ProtocolError::Any(anyhow::Error::msg(format!(
+                "Record with state {:?} and bound {:?} is not a packet with state {:?} and bound {:?}",
+                self.state, self.bound, R::STATE, R::BOUND,
+            ))));
+        }
+        let mut cursor = self.payload.as_slice();
+        match version {
+            Some(version) => R::read_with_id(&mut ProtocolVersionedCursor::new(cursor, version)),
+            None => R::read_with_id(&mut cursor),
+        }
+    }
+
+    /// Replaces the compressed payload with the packet id and the packet body
+    pub fn decompress(&mut self) -> ProtocolResult<()> {
+        if self.compressed {
+            self.payload = self.packet_payload()?.into_owned();
+            self.compressed = false;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the header of the capture and then records
+pub struct ProtocolCaptureWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> ProtocolCaptureWriter<W> {
+    pub fn new(mut writer: W, protocol_version: Option<i32>) -> ProtocolFrameResult<Self> {
+        let mut header = Vec::new();
+        header.extend_from_slice(&CAPTURE_MAGIC);
+        CAPTURE_FORMAT_VERSION.write(&mut header).map_err(ProtocolError::from)?;
+        // protocol version is always written with the value, so the header has constant length
+        protocol_version.is_some().write(&mut header).map_err(ProtocolError::from)?;
+        protocol_version.unwrap_or(0).write(&mut header).map_err(ProtocolError::from)?;
+        writer.write_all(&header)?;
+        Ok(Self { writer })
+    }
+
+    pub fn write_record(&mut self, record: &ProtocolCaptureRecord) -> ProtocolFrameResult<()> {
+        let payload_length = u32::try_from(record.payload.len())
+            .map_err(|_| anyhow::Error::msg("Too long payload"))
+            .map_err(ProtocolError::from)?;
+        let mut header = Vec::with_capacity(CAPTURE_RECORD_HEADER_LENGTH);
+        record.timestamp.write(&mut header).map_err(ProtocolError::from)?;
+        (record.bound as u8).write(&mut header).map_err(ProtocolError::from)?;
+        (record.state as u8).write(&mut header).map_err(ProtocolError::from)?;
+        record.compressed.write(&mut header).map_err(ProtocolError::from)?;
+        payload_length.write(&mut header).map_err(ProtocolError::from)?;
+        self.writer.write_all(&header)?;
+        self.writer.write_all(&record.payload)?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> ProtocolFrameResult<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads the header of the capture and then iterates over its records
+pub struct ProtocolCaptureReader<R: Read> {
+    reader: R,
+    protocol_version: Option<i32>,
+}
+
+impl<R: Read> ProtocolCaptureReader<R> {
+    pub fn new(mut reader: R) -> ProtocolFrameResult<Self> {
+        let mut header = [0u8; CAPTURE_MAGIC.len() + 6];
+        reader.read_exact(&mut header)?;
+        if header[..CAPTURE_MAGIC.len()] != CAPTURE_MAGIC {
+            return Err(ProtocolError::Any(anyhow::Error::msg("Not a capture file")).into());
+        }
+        let mut cursor = &header[CAPTURE_MAGIC.len()..];
+        let format_version = u8::read(&mut cursor)?;
+        if format_version != CAPTURE_FORMAT_VERSION {
+            return Err(ProtocolError::Any(anyhow::Error::msg(format!("Unsupported capture format version {}", format_version))).into());
+        }
+        let has_protocol_version = bool::read(&mut cursor)?;
+        let protocol_version = i32::read(&mut cursor)?;
+        let protocol_version = has_protocol_version.then_some(protocol_version);
+        Ok(Self { reader, protocol_version })
+    }
+
+    /// Protocol version of the connection
+    pub fn protocol_version(&self) -> Option<i32> {
+        self.protocol_version
+    }
+
+    pub fn read_record(&mut self) -> ProtocolFrameResult<Option<ProtocolCaptureRecord>> {
+        let mut header = [0u8; CAPTURE_RECORD_HEADER_LENGTH];
+        // the end of the file is allowed only between records
+        loop {
+            match self.reader.read(&mut header[..1]) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        self.reader.read_exact(&mut header[1..])?;
+        let mut cursor = header.as_slice();
+        let timestamp = u64::read(&mut cursor)?;
+        let bound = match u8::read(&mut cursor)? {
+            0 => ProtocolPacketBound::Client,
+            1 => ProtocolPacketBound::Server,
+            bound => return Err(ProtocolError::BadEnumKey { ty: "ProtocolPacketBound", key: bound.to_string() }.into()),
+        };
+        let state = match u8::read(&mut cursor)? {
+            0 => ProtocolPacketState::Handshake,
+            1 => ProtocolPacketState::Status,
+            2 => ProtocolPacketState::Login,
+            3 => ProtocolPacketState::Play,
+            state => return Err(ProtocolError::BadEnumKey { ty: "ProtocolPacketState", key: state.to_string() }.into()),
+        };
+        let compressed = bool::read(&mut cursor)?;
+        let payload_length = u32::read(&mut cursor)? as usize;
+        // the payload grows while it is read, so a broken length does not allocate memory for nothing
+        let mut payload = Vec::new();
+        (&mut self.reader).take(payload_length as u64).read_to_end(&mut payload)?;
+        if payload.len() != payload_length {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+        Ok(Some(ProtocolCaptureRecord { timestamp, bound, state, compressed, payload }))
+    }
+}
+
+impl<R: Read> Iterator for ProtocolCaptureReader<R> {
+    type Item = ProtocolFrameResult<ProtocolCaptureRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+/// Records the frames of a connection while they are decoded and encoded
+pub struct ProtocolCapture {
+    writer: ProtocolCaptureWriter<Box<dyn Write + Send>>,
+    /// Should be changed together with the state of the connection, otherwise records are labeled with a wrong state.
+    /// `ProtocolTokioCodec::set_state` changes it for a codec
+    pub state: ProtocolPacketState,
+    /// Bound of the decoded frames. Encoded frames have the opposite bound
+    pub bound: ProtocolPacketBound,
+}
+
+impl ProtocolCapture {
+    pub fn new(writer: ProtocolCaptureWriter<Box<dyn Write + Send>>, bound: ProtocolPacketBound) -> Self {
+        Self { writer, state: ProtocolPacketState::Handshake, bound }
+    }
+
+    pub fn record_decoded(&mut self, compressed: bool, payload: &[u8]) -> ProtocolFrameResult<()> {
+        let record = ProtocolCaptureRecord::new(self.bound, self.state, compressed, payload.to_vec());
+        self.writer.write_record(&record)
+    }
+
+    pub fn record_encoded(&mut self, compressed: bool, payload: &[u8]) -> ProtocolFrameResult<()> {
+        let bound = match self.bound {
+            ProtocolPacketBound::Client => ProtocolPacketBound::Server,
+            ProtocolPacketBound::Server => ProtocolPacketBound::Client,
+        };
+        let record = ProtocolCaptureRecord::new(bound, self.state, compressed, payload.to_vec());
+        self.writer.write_record(&record)
+    }
+
+    pub fn flush(&mut self) -> ProtocolFrameResult<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_writer(self) -> ProtocolCaptureWriter<Box<dyn Write + Send>> {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+    use crate::frame::ProtocolFrameError;
+    use super::*;
+
+    #[test]
+    fn capture_round_trip_test() {
+        let records = vec![
+            ProtocolCaptureRecord::new(ProtocolPacketBound::Server, ProtocolPacketState::Handshake, false, vec![0, 1, 2]),
+            ProtocolCaptureRecord::new(ProtocolPacketBound::Client, ProtocolPacketState::Play, true, vec![9; 300]),
+        ];
+        let mut writer = ProtocolCaptureWriter::new(Vec::new(), Some(761)).unwrap();
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+        let bytes = writer.into_inner();
+        let reader = ProtocolCaptureReader::new(bytes.as_slice()).unwrap();
+        assert_eq!(reader.protocol_version(), Some(761));
+        assert_eq!(reader.collect::<ProtocolFrameResult<Vec<_>>>().unwrap(), records);
+        let mut reader = ProtocolCaptureReader::new(&bytes[..bytes.len() - 1]).unwrap();
+        assert_eq!(reader.next().unwrap().unwrap(), records[0]);
+        assert!(matches!(reader.next(), Some(Err(ProtocolFrameError::Io(err))) if err.kind() == ErrorKind::UnexpectedEof));
+        assert!(ProtocolCaptureReader::new(&bytes[1..]).is_err());
+    }
+
+    #[test]
+    fn capture_bad_length_test() {
+        let mut bytes = ProtocolCaptureWriter::new(Vec::new(), None).unwrap().into_inner();
+        // record which claims a payload of 4 GiB, but contains only two bytes of it
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.extend_from_slice(&[ProtocolPacketBound::Client as u8, ProtocolPacketState::Play as u8, 0]);
+        bytes.extend_from_slice(&u32::MAX.to_be_bytes());
+        bytes.extend_from_slice(&[1, 2]);
+        let mut reader = ProtocolCaptureReader::new(bytes.as_slice()).unwrap();
+        assert!(matches!(reader.next(), Some(Err(ProtocolFrameError::Io(err))) if err.kind() == ErrorKind::UnexpectedEof));
+    }
+
+    /// Reader which is interrupted before every read
+    struct InterruptedReader<'a> {
+        bytes: &'a [u8],
+        interrupted: bool,
+    }
+
+    impl<'a> Read for InterruptedReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.interrupted = !self.interrupted;
+            match self.interrupted {
+                true => Err(ErrorKind::Interrupted.into()),
+                false => self.bytes.read(buf),
+            }
+        }
+    }
+
+    #[test]
+    fn capture_interrupted_test() {
+        let record = ProtocolCaptureRecord::new(ProtocolPacketBound::Server, ProtocolPacketState::Status, false, vec![1]);
+        let mut writer = ProtocolCaptureWriter::new(Vec::new(), None).unwrap();
+        writer.write_record(&record).unwrap();
+        let bytes = writer.into_inner();
+        let reader = ProtocolCaptureReader::new(InterruptedReader { bytes: bytes.as_slice(), interrupted: false }).unwrap();
+        assert_eq!(reader.collect::<ProtocolFrameResult<Vec<_>>>().unwrap(), vec![record]);
+    }
+}
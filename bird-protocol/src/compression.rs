@@ -0,0 +1,148 @@
+use std::borrow::Cow;
+use std::io::{Read, Write};
+
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+
+use crate::*;
+use crate::frame::{ProtocolFrameCodec, ProtocolFrameResult};
+
+/// The biggest decompressed packet length vanilla accepts
+pub const DEFAULT_MAX_DECOMPRESSED_LENGTH: usize = 8388608;
+
+/// Compressed packet format which is turned on by the set compression packet.
+/// Each connection should keep its own value, because the threshold is sent per connection
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ProtocolCompression {
+    pub threshold: usize,
+    pub max_decompressed_length: usize,
+    pub level: u32,
+}
+
+impl ProtocolCompression {
+    pub const fn new(threshold: usize) -> Self {
+        Self {
+            threshold,
+            max_decompressed_length: DEFAULT_MAX_DECOMPRESSED_LENGTH,
+            level: 6,
+        }
+    }
+
+    /// Negative threshold (the same as in the set compression packet) disables compression
+    pub fn from_threshold(threshold: i32) -> Option<Self> {
+        usize::try_from(threshold).ok().map(Self::new)
+    }
+
+    /// Writes data length and the payload which is compressed only if it is not smaller than the threshold
+    pub fn compress<W: ProtocolWriter>(&self, payload: &[u8], writer: &mut W) -> anyhow::Result<()> {
+        if payload.len() < self.threshold {
+            VarInt::write_variant(&0, writer)?;
+            writer.write_bytes(payload);
+            return Ok(());
+        }
+        VarInt::write_variant(&(payload.len() as i32), writer)?;
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::new(self.level));
+        encoder.write_all(payload)?;
+        writer.write_vec_bytes(encoder.finish()?);
+        Ok(())
+    }
+
+    /// Reads data length and returns the decompressed payload.
+    /// # Features
+    /// Data length is checked before decompression and the decompressor never produces more bytes than it
+    pub fn decompress<'a>(&self, bytes: &'a [u8]) -> ProtocolResult<Cow<'a, [u8]>> {
+        let mut cursor = bytes;
+        let data_length: i32 = VarInt::read_variant(&mut cursor)?;
+        if data_length == 0 {
+            return Ok(Cow::Borrowed(cursor));
+        }
+        let data_length = usize::try_from(data_length)
+            .map_err(|_| anyhow::Error::msg("Data length is negative"))?;
+        if data_length < self.threshold {
+            return Err(anyhow::Error::msg(format!(
+                "Badly compressed packet: size of {} is below threshold of {}", data_length, self.threshold
+            )).into());
+        }
+        if data_length > self.max_decompressed_length {
+            return Err(anyhow::Error::msg(format!(
+                "Badly compressed packet: size of {} is larger than maximum of {}", data_length, self.max_decompressed_length
+            )).into());
+        }
+        let mut decompressed = Vec::with_capacity(data_length);
+        ZlibDecoder::new(cursor)
+            .take(data_length as u64 + 1)
+            .read_to_end(&mut decompressed)
+            .map_err(anyhow::Error::from)?;
+        match decompressed.len() == data_length {
+            true => Ok(Cow::Owned(decompressed)),
+            false => Err(anyhow::Error::msg(format!(
+                "Badly compressed packet: expected size of {}, got {}", data_length, decompressed.len()
+            )).into()),
+        }
+    }
+}
+
+impl ProtocolFrameCodec {
+    /// Reads the next frame and decompresses its payload if compression is enabled
+    pub fn read_compressed_frame<'a, C: ProtocolCursor<'a>>(
+        &self, compression: Option<&ProtocolCompression>, cursor: &mut C,
+    ) -> ProtocolFrameResult<Cow<'a, [u8]>> {
+        let payload = self.read_frame(cursor)?;
+        Ok(match compression {
+            Some(compression) => compression.decompress(payload)?,
+            None => Cow::Borrowed(payload),
+        })
+    }
+
+    /// Compresses the payload if compression is enabled and writes it as a frame
+    pub fn write_compressed_frame<W: ProtocolWriter>(
+        &self, compression: Option<&ProtocolCompression>, payload: &[u8], writer: &mut W,
+    ) -> ProtocolFrameResult<()> {
+        match compression {
+            Some(compression) => {
+                let mut compressed = Vec::new();
+                compression.compress(payload, &mut compressed).map_err(ProtocolError::from)?;
+                self.write_frame(&compressed, writer)
+            }
+            None => self.write_frame(payload, writer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compression_round_trip_test() {
+        let codec = ProtocolFrameCodec::default();
+        let compression = ProtocolCompression::new(64);
+        let small = [1u8, 2, 3];
+        let big = vec![5u8; 4096];
+        let mut stream = Vec::new();
+        codec.write_compressed_frame(Some(&compression), &small, &mut stream).unwrap();
+        codec.write_compressed_frame(Some(&compression), &big, &mut stream).unwrap();
+        assert!(stream.len() < big.len());
+        let mut cursor = stream.as_slice();
+        let first = codec.read_compressed_frame(Some(&compression), &mut cursor).unwrap();
+        assert!(matches!(first, Cow::Borrowed(_)));
+        assert_eq!(first.as_ref(), small.as_slice());
+        let second = codec.read_compressed_frame(Some(&compression), &mut cursor).unwrap();
+        assert_eq!(second.as_ref(), big.as_slice());
+    }
+
+    #[test]
+    fn decompression_bomb_test() {
+        let mut compression = ProtocolCompression::new(16);
+        let mut bytes = Vec::new();
+        compression.compress(&vec![0u8; 1024], &mut bytes).unwrap();
+        compression.max_decompressed_length = 512;
+        assert!(compression.decompress(&bytes).is_err());
+        // data length lies about the real size
+        let mut lying = Vec::new();
+        VarInt::write_variant(&32, &mut lying).unwrap();
+        lying.extend_from_slice(&bytes[2..]);
+        assert!(ProtocolCompression::new(16).decompress(&lying).is_err());
+    }
+}
@@ -0,0 +1,310 @@
+use std::fmt::{Debug, Display, Formatter, Write};
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use crate::*;
+
+/// The longest printed value of a field. Longer values are cut
+pub const MAX_DISSECT_VALUE_LENGTH: usize = 96;
+
+/// Count of bytes which are shown next to a field in the annotated dump
+const DISSECT_PREVIEW_BYTES: usize = 8;
+
+/// Field which was read from the dissected bytes
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DissectNode {
+    /// Type which contains the field
+    pub ty: &'static str,
+    pub path: &'static str,
+    pub range: Range<usize>,
+    /// Decoded value if the type of the field is printable
+    pub value: Option<String>,
+    pub children: Vec<DissectNode>,
+    /// False if reading of the field failed
+    pub complete: bool,
+}
+
+/// Cursor which records every field read by derived readers
+pub struct DissectCursor<'a, C> {
+    pub cursor: C,
+    version: Option<i32>,
+    length: usize,
+    open: Vec<DissectNode>,
+    nodes: Vec<DissectNode>,
+    _marker: PhantomData<&'a ()>,
+}
+
+impl<'a, C: ProtocolCursor<'a>> DissectCursor<'a, C> {
+    pub fn new(cursor: C, version: Option<i32>) -> Self {
+        Self {
+            length: cursor.remaining_bytes(),
+            cursor,
+            version,
+            open: Vec::new(),
+            nodes: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Count of the read bytes
+    pub fn position(&self) -> usize {
+        self.length - self.cursor.remaining_bytes()
+    }
+
+    /// Returns the recorded fields. Fields which are still being read are incomplete
+    pub fn finish(mut self) -> Vec<DissectNode> {
+        while !self.open.is_empty() {
+            self.close_field(None, false);
+        }
+        self.nodes
+    }
+
+    fn close_field(&mut self, value: Option<&dyn Debug>, complete: bool) {
+        let end = self.position();
+        if let Some(mut node) = self.open.pop() {
+            node.range.end = end;
+            node.value = value.map(|value| {
+                let mut value = format!("{:?}", value);
+                if value.len() > MAX_DISSECT_VALUE_LENGTH {
+                    let mut cut = MAX_DISSECT_VALUE_LENGTH;
+                    while !value.is_char_boundary(cut) {
+                        cut -= 1;
+                    }
+                    value.truncate(cut);
+                    value.push_str("...");
+                }
+                value
+            });
+            node.complete = complete;
+            match self.open.last_mut() {
+                Some(parent) => parent.children.push(node),
+                None => self.nodes.push(node),
+            }
+        }
+    }
+}
+
+impl<'a, C: ProtocolCursor<'a>> ProtocolCursor<'a> for DissectCursor<'a, C> {
+    fn take_byte(&mut self) -> ProtocolResult<u8> {
+        self.cursor.take_byte()
+    }
+
+    fn take_bytes(&mut self, length: usize) -> ProtocolResult<&'a [u8]> {
+        self.cursor.take_bytes(length)
+    }
+
+    fn remaining_bytes(&self) -> usize {
+        self.cursor.remaining_bytes()
+    }
+
+    /// Fields read from the took cursor are not recorded
+    fn take_cursor(&self) -> Self {
+        Self {
+            cursor: self.cursor.take_cursor(),
+            version: self.version,
+            length: self.length,
+            open: Vec::new(),
+            nodes: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    fn has_bytes(&self, length: usize) -> bool {
+        self.cursor.has_bytes(length)
+    }
+
+    fn protocol_version(&self) -> Option<i32> {
+        self.version.or_else(|| self.cursor.protocol_version())
+    }
+
+    fn reserve_allocation(&mut self, count: usize, element_size: usize) -> ProtocolResult<()> {
+        self.cursor.reserve_allocation(count, element_size)
+    }
+
+    fn enter_field(&mut self, ty: &'static str, path: &'static str) {
+        let start = self.position();
+        self.open.push(DissectNode {
+            ty,
+            path,
+            range: start..start,
+            value: None,
+            children: Vec::new(),
+            complete: false,
+        })
+    }
+
+    fn leave_field(&mut self, value: Option<&dyn Debug>) {
+        self.close_field(value, true)
+    }
+}
+
+/// Explanation of bytes: which fields were read from which bytes
+pub struct Dissection<'a> {
+    pub bytes: &'a [u8],
+    pub nodes: Vec<DissectNode>,
+    /// Count of the read bytes
+    pub read: usize,
+    pub error: Option<ProtocolError>,
+}
+
+impl<'a> Dissection<'a> {
+    /// Reads `bytes` with `read` and records the fields read by derived readers
+    pub fn new<T, F>(bytes: &'a [u8], version: Option<i32>, read: F) -> Self
+        where F: FnOnce(&mut DissectCursor<'a, &'a [u8]>) -> ProtocolResult<T> {
+        let mut cursor = DissectCursor::new(bytes, version);
+        let error = read(&mut cursor).err();
+        let read = cursor.position();
+        Self { bytes, nodes: cursor.finish(), read, error }
+    }
+
+    /// Dissects a frame which starts with the packet id
+    pub fn of_packet<R: ProtocolPacketRegistry<'a>>(frame: &'a [u8], version: Option<i32>) -> Self {
+        Self::new(frame, version, |cursor| {
+            let id = read_packet_id(cursor)?;
+            R::read_by_id(id, cursor)
+        })
+    }
+
+    fn write_node(f: &mut Formatter<'_>, bytes: &[u8], node: &DissectNode, depth: usize) -> std::fmt::Result {
+        let node_bytes = &bytes[node.range.clone()];
+        let mut preview = String::new();
+        for byte in node_bytes.iter().take(DISSECT_PREVIEW_BYTES) {
+            write!(preview, "{:02x} ", byte)?;
+        }
+        if node_bytes.len() > DISSECT_PREVIEW_BYTES {
+            preview.push_str(".. ");
+        }
+        write!(
+            f, "{:04x}..{:04x} {:27}{:indent$}{}.{}",
+            node.range.start, node.range.end, preview, "", node.ty, node.path, indent = depth * 2,
+        )?;
+        match (&node.value, node.complete) {
+            (_, false) => writeln!(f, " <failed>")?,
+            (Some(value), true) => writeln!(f, " = {}", value)?,
+            _ => writeln!(f)?,
+        }
+        for child in &node.children {
+            Self::write_node(f, bytes, child, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Display for Dissection<'a> {
+    /// Hexdump of the bytes followed by the tree of fields
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&hexdump(self.bytes))?;
+        for node in &self.nodes {
+            Self::write_node(f, self.bytes, node, 0)?;
+        }
+        if self.read < self.bytes.len() {
+            writeln!(f, "{:04x}..{:04x} {} bytes are not read", self.read, self.bytes.len(), self.bytes.len() - self.read)?;
+        }
+        if let Some(ref error) = self.error {
+            writeln!(f, "error: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the packet id of a frame as a recorded field
+pub fn read_packet_id<'a, C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<i32> {
+    cursor.enter_field("packet", "id");
+    let id: i32 = VarInt::read_variant(cursor)?;
+    cursor.leave_field(Some(&id));
+    Ok(id)
+}
+
+/// Lines of 16 bytes with their offset and ascii characters
+pub fn hexdump(bytes: &[u8]) -> String {
+    let mut res = String::new();
+    for (index, line) in bytes.chunks(16).enumerate() {
+        let _ = write!(res, "{:04x} ", index * 16);
+        for byte in line {
+            let _ = write!(res, " {:02x}", byte);
+        }
+        res.push_str(&" ".repeat((16 - line.len()) * 3 + 2));
+        res.push('|');
+        res.extend(line.iter().map(|&byte| match byte.is_ascii_graphic() || byte == b' ' {
+            true => byte as char,
+            false => '.',
+        }));
+        res.push_str("|\n");
+    }
+    res
+}
+
+// Derived readers pass the read values through `(&DissectValue(&value)).dissect_debug()`,
+// which is resolved to `DissectDebug` if the value is printable and to `DissectNotDebug` otherwise
+
+#[doc(hidden)]
+pub struct DissectValue<'b, T>(pub &'b T);
+
+#[doc(hidden)]
+pub trait DissectDebug {
+    fn dissect_debug(&self) -> Option<&dyn Debug>;
+}
+
+impl<'b, T: Debug> DissectDebug for DissectValue<'b, T> {
+    #[inline]
+    fn dissect_debug(&self) -> Option<&dyn Debug> {
+        Some(self.0)
+    }
+}
+
+#[doc(hidden)]
+pub trait DissectNotDebug {
+    fn dissect_debug(&self) -> Option<&dyn Debug>;
+}
+
+impl<'b, T> DissectNotDebug for &DissectValue<'b, T> {
+    #[inline]
+    fn dissect_debug(&self) -> Option<&dyn Debug> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hexdump_test() {
+        assert_eq!(
+            hexdump(b"0123456789abcdef\x00\xFF"),
+            "0000  30 31 32 33 34 35 36 37 38 39 61 62 63 64 65 66  |0123456789abcdef|\n\
+             0010  00 ff                                            |..|\n",
+        );
+    }
+
+    #[test]
+    fn dissect_cursor_test() {
+        let bytes = [1, 0xAC, 0x02];
+        let dissection = Dissection::new(&bytes, None, |cursor| {
+            cursor.enter_field("Test", "first");
+            let first = u8::read(cursor)?;
+            cursor.enter_field("Test", "second");
+            let second: i32 = VarInt::read_variant(cursor)?;
+            cursor.leave_field(Some(&second));
+            cursor.leave_field(Some(&first));
+            u8::read(cursor)
+        });
+        assert_eq!(dissection.read, 3);
+        assert!(matches!(dissection.error, Some(ProtocolError::End)));
+        assert_eq!(dissection.nodes, vec![DissectNode {
+            ty: "Test",
+            path: "first",
+            range: 0..3,
+            value: Some("1".into()),
+            children: vec![DissectNode {
+                ty: "Test",
+                path: "second",
+                range: 1..3,
+                value: Some("300".into()),
+                children: vec![],
+                complete: true,
+            }],
+            complete: true,
+        }]);
+    }
+}
@@ -0,0 +1,247 @@
+use std::io;
+
+use aes::Aes128;
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use aes::cipher::generic_array::GenericArray;
+use rsa::{PaddingScheme, PublicKey, RsaPrivateKey, RsaPublicKey};
+use rsa::pkcs8::{DecodePublicKey, EncodePublicKey};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::*;
+
+/// Length of the shared secret (AES-128 key)
+pub const SHARED_SECRET_LENGTH: usize = 16;
+
+pub const DEFAULT_KEY_BITS: usize = 1024;
+
+pub const DEFAULT_VERIFY_TOKEN_LENGTH: usize = 4;
+
+/// RSA keypair of the server which is used in the encryption handshake
+pub struct ProtocolKeyPair {
+    private_key: RsaPrivateKey,
+    public_key_der: Vec<u8>,
+}
+
+impl ProtocolKeyPair {
+    pub fn generate() -> anyhow::Result<Self> {
+        Self::from_private_key(RsaPrivateKey::new(&mut rand::thread_rng(), DEFAULT_KEY_BITS)?)
+    }
+
+    pub fn from_private_key(private_key: RsaPrivateKey) -> anyhow::Result<Self> {
+        let public_key_der = RsaPublicKey::from(&private_key)
+            .to_public_key_der()?
+            .as_ref()
+            .to_vec();
+        Ok(Self { private_key, public_key_der })
+    }
+
+    /// DER encoded public key which is sent in the encryption request
+    pub fn public_key_der(&self) -> &[u8] {
+        &self.public_key_der
+    }
+
+    pub fn decrypt(&self, bytes: &[u8]) -> ProtocolResult<Vec<u8>> {
+        self.private_key
+            .decrypt(PaddingScheme::new_pkcs1v15_encrypt(), bytes)
+            .map_err(|err| anyhow::Error::msg(format!("Failed to decrypt: {}", err)).into())
+    }
+
+    pub fn decrypt_shared_secret(&self, encrypted: &[u8]) -> ProtocolResult<[u8; SHARED_SECRET_LENGTH]> {
+        self.decrypt(encrypted)?
+            .as_slice()
+            .try_into()
+            .map_err(|_| anyhow::Error::msg("Shared secret must be 16 bytes long").into())
+    }
+
+    /// Decrypts verify token sent by the client and compares it with the one sent in the encryption request
+    pub fn check_verify_token(&self, encrypted: &[u8], verify_token: &[u8]) -> ProtocolResult<()> {
+        match self.decrypt(encrypted)?.as_slice() == verify_token {
+            true => Ok(()),
+            false => Err(anyhow::Error::msg("Verify token does not match").into()),
+        }
+    }
+}
+
+pub fn generate_verify_token() -> [u8; DEFAULT_VERIFY_TOKEN_LENGTH] {
+    rand::random()
+}
+
+/// Checks signature of the verify token sent by clients with a chat signing key instead of the encrypted verify token
+pub fn check_salted_verify_token_signature(
+    player_public_key_der: &[u8], verify_token: &[u8], salt: i64, signature: &[u8],
+) -> ProtocolResult<()> {
+    let public_key = RsaPublicKey::from_public_key_der(player_public_key_der)
+        .map_err(|err| anyhow::Error::msg(format!("Bad player public key: {}", err)))?;
+    let mut hasher = Sha256::new();
+    hasher.update(verify_token);
+    hasher.update(salt.to_be_bytes());
+    public_key
+        .verify(PaddingScheme::new_pkcs1v15_sign::<Sha256>(), &hasher.finalize(), signature)
+        .map_err(|_| anyhow::Error::msg("Verify token signature does not match").into())
+}
+
+/// Server hash which is sent to the session server in the online mode
+pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key_der: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(server_id.as_bytes());
+    hasher.update(shared_secret);
+    hasher.update(public_key_der);
+    let mut hash: [u8; 20] = hasher.finalize().into();
+    let negative = hash[0] & 0x80 != 0;
+    if negative {
+        // two's complement
+        let mut carry = true;
+        for byte in hash.iter_mut().rev() {
+            *byte = !*byte;
+            if carry {
+                let (value, overflow) = byte.overflowing_add(1);
+                *byte = value;
+                carry = overflow;
+            }
+        }
+    }
+    let hex: String = hash.iter().map(|byte| format!("{:02x}", byte)).collect();
+    let hex = hex.trim_start_matches('0');
+    match negative {
+        true => format!("-{}", hex),
+        false => hex.to_string(),
+    }
+}
+
+/// AES-128-CFB8 encryptor of outgoing bytes. The shared secret is used as key and iv
+pub struct ProtocolEncryptor(cfb8::Encryptor<Aes128>);
+
+/// AES-128-CFB8 decryptor of incoming bytes. The shared secret is used as key and iv
+pub struct ProtocolDecryptor(cfb8::Decryptor<Aes128>);
+
+impl ProtocolEncryptor {
+    pub fn new(shared_secret: &[u8; SHARED_SECRET_LENGTH]) -> Self {
+        Self(cfb8::Encryptor::new(shared_secret.into(), shared_secret.into()))
+    }
+
+    pub fn encrypt(&mut self, bytes: &mut [u8]) {
+        for byte in bytes.chunks_exact_mut(1) {
+            self.0.encrypt_block_mut(GenericArray::from_mut_slice(byte));
+        }
+    }
+}
+
+impl ProtocolDecryptor {
+    pub fn new(shared_secret: &[u8; SHARED_SECRET_LENGTH]) -> Self {
+        Self(cfb8::Decryptor::new(shared_secret.into(), shared_secret.into()))
+    }
+
+    pub fn decrypt(&mut self, bytes: &mut [u8]) {
+        for byte in bytes.chunks_exact_mut(1) {
+            self.0.decrypt_block_mut(GenericArray::from_mut_slice(byte));
+        }
+    }
+}
+
+/// Creates the encryptor and the decryptor for one connection
+pub fn protocol_ciphers(shared_secret: &[u8; SHARED_SECRET_LENGTH]) -> (ProtocolEncryptor, ProtocolDecryptor) {
+    (ProtocolEncryptor::new(shared_secret), ProtocolDecryptor::new(shared_secret))
+}
+
+/// Decrypts everything that is read from the inner reader
+pub struct ProtocolDecryptReader<R> {
+    reader: R,
+    decryptor: ProtocolDecryptor,
+}
+
+impl<R> ProtocolDecryptReader<R> {
+    pub fn new(reader: R, decryptor: ProtocolDecryptor) -> Self {
+        Self { reader, decryptor }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: io::Read> io::Read for ProtocolDecryptReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let length = self.reader.read(buf)?;
+        self.decryptor.decrypt(&mut buf[..length]);
+        Ok(length)
+    }
+}
+
+/// Encrypts everything that is written to the inner writer
+pub struct ProtocolEncryptWriter<W> {
+    writer: W,
+    encryptor: ProtocolEncryptor,
+    buffer: Vec<u8>,
+}
+
+impl<W> ProtocolEncryptWriter<W> {
+    pub fn new(writer: W, encryptor: ProtocolEncryptor) -> Self {
+        Self { writer, encryptor, buffer: Vec::new() }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: io::Write> io::Write for ProtocolEncryptWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // cipher state is already advanced, so everything must be written
+        self.buffer.clear();
+        self.buffer.extend_from_slice(buf);
+        self.encryptor.encrypt(&mut self.buffer);
+        self.writer.write_all(&self.buffer)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl<W: ProtocolWriter> ProtocolWriter for ProtocolEncryptWriter<W> {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.buffer.clear();
+        self.buffer.extend_from_slice(bytes);
+        self.encryptor.encrypt(&mut self.buffer);
+        self.writer.write_bytes(&self.buffer)
+    }
+
+    fn protocol_version(&self) -> Option<i32> {
+        self.writer.protocol_version()
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.writer.reserve(additional)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use super::*;
+
+    #[test]
+    fn server_hash_test() {
+        // values from wiki.vg
+        assert_eq!(server_hash("Notch", &[], &[]), "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48");
+        assert_eq!(server_hash("jeb_", &[], &[]), "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1");
+        assert_eq!(server_hash("simon", &[], &[]), "88e16a1019277b15d58faf0541e11910eb756f6");
+    }
+
+    #[test]
+    fn cipher_stream_test() {
+        let secret = [7u8; SHARED_SECRET_LENGTH];
+        let (encryptor, decryptor) = protocol_ciphers(&secret);
+        let mut writer = ProtocolEncryptWriter::new(Vec::new(), encryptor);
+        writer.write_all(b"Hello, ").unwrap();
+        writer.write_all(b"world!").unwrap();
+        let encrypted = writer.into_inner();
+        assert_ne!(encrypted.as_slice(), b"Hello, world!");
+        let mut reader = ProtocolDecryptReader::new(encrypted.as_slice(), decryptor);
+        let mut decrypted = Vec::new();
+        reader.read_to_end(&mut decrypted).unwrap();
+        assert_eq!(decrypted.as_slice(), b"Hello, world!");
+    }
+}
@@ -0,0 +1,278 @@
+use crate::*;
+use crate::version::ProtocolVersionedWriter;
+
+/// The biggest frame length vanilla accepts (3 byte VarInt)
+pub const DEFAULT_MAX_FRAME_LENGTH: usize = 2097151;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ProtocolFrameError {
+    #[error("Incomplete frame, need at least {0} more bytes")]
+    Incomplete(usize),
+    #[error("Frame length {length} is bigger than maximum frame length {max_length}")]
+    TooLong {
+        length: usize,
+        max_length: usize,
+    },
+    #[error("Frame length is negative: {0}")]
+    NegativeLength(i32),
+    #[error("Written payload length {written} is not equal to the computed length {expected}")]
+    LengthMismatch {
+        expected: usize,
+        written: usize,
+    },
+    #[error("Protocol: {0}")]
+    Protocol(#[from] ProtocolError),
+    #[error("IO: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type ProtocolFrameResult<T> = Result<T, ProtocolFrameError>;
+
+/// Splits a byte stream into VarInt length-prefixed frames and writes them back.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ProtocolFrameCodec {
+    pub max_length: usize,
+}
+
+impl Default for ProtocolFrameCodec {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_FRAME_LENGTH)
+    }
+}
+
+impl ProtocolFrameCodec {
+    pub const fn new(max_length: usize) -> Self {
+        Self { max_length }
+    }
+
+    fn check_length(&self, length: usize) -> ProtocolFrameResult<()> {
+        match length > self.max_length {
+            true => Err(ProtocolFrameError::TooLong { length, max_length: self.max_length }),
+            false => Ok(()),
+        }
+    }
+
+    /// Reads the next frame and returns its payload (without the length prefix).
+    /// # Features
+    /// If the frame is not fully available, [`ProtocolFrameError::Incomplete`] is returned
+    /// and the cursor is left untouched, so the same call can be repeated after more bytes arrive
+    pub fn read_frame<'a, C: ProtocolCursor<'a>>(&self, cursor: &mut C) -> ProtocolFrameResult<&'a [u8]> {
+        let mut frame_cursor = cursor.take_cursor();
+        let length: i32 = match VarInt::read_variant(&mut frame_cursor) {
+            Ok(length) => length,
+            Err(ProtocolError::End) => return Err(ProtocolFrameError::Incomplete(1)),
+            Err(err) => return Err(err.into()),
+        };
+        let length = usize::try_from(length)
+            .map_err(|_| ProtocolFrameError::NegativeLength(length))?;
+        self.check_length(length)?;
+        let remaining = frame_cursor.remaining_bytes();
+        if remaining < length {
+            return Err(ProtocolFrameError::Incomplete(length - remaining));
+        }
+        let payload = frame_cursor.take_bytes(length)?;
+        *cursor = frame_cursor;
+        Ok(payload)
+    }
+
+    pub fn write_frame<W: ProtocolWriter>(&self, payload: &[u8], writer: &mut W) -> ProtocolFrameResult<()> {
+        self.check_length(payload.len())?;
+        VarInt::write_variant(&(payload.len() as i32), writer).map_err(ProtocolError::from)?;
+        writer.write_bytes(payload);
+        Ok(())
+    }
+
+    /// Writes a frame with the packet id and the packet body as payload.
+    /// The packet is written directly into the writer, because its length is computed beforehand.
+    /// If writing fails or the written length differs from the computed one, an error is returned
+    /// and the writer is rolled back if it supports [`ProtocolWriter::truncate`]
+    pub fn write_packet<P: ProtocolPacket + ProtocolWritable, W: ProtocolWriter>(
+        &self, packet: &P, writer: &mut W,
+    ) -> ProtocolFrameResult<()> {
+        let version = writer.protocol_version();
+        let length = packet_payload_len(packet, version);
+        self.check_length(length)?;
+        let start = writer.position();
+        let result = write_packet_with_length(packet, length, version, writer);
+        if let (Err(_), Some(start)) = (&result, start) {
+            writer.truncate(start);
+        }
+        result
+    }
+}
+
+fn write_packet_with_length<P: ProtocolPacket + ProtocolWritable, W: ProtocolWriter>(
+    packet: &P, length: usize, version: Option<i32>, writer: &mut W,
+) -> ProtocolFrameResult<()> {
+    writer.reserve(VarInt::encoded_len_variant(&(length as i32), version) + length);
+    VarInt::write_variant(&(length as i32), writer).map_err(ProtocolError::from)?;
+    let mut counter = ProtocolCountingWriter { writer, written: 0 };
+    VarInt::write_variant(&P::id_for_version(version), &mut counter).map_err(ProtocolError::from)?;
+    packet.write(&mut counter).map_err(ProtocolError::from)?;
+    match counter.written == length {
+        true => Ok(()),
+        false => Err(ProtocolFrameError::LengthMismatch { expected: length, written: counter.written }),
+    }
+}
+
+/// Passes bytes to the inner writer and counts them
+struct ProtocolCountingWriter<'w, W> {
+    writer: &'w mut W,
+    written: usize,
+}
+
+impl<'w, W: ProtocolWriter> ProtocolWriter for ProtocolCountingWriter<'w, W> {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.written += bytes.len();
+        self.writer.write_bytes(bytes)
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        self.written += 1;
+        self.writer.write_byte(byte)
+    }
+
+    fn write_vec_bytes(&mut self, bytes: Vec<u8>) {
+        self.written += bytes.len();
+        self.writer.write_vec_bytes(bytes)
+    }
+
+    fn protocol_version(&self) -> Option<i32> {
+        self.writer.protocol_version()
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.writer.reserve(additional)
+    }
+}
+
+/// Exact length of the packet id and the packet body as they are in the given protocol version
+pub fn packet_payload_len<P: ProtocolPacket + ProtocolWritable>(packet: &P, version: Option<i32>) -> usize {
+    VarInt::encoded_len_variant(&P::id_for_version(version), version) + packet.encoded_len_for_version(version)
+}
+
+/// Writes the packet id and the packet body as they are in the given protocol version
+pub fn packet_payload<P: ProtocolPacket + ProtocolWritable>(packet: &P, version: Option<i32>) -> ProtocolResult<Vec<u8>> {
+    let mut payload = Vec::with_capacity(packet_payload_len(packet, version));
+    VarInt::write_variant(&P::id_for_version(version), &mut payload)?;
+    Ok(match version {
+        Some(version) => {
+            let mut payload = ProtocolVersionedWriter::new(payload, version);
+            packet.write(&mut payload)?;
+            payload.into_inner()
+        }
+        None => {
+            packet.write(&mut payload)?;
+            payload
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_round_trip_test() {
+        let codec = ProtocolFrameCodec::default();
+        let first = [1u8, 2, 3];
+        let second = vec![7u8; 300];
+        let mut stream = Vec::new();
+        codec.write_frame(&first, &mut stream).unwrap();
+        codec.write_frame(&second, &mut stream).unwrap();
+        let mut cursor = stream.as_slice();
+        assert_eq!(codec.read_frame(&mut cursor).unwrap(), first.as_slice());
+        assert_eq!(codec.read_frame(&mut cursor).unwrap(), second.as_slice());
+        assert!(matches!(codec.read_frame(&mut cursor), Err(ProtocolFrameError::Incomplete(1))));
+    }
+
+    #[test]
+    fn frame_incomplete_test() {
+        let codec = ProtocolFrameCodec::default();
+        let mut stream = Vec::new();
+        codec.write_frame(&[0u8; 200], &mut stream).unwrap();
+        let mut cursor = &stream[..1];
+        assert!(matches!(codec.read_frame(&mut cursor), Err(ProtocolFrameError::Incomplete(1))));
+        assert_eq!(cursor.len(), 1);
+        let mut cursor = &stream[..52];
+        assert!(matches!(codec.read_frame(&mut cursor), Err(ProtocolFrameError::Incomplete(150))));
+        assert_eq!(cursor.len(), 52);
+    }
+
+    #[test]
+    fn frame_max_length_test() {
+        let codec = ProtocolFrameCodec::new(16);
+        let mut stream = Vec::new();
+        assert!(matches!(
+            codec.write_frame(&[0u8; 17], &mut stream),
+            Err(ProtocolFrameError::TooLong { length: 17, max_length: 16 })
+        ));
+        ProtocolFrameCodec::default().write_frame(&[0u8; 17], &mut stream).unwrap();
+        assert!(matches!(
+            codec.read_frame(&mut stream.as_slice()),
+            Err(ProtocolFrameError::TooLong { length: 17, max_length: 16 })
+        ));
+    }
+
+    /// Packet which writes more bytes than its computed length
+    struct UnderestimatedPacket;
+
+    impl ProtocolPacket for UnderestimatedPacket {
+        const ID: i32 = 0x1;
+        const BOUND: ProtocolPacketBound = ProtocolPacketBound::Client;
+        const STATE: ProtocolPacketState = ProtocolPacketState::Play;
+    }
+
+    impl ProtocolSize for UnderestimatedPacket {
+        const SIZE: Range<u32> = (2..2);
+    }
+
+    impl ProtocolWritable for UnderestimatedPacket {
+        fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+            writer.write_bytes(&[1, 2, 3]);
+            Ok(())
+        }
+
+        fn encoded_len_for_version(&self, _version: Option<i32>) -> usize {
+            2
+        }
+    }
+
+    struct TestPacketBytes(Vec<u8>);
+
+    impl ProtocolPacket for TestPacketBytes {
+        const ID: i32 = 0x2;
+        const BOUND: ProtocolPacketBound = ProtocolPacketBound::Client;
+        const STATE: ProtocolPacketState = ProtocolPacketState::Play;
+    }
+
+    impl ProtocolSize for TestPacketBytes {
+        const SIZE: Range<u32> = (0..u32::MAX);
+    }
+
+    impl ProtocolWritable for TestPacketBytes {
+        fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+            writer.write_bytes(&self.0);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn write_packet_length_mismatch_test() {
+        let codec = ProtocolFrameCodec::default();
+        let mut stream = vec![7u8];
+        assert!(matches!(
+            codec.write_packet(&UnderestimatedPacket, &mut stream),
+            Err(ProtocolFrameError::LengthMismatch { expected: 3, written: 4 })
+        ));
+        assert_eq!(stream, vec![7]);
+        let mut buffer = [0u8; 8];
+        let mut writer = ProtocolSliceWriter::new(&mut buffer);
+        writer.write_byte(7);
+        assert!(codec.write_packet(&UnderestimatedPacket, &mut writer).is_err());
+        assert_eq!(writer.finish().unwrap(), &[7]);
+        let mut writer = ProtocolSliceWriter::new(&mut buffer);
+        codec.write_packet(&TestPacketBytes(vec![4, 5]), &mut writer).unwrap();
+        assert_eq!(writer.finish().unwrap(), &[3, 0x2, 4, 5]);
+    }
+}
@@ -5,10 +5,12 @@ use bird_chat::component::Component;
 use bird_chat::identifier::{Identifier, IdentifierInner};
 use bird_util::*;
 
+#[cfg(any(feature = "json", feature = "fastnbt"))]
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::*;
+use crate::budget::array_byte_length;
 
 macro_rules! protocol_raw {
     ($($ty: ty$(,)+)*) => {
@@ -108,6 +110,10 @@ impl<T: ProtocolWritable> ProtocolVariantWritable<T> for T {
     fn write_variant<W: ProtocolWriter>(object: &T, writer: &mut W) -> anyhow::Result<()> {
         object.write(writer)
     }
+
+    fn encoded_len_variant(object: &T, version: Option<i32>) -> usize {
+        object.encoded_len_for_version(version)
+    }
 }
 
 macro_rules! delegate_size {
@@ -158,6 +164,10 @@ macro_rules! number_impl {
             fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
                 Ok(writer.write_fixed_bytes(self.to_be_bytes()))
             }
+
+            fn encoded_len_for_version(&self, _version: Option<i32>) -> usize {
+                std::mem::size_of::<Self>()
+            }
         }
     };
     ($($ty: ty$(,)*)*) => {
@@ -183,6 +193,10 @@ impl ProtocolWritable for bool {
         }
             .write(writer)
     }
+
+    fn encoded_len_for_version(&self, _version: Option<i32>) -> usize {
+        1
+    }
 }
 
 macro_rules! var_number_impl {
@@ -194,7 +208,7 @@ macro_rules! var_number_impl {
                     let mut position = 0u8;
                     loop {
                         let current_byte = cursor.take_byte()?;
-                        value |= ((current_byte & 0x7F) << position) as $signed;
+                        value |= ((current_byte & 0x7F) as $signed) << position;
                         if ((current_byte & 0x80) == 0) {
                             break;
                         }
@@ -220,6 +234,16 @@ macro_rules! var_number_impl {
                     }
                     Ok(())
                 }
+
+                fn encoded_len_variant(object: &$signed, _version: Option<i32>) -> usize {
+                    let mut object = *object as $unsigned;
+                    let mut length = 1;
+                    while (object & !0x7F) != 0 {
+                        object >>= 7;
+                        length += 1;
+                    }
+                    length
+                }
             }
         )*
     }
@@ -239,6 +263,10 @@ macro_rules! var_number_lower_nums_impl {
                 fn write_variant<W: ProtocolWriter>(object: &$lower_ty, writer: &mut W) -> anyhow::Result<()> {
                     Self::write_variant(&(*object as $orig), writer)
                 }
+
+                fn encoded_len_variant(object: &$lower_ty, version: Option<i32>) -> usize {
+                    Self::encoded_len_variant(&(*object as $orig), version)
+                }
             }
         )*)*
     }
@@ -259,6 +287,10 @@ impl<'a> ProtocolVariantWritable<bool> for VarInt {
     fn write_variant<W: ProtocolWriter>(object: &bool, writer: &mut W) -> anyhow::Result<()> {
         object.write(writer)
     }
+
+    fn encoded_len_variant(_object: &bool, _version: Option<i32>) -> usize {
+        1
+    }
 }
 
 impl<'a> ProtocolVariantReadable<'a, bool> for VarLong {
@@ -271,6 +303,10 @@ impl<'a> ProtocolVariantWritable<bool> for VarLong {
     fn write_variant<W: ProtocolWriter>(object: &bool, writer: &mut W) -> anyhow::Result<()> {
         object.write(writer)
     }
+
+    fn encoded_len_variant(_object: &bool, _version: Option<i32>) -> usize {
+        1
+    }
 }
 
 var_number_impl!(VarInt = (i32, u32), VarLong = (i64, u64));
@@ -289,6 +325,10 @@ impl<T: ProtocolWritable> ProtocolWritable for Option<T> {
             None => false.write(writer),
         }
     }
+
+    fn encoded_len_for_version(&self, version: Option<i32>) -> usize {
+        1 + self.as_ref().map_or(0, |object| object.encoded_len_for_version(version))
+    }
 }
 
 impl<'a, T: ProtocolReadable<'a>> ProtocolReadable<'a> for Option<T> {
@@ -337,8 +377,8 @@ pub fn read_str_with_limit<'a, C: ProtocolCursor<'a>, const LIMIT: usize>(
     let length: i32 = VarInt::read_variant(cursor)?;
     let length = length as usize;
     match length <= LIMIT {
-        true => from_utf8(cursor.take_bytes(length)?).map_err(|err| ProtocolError::Any(err.into())),
-        false => Err(anyhow::Error::msg("Too long string").into()),
+        true => Ok(from_utf8(cursor.take_bytes(length)?)?),
+        false => Err(ProtocolError::StringTooLong { length, limit: LIMIT }),
     }
 }
 
@@ -351,6 +391,10 @@ impl ProtocolWritable for &str {
     fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
         write_str_with_limit::<W, DEFAULT_LIMIT>(self, writer)
     }
+
+    fn encoded_len_for_version(&self, version: Option<i32>) -> usize {
+        VarInt::encoded_len_variant(&(self.len() as i32), version) + self.len()
+    }
 }
 
 impl<'a> ProtocolReadable<'a> for &'a str {
@@ -365,6 +409,10 @@ impl ProtocolWritable for String {
     fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
         self.as_str().write(writer)
     }
+
+    fn encoded_len_for_version(&self, version: Option<i32>) -> usize {
+        self.as_str().encoded_len_for_version(version)
+    }
 }
 
 impl<'a> ProtocolReadable<'a> for String {
@@ -380,6 +428,10 @@ impl<'a> ProtocolWritable for Cow<'a, str> {
             Cow::Borrowed(borrowed) => borrowed.write(writer),
         }
     }
+
+    fn encoded_len_for_version(&self, version: Option<i32>) -> usize {
+        self.as_ref().encoded_len_for_version(version)
+    }
 }
 
 impl<'a> ProtocolReadable<'a> for Cow<'a, str> {
@@ -388,7 +440,7 @@ impl<'a> ProtocolReadable<'a> for Cow<'a, str> {
     }
 }
 
-const fn byte_array_into_t_array<T: Sized>(array: &[u8]) -> &[T] {
+pub(crate) const fn byte_array_into_t_array<T: Sized>(array: &[u8]) -> &[T] {
     unsafe { std::slice::from_raw_parts(array.as_ptr() as *const T, array.len() / std::mem::size_of::<T>()) }
 }
 
@@ -479,18 +531,31 @@ for LengthFunctionRawArray<V, VV, T>
         T::write_variant(&(object.len() * if T::ELEMENT_COUNT { 1 } else { std::mem::size_of::<V>() }), writer)?;
         Ok(writer.write_bytes(t_array_into_byte_array(object)))
     }
+
+    fn encoded_len_variant(object: &[V], version: Option<i32>) -> usize {
+        T::encoded_len_variant(&(object.len() * if T::ELEMENT_COUNT { 1 } else { std::mem::size_of::<V>() }), version)
+            + std::mem::size_of_val(object)
+    }
 }
 
 impl<'a, V: Sized, VV: ProtocolRaw, T: ProtocolLengthDeterminer<'a>> ProtocolVariantWritable<&'a [V]> for LengthFunctionRawArray<V, VV, T> {
     fn write_variant<W: ProtocolWriter>(object: &&'a [V], writer: &mut W) -> anyhow::Result<()> {
         Self::write_variant(*object, writer)
     }
+
+    fn encoded_len_variant(object: &&'a [V], version: Option<i32>) -> usize {
+        Self::encoded_len_variant(*object, version)
+    }
 }
 
 impl<'a, V: Sized, VV: ProtocolRaw, T: ProtocolLengthDeterminer<'a>> ProtocolVariantWritable<Vec<V>> for LengthFunctionRawArray<V, VV, T> {
     fn write_variant<W: ProtocolWriter>(object: &Vec<V>, writer: &mut W) -> anyhow::Result<()> {
         Self::write_variant(object.as_slice(), writer)
     }
+
+    fn encoded_len_variant(object: &Vec<V>, version: Option<i32>) -> usize {
+        Self::encoded_len_variant(object.as_slice(), version)
+    }
 }
 
 impl<'a, V: Sized + Clone, VV: ProtocolRaw, T: ProtocolLengthDeterminer<'a>> ProtocolVariantWritable<Cow<'_, [V]>> for LengthFunctionRawArray<V, VV, T>
@@ -507,12 +572,19 @@ impl<'a, V: Sized + Clone, VV: ProtocolRaw, T: ProtocolLengthDeterminer<'a>> Pro
             writer,
         )
     }
+
+    fn encoded_len_variant(object: &Cow<'_, [V]>, version: Option<i32>) -> usize {
+        Self::encoded_len_variant(object.as_ref(), version)
+    }
 }
 
 impl<'a, V: Sized, VV: ProtocolRaw, T: ProtocolLengthDeterminer<'a>> ProtocolVariantReadable<'a, &'a [V]> for LengthFunctionRawArray<V, VV, T>
 {
     fn read_variant<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<&'a [V]> {
-        let length = T::read_variant(cursor)? / if T::ELEMENT_COUNT { 1 } else { std::mem::size_of::<V>() };
+        let length = match T::ELEMENT_COUNT {
+            true => array_byte_length(T::read_variant(cursor)?, std::mem::size_of::<V>())?,
+            false => T::read_variant(cursor)? / std::mem::size_of::<V>() * std::mem::size_of::<V>(),
+        };
         Ok(byte_array_into_t_array(cursor.take_bytes(length)?))
     }
 }
@@ -520,7 +592,11 @@ impl<'a, V: Sized, VV: ProtocolRaw, T: ProtocolLengthDeterminer<'a>> ProtocolVar
 impl<'a, V: Sized + Clone + 'a, VV: ProtocolRaw, T: ProtocolLengthDeterminer<'a>> ProtocolVariantReadable<'a, Vec<V>> for LengthFunctionRawArray<V, VV, T>
 {
     fn read_variant<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Vec<V>> {
-        Self::read_variant(cursor).map(|slice: &'a [V]| slice.into())
+        // the allocation is reserved while the elements are still remaining bytes of the cursor
+        let slice: &'a [V] = Self::read_variant(&mut cursor.take_cursor())?;
+        cursor.reserve_allocation(slice.len(), std::mem::size_of::<V>())?;
+        let slice: &'a [V] = Self::read_variant(cursor)?;
+        Ok(slice.into())
     }
 }
 
@@ -546,6 +622,10 @@ impl<'a, V, VV: ProtocolVariantWritable<V>, T: ProtocolLengthDeterminer<'a>> Pro
         }
         Ok(())
     }
+
+    fn encoded_len_variant(object: &[V], version: Option<i32>) -> usize {
+        T::encoded_len_variant(&object.len(), version) + elements_encoded_len::<V, VV>(object, version)
+    }
 }
 
 impl<'a, V, VV: ProtocolVariantWritable<V>, T: ProtocolLengthDeterminer<'a>> ProtocolVariantWritable<Vec<V>> for LengthFunctionArray<V, VV, T>
@@ -554,6 +634,10 @@ impl<'a, V, VV: ProtocolVariantWritable<V>, T: ProtocolLengthDeterminer<'a>> Pro
     fn write_variant<W: ProtocolWriter>(object: &Vec<V>, writer: &mut W) -> anyhow::Result<()> {
         Self::write_variant(object.as_slice(), writer)
     }
+
+    fn encoded_len_variant(object: &Vec<V>, version: Option<i32>) -> usize {
+        Self::encoded_len_variant(object.as_slice(), version)
+    }
 }
 
 impl<
@@ -576,6 +660,10 @@ impl<
             writer,
         )
     }
+
+    fn encoded_len_variant(object: &Cow<'_, [V]>, version: Option<i32>) -> usize {
+        Self::encoded_len_variant(object.as_ref(), version)
+    }
 }
 
 impl<
@@ -588,11 +676,7 @@ impl<
 {
     fn read_variant<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Vec<V>> {
         let length = T::read_variant(cursor)?;
-        let mut result = Vec::new();
-        for _ in 0..length {
-            result.push(VV::read_variant(cursor)?);
-        }
-        Ok(result)
+        read_array_elements::<V, VV, C>(length, cursor)
     }
 }
 
@@ -609,14 +693,209 @@ impl<
     }
 }
 
+fn read_array_elements<'a, V, VV: ProtocolVariantReadable<'a, V>, C: ProtocolCursor<'a>>(length: usize, cursor: &mut C) -> ProtocolResult<Vec<V>> {
+    if !cursor.has_bytes(length.saturating_mul(VV::SIZE.start as usize)) {
+        return Err(ProtocolError::End);
+    }
+    cursor.reserve_allocation(length, std::mem::size_of::<V>())?;
+    let start = cursor.remaining_bytes();
+    let mut result = Vec::new();
+    for index in 0..length {
+        let offset = start - cursor.remaining_bytes();
+        result.push(VV::read_variant(cursor).map_err(|err| err.with_index(index, offset))?);
+    }
+    Ok(result)
+}
+
+fn elements_encoded_len<V, VV: ProtocolVariantWritable<V>>(array: &[V], version: Option<i32>) -> usize {
+    array.iter().map(|value| VV::encoded_len_variant(value, version)).sum()
+}
+
+fn write_array_elements<V, VV: ProtocolVariantWritable<V>, W: ProtocolWriter>(array: &[V], length: usize, writer: &mut W) -> anyhow::Result<()> {
+    if array.len() != length {
+        return Err(anyhow::Error::msg(format!("Array length {} is not equal to the provided length {}", array.len(), length)));
+    }
+    for value in array {
+        VV::write_variant(value, writer)?;
+    }
+    Ok(())
+}
+
+impl<V, VV> ProtocolLengthFromArray<V, VV> for Vec<V> {
+    fn read_with_length<'a, C: ProtocolCursor<'a>>(length: usize, cursor: &mut C) -> ProtocolResult<Self>
+        where V: 'a, VV: ProtocolVariantReadable<'a, V> {
+        read_array_elements::<V, VV, C>(length, cursor)
+    }
+
+    fn write_with_length<W: ProtocolWriter>(&self, length: usize, writer: &mut W) -> anyhow::Result<()>
+        where VV: ProtocolVariantWritable<V> {
+        write_array_elements::<V, VV, W>(self, length, writer)
+    }
+
+    fn encoded_len_without_length(&self, version: Option<i32>) -> usize
+        where VV: ProtocolVariantWritable<V> {
+        elements_encoded_len::<V, VV>(self, version)
+    }
+}
+
+impl<'b, V: Clone, VV> ProtocolLengthFromArray<V, VV> for Cow<'b, [V]> {
+    fn read_with_length<'a, C: ProtocolCursor<'a>>(length: usize, cursor: &mut C) -> ProtocolResult<Self>
+        where V: 'a, VV: ProtocolVariantReadable<'a, V> {
+        read_array_elements::<V, VV, C>(length, cursor).map(Cow::Owned)
+    }
+
+    fn write_with_length<W: ProtocolWriter>(&self, length: usize, writer: &mut W) -> anyhow::Result<()>
+        where VV: ProtocolVariantWritable<V> {
+        write_array_elements::<V, VV, W>(self, length, writer)
+    }
+
+    fn encoded_len_without_length(&self, version: Option<i32>) -> usize
+        where VV: ProtocolVariantWritable<V> {
+        elements_encoded_len::<V, VV>(self, version)
+    }
+}
+
+/// Reads `len_from` array of elements without variant
+pub fn read_length_from_array<'a, A, V, C>(length: usize, cursor: &mut C) -> ProtocolResult<A>
+    where A: ProtocolLengthFromArray<V, V>, V: ProtocolReadable<'a>, C: ProtocolCursor<'a> {
+    A::read_with_length(length, cursor)
+}
+
+/// Writes `len_from` array of elements without variant
+pub fn write_length_from_array<A, V, W>(array: &A, length: usize, writer: &mut W) -> anyhow::Result<()>
+    where A: ProtocolLengthFromArray<V, V>, V: ProtocolWritable, W: ProtocolWriter {
+    array.write_with_length(length, writer)
+}
+
+/// Encoded length of `len_from` array of elements without variant
+pub fn length_from_array_encoded_len<A, V>(array: &A, version: Option<i32>) -> usize
+    where A: ProtocolLengthFromArray<V, V>, V: ProtocolWritable {
+    array.encoded_len_without_length(version)
+}
+
+impl<V: ProtocolLengthLimitSize, const MAX: usize> ProtocolSize for LengthLimited<V, MAX> {
+    const SIZE: Range<u32> = (V::PREFIX_SIZE.start..add_u32_without_overflow(
+        V::PREFIX_SIZE.end,
+        match (MAX as u32).checked_mul(V::ELEMENT_SIZE.end) {
+            Some(size) => size,
+            None => u32::MAX,
+        },
+    ));
+}
+
+impl<'a, V, VV, const MAX: usize> ProtocolVariantReadable<'a, V> for LengthLimited<VV, MAX>
+    where VV: ProtocolVariantReadable<'a, V> + ProtocolLengthLimit<V> + ProtocolLengthLimitPrefix<'a> {
+    fn read_variant<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<V> {
+        // the prefix is checked first, so a hostile length does not cause an allocation
+        if let Some(length) = VV::peek_length(cursor)? {
+            if length > MAX {
+                return Err(VV::too_long(length, MAX));
+            }
+        }
+        let value = VV::read_variant(cursor)?;
+        match VV::length_of(&value) {
+            length if length > MAX => Err(VV::too_long(length, MAX)),
+            _ => Ok(value),
+        }
+    }
+}
+
+impl<V, VV: ProtocolVariantWritable<V> + ProtocolLengthLimit<V>, const MAX: usize> ProtocolVariantWritable<V> for LengthLimited<VV, MAX> {
+    fn write_variant<W: ProtocolWriter>(object: &V, writer: &mut W) -> anyhow::Result<()> {
+        match VV::length_of(object) {
+            length if length > MAX => Err(VV::too_long(length, MAX).into()),
+            _ => VV::write_variant(object, writer),
+        }
+    }
+
+    fn encoded_len_variant(object: &V, version: Option<i32>) -> usize {
+        VV::encoded_len_variant(object, version)
+    }
+}
+
+macro_rules! str_length_limit {
+    ($($ty: ty$(,)*)*) => {
+        $(
+            impl ProtocolLengthLimitSize for $ty {
+                const PREFIX_SIZE: Range<u32> = VarInt::SIZE;
+                const ELEMENT_SIZE: Range<u32> = (1..4);
+
+                fn too_long(length: usize, limit: usize) -> ProtocolError {
+                    ProtocolError::StringTooLong { length, limit }
+                }
+            }
+
+            impl ProtocolLengthLimit<$ty> for $ty {
+                fn length_of(value: &$ty) -> usize {
+                    value.encode_utf16().count()
+                }
+            }
+
+            impl<'a> ProtocolLengthLimitPrefix<'a> for $ty {
+                fn peek_length<C: ProtocolCursor<'a>>(cursor: &C) -> ProtocolResult<Option<usize>> {
+                    let bytes: i32 = VarInt::read_variant(&mut cursor.take_cursor())?;
+                    let bytes = bytes.max(0) as usize;
+                    // one UTF-16 code unit takes at most 3 bytes
+                    Ok(Some(bytes / 3 + usize::from(bytes % 3 != 0)))
+                }
+            }
+        )*
+    }
+}
+
+str_length_limit!(&str, String, Cow<'_, str>);
+
+impl<'a, V, VV, T: ProtocolLengthDeterminer<'a>> ProtocolLengthLimitSize for LengthFunctionRawArray<V, VV, T> {
+    const PREFIX_SIZE: Range<u32> = T::SIZE;
+    const ELEMENT_SIZE: Range<u32> = (size_of::<V>() as u32..size_of::<V>() as u32);
+}
+
+impl<'a, V, VV, T: ProtocolLengthDeterminer<'a>, A: AsRef<[V]> + ?Sized> ProtocolLengthLimit<A> for LengthFunctionRawArray<V, VV, T> {
+    fn length_of(value: &A) -> usize {
+        value.as_ref().len()
+    }
+}
+
+impl<'a, V, VV, T: ProtocolLengthDeterminer<'a>> ProtocolLengthLimitPrefix<'a> for LengthFunctionRawArray<V, VV, T> {
+    fn peek_length<C: ProtocolCursor<'a>>(cursor: &C) -> ProtocolResult<Option<usize>> {
+        let length = T::read_variant(&mut cursor.take_cursor())?;
+        Ok(Some(match T::ELEMENT_COUNT {
+            true => length,
+            false => length / size_of::<V>().max(1),
+        }))
+    }
+}
+
+impl<'a, V, VV: ProtocolSize, T: ProtocolLengthDeterminer<'a>> ProtocolLengthLimitSize for LengthFunctionArray<V, VV, T> {
+    const PREFIX_SIZE: Range<u32> = T::SIZE;
+    const ELEMENT_SIZE: Range<u32> = VV::SIZE;
+}
+
+impl<'a, V, VV: ProtocolSize, T: ProtocolLengthDeterminer<'a>, A: AsRef<[V]> + ?Sized> ProtocolLengthLimit<A> for LengthFunctionArray<V, VV, T> {
+    fn length_of(value: &A) -> usize {
+        value.as_ref().len()
+    }
+}
+
+impl<'a, V, VV: ProtocolSize, T: ProtocolLengthDeterminer<'a>> ProtocolLengthLimitPrefix<'a> for LengthFunctionArray<V, VV, T> {
+    fn peek_length<C: ProtocolCursor<'a>>(cursor: &C) -> ProtocolResult<Option<usize>> {
+        match T::ELEMENT_COUNT {
+            true => Ok(Some(T::read_variant(&mut cursor.take_cursor())?)),
+            false => Ok(None),
+        }
+    }
+}
+
 fixed_range_size!(Json = (VarInt::SIZE.start, (CHAT_LIMIT as u32 * 4 + 3)));
 
+#[cfg(feature = "json")]
 impl<T: Serialize> ProtocolVariantWritable<T> for Json {
     fn write_variant<W: ProtocolWriter>(object: &T, writer: &mut W) -> anyhow::Result<()> {
         write_str_with_limit::<W, CHAT_LIMIT>(serde_json::to_string(object)?.as_str(), writer)
     }
 }
 
+#[cfg(feature = "json")]
 impl<'a, T: Deserialize<'a>> ProtocolVariantReadable<'a, T> for Json {
     fn read_variant<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<T> {
         serde_json::from_str(read_str_with_limit::<C, CHAT_LIMIT>(cursor)?)
@@ -645,12 +924,14 @@ impl<'a> ProtocolReadable<'a> for Uuid {
 
 fixed_range_size!(Component<'_> = (1, (262144 * 4) + 3));
 
+#[cfg(feature = "json")]
 impl<'a> ProtocolWritable for Component<'a> {
     fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
         write_str_with_limit::<_, CHAT_LIMIT>(serde_json::to_string(self)?.as_str(), writer)
     }
 }
 
+#[cfg(feature = "json")]
 impl<'a> ProtocolReadable<'a> for Component<'a> {
     fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
         read_str_with_limit::<_, CHAT_LIMIT>(cursor)
@@ -689,7 +970,7 @@ delegate_size!(Angle = u8);
 
 impl ProtocolVariantWritable<f32> for Angle {
     fn write_variant<W: ProtocolWriter>(object: &f32, writer: &mut W) -> anyhow::Result<()> {
-        ((*object * 256.0 / std::f32::consts::PI) as u8).write(writer)
+        ((*object * 256.0 / std::f32::consts::PI).round() as u8).write(writer)
     }
 }
 
@@ -699,19 +980,37 @@ impl<'a> ProtocolVariantReadable<'a, f32> for Angle {
     }
 }
 
-fixed_range_size!(Nbt = (1, u32::MAX));
+fixed_range_size!(Nbt = (1, u32::MAX), FastNbt = (1, u32::MAX));
+
+#[cfg(feature = "birdnbt")]
+mod birdnbt_impls {
+    use super::*;
+    use crate::nbt::serde::{read_nbt_serde, write_nbt_serde};
+
+    impl<T: Serialize> ProtocolVariantWritable<T> for Nbt {
+        fn write_variant<W: ProtocolWriter>(object: &T, writer: &mut W) -> anyhow::Result<()> {
+            write_nbt_serde(object, writer)
+        }
+    }
+
+    impl<'a, T: Deserialize<'a>> ProtocolVariantReadable<'a, T> for Nbt {
+        fn read_variant<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<T> {
+            read_nbt_serde(cursor)
+        }
+    }
+}
 
 #[cfg(feature = "fastnbt")]
 mod fastnbt_impls {
     use super::*;
 
-    impl<T: Serialize> ProtocolVariantWritable<T> for Nbt {
+    impl<T: Serialize> ProtocolVariantWritable<T> for FastNbt {
         fn write_variant<W: ProtocolWriter>(object: &T, writer: &mut W) -> anyhow::Result<()> {
             Ok(writer.write_vec_bytes(fastnbt::to_bytes(object)?))
         }
     }
 
-    impl<'a, T: Deserialize<'a>> ProtocolVariantReadable<'a, T> for Nbt {
+    impl<'a, T: Deserialize<'a>> ProtocolVariantReadable<'a, T> for FastNbt {
         fn read_variant<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<T> {
             fastnbt::from_reader(ReadableProtocolCursor::new(cursor))
                 .map_err(|err| ProtocolError::Any(err.into()))
@@ -738,7 +1037,7 @@ pub(crate) mod nbt {
         }
 
         fn skip(&mut self, length: usize) -> ProtocolResult<()> {
-            self.cursor.take_bytes(length).map(|_| ())
+            self.take_bytes(length).map(|_| ())
         }
     }
 
@@ -768,6 +1067,14 @@ pub(crate) mod nbt {
         fn has_bytes(&self, length: usize) -> bool {
             self.cursor.has_bytes(length)
         }
+
+        fn protocol_version(&self) -> Option<i32> {
+            self.cursor.protocol_version()
+        }
+
+        fn reserve_allocation(&mut self, count: usize, element_size: usize) -> ProtocolResult<()> {
+            self.cursor.reserve_allocation(count, element_size)
+        }
     }
 
     pub fn skip_string<'a, C: ProtocolCursor<'a>>(cursor: &mut ProtocolSkipCursor<'a, C>) -> ProtocolResult<()> {
@@ -797,16 +1104,16 @@ pub(crate) mod nbt {
     pub fn skip_tag<'a, C: ProtocolCursor<'a>>(cursor: &mut ProtocolSkipCursor<'a, C>, tag: u8, times: usize) -> ProtocolResult<()> {
         match tag {
             0 => Ok(()),
-            1 => cursor.skip(1 * times),
-            2 => cursor.skip(2 * times),
-            3 => cursor.skip(4 * times),
-            4 => cursor.skip(8 * times),
-            5 => cursor.skip(4 * times),
-            6 => cursor.skip(8 * times),
+            1 => cursor.skip(array_byte_length(times, 1)?),
+            2 => cursor.skip(array_byte_length(times, 2)?),
+            3 => cursor.skip(array_byte_length(times, 4)?),
+            4 => cursor.skip(array_byte_length(times, 8)?),
+            5 => cursor.skip(array_byte_length(times, 4)?),
+            6 => cursor.skip(array_byte_length(times, 8)?),
             7 => {
                 for _ in 0..times {
                     let length = i32::read(cursor)?;
-                    cursor.skip(length as usize)?
+                    cursor.skip(array_byte_length(length, 1)?)?
                 }
                 Ok(())
             }
@@ -820,27 +1127,27 @@ pub(crate) mod nbt {
                 for _ in 0..times {
                     let tag = u8::read(cursor)?;
                     let times = i32::read(cursor)?;
-                    skip_tag(cursor, tag, times as usize)?
+                    skip_tag(cursor, tag, times.max(0) as usize)?
                 }
                 Ok(())
             }
             10 => {
                 for _ in 0..times {
-                    skip_compound(cursor)?
+                    skip_entered_compound(cursor)?
                 }
                 Ok(())
             }
             11 => {
                 for _ in 0..times {
                     let length = i32::read(cursor)?;
-                    cursor.skip(length as usize * 4)?;
+                    cursor.skip(array_byte_length(length, 4)?)?;
                 }
                 Ok(())
             }
             12 => {
                 for _ in 0..times {
                     let length = i32::read(cursor)?;
-                    cursor.skip(length as usize * 8)?;
+                    cursor.skip(array_byte_length(length, 8)?)?;
                 }
                 Ok(())
             }
@@ -900,6 +1207,8 @@ impl<'a> ProtocolVariantReadable<'a, Cow<'a, [u8]>> for NbtBytes {
 
 delegate_size!(BlockPosition = u64);
 
+delegate_size!(SectionPosition = u64);
+
 #[cfg(feature = "euclid")]
 mod euclid_impls {
     use super::*;
@@ -981,6 +1290,34 @@ mod euclid_impls {
             Ok(Vector3D { x, y, z, _unit: PhantomData })
         }
     }
+
+    impl<U> ProtocolVariantWritable<Vector3D<i32, U>> for SectionPosition {
+        fn write_variant<W: ProtocolWriter>(object: &Vector3D<i32, U>, writer: &mut W) -> anyhow::Result<()> {
+            (((object.x as i64 & 0x3FFFFF) << 42) |
+                ((object.z as i64 & 0x3FFFFF) << 20) |
+                (object.y as i64 & 0xFFFFF)
+            ).write(writer)
+        }
+    }
+
+    impl<'a, U: 'a> ProtocolVariantReadable<'a, Vector3D<i32, U>> for SectionPosition {
+        fn read_variant<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Vector3D<i32, U>> {
+            let value = u64::read(cursor)?;
+            let mut x = (value >> 42) as i32;
+            let mut y = (value & 0xFFFFF) as i32;
+            let mut z = ((value >> 20) & 0x3FFFFF) as i32;
+            if x >= 0x200000 {
+                x -= 0x400000
+            }
+            if y >= 0x80000 {
+                y -= 0x100000
+            }
+            if z >= 0x200000 {
+                z -= 0x400000
+            }
+            Ok(Vector3D { x, y, z, _unit: PhantomData })
+        }
+    }
 }
 
 impl<T, const N: u8> ProtocolSize for FixedPointNumber<T, N>
@@ -1051,8 +1388,8 @@ impl<'a, 'b, C: ProtocolCursor<'b>, L: ProtocolCursorIteratorLimiter, V, VV: Pro
         if !self.limiter.next() { return None; }
         match VV::read_variant(self.cursor) {
             Ok(val) => Some(val),
-            Err(ProtocolError::End) => None,
-            Err(ProtocolError::Any(err)) => {
+            Err(err) if err.is_end() => None,
+            Err(err) => {
                 // TODO think about error handling
                 if cfg!(debug_assertions) {
                     panic!("{}", err);
@@ -1071,7 +1408,7 @@ impl<T: ProtocolSize, const S: usize> ProtocolSize for ProtocolSizeOption<T, S>
 impl<'a, T: ProtocolReadable<'a>, const S: usize> ProtocolVariantReadable<'a, Option<T>> for ProtocolSizeOption<T, S> {
     fn read_variant<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Option<T>> {
         match cursor.remaining_bytes() == S {
-            true => Ok(None),
+            true => cursor.take_bytes(S).map(|_| None),
             false => T::read(cursor).map(|val| Some(val))
         }
     }
@@ -1121,6 +1458,13 @@ impl<'a, T: ProtocolWritable + ProtocolRaw, const LENGTH: usize> ProtocolVariant
     }
 }
 
+impl<'a, T: ProtocolWritable + ProtocolRaw + Clone, const LENGTH: usize> ProtocolVariantWritable<Cow<'a, [T; LENGTH]>> for ConstLengthRawArray<T, LENGTH> {
+    fn write_variant<W: ProtocolWriter>(object: &Cow<'a, [T; LENGTH]>, writer: &mut W) -> anyhow::Result<()> {
+        writer.write_bytes(t_array_into_byte_array(object.as_ref()));
+        Ok(())
+    }
+}
+
 impl<T, const LENGTH: usize> ConstLengthRawArray<T, LENGTH> {
     const BYTES_LENGTH: usize = LENGTH * size_of::<T>();
 }
@@ -1133,6 +1477,13 @@ impl<'a, T: ProtocolReadable<'a> + ProtocolRaw, const LENGTH: usize> ProtocolVar
     }
 }
 
+impl<'a, T: ProtocolReadable<'a> + ProtocolRaw + Clone, const LENGTH: usize> ProtocolVariantReadable<'a, Cow<'a, [T; LENGTH]>> for ConstLengthRawArray<T, LENGTH>
+    where [(); Self::BYTES_LENGTH ]: {
+    fn read_variant<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Cow<'a, [T; LENGTH]>> {
+        <Self as ProtocolVariantReadable<'a, &'a [T; LENGTH]>>::read_variant(cursor).map(Cow::Borrowed)
+    }
+}
+
 impl<V, VV: ProtocolSize> ProtocolSize for ProtocolVariantOption<V, VV> {
     const SIZE: Range<u32> = 1..add_u32_without_overflow(VV::SIZE.end, 1);
 }
@@ -1157,3 +1508,53 @@ impl<'a, V, VV: ProtocolVariantReadable<'a, V>> ProtocolVariantReadable<'a, Opti
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn angle_test() {
+        for step in 0..=u8::MAX {
+            let angle = step as f32 * std::f32::consts::PI / 256.0;
+            let mut bytes = Vec::new();
+            Angle::write_variant(&angle, &mut bytes).unwrap();
+            assert_eq!(bytes, [step]);
+            assert_eq!(Angle::read_variant(&mut bytes.as_slice()).unwrap(), angle);
+        }
+    }
+
+    #[test]
+    fn size_option_test() {
+        let mut bytes = Vec::new();
+        ProtocolSizeOption::<i32, 1>::write_variant(&None, &mut bytes).unwrap();
+        assert_eq!(bytes, [0]);
+        let mut cursor = bytes.as_slice();
+        assert_eq!(ProtocolSizeOption::<i32, 1>::read_variant(&mut cursor).unwrap(), None);
+        // the placeholder is read, so the cursor is at the end of the object
+        assert!(cursor.is_empty());
+        let mut cursor = [0, 0, 0, 7].as_slice();
+        assert_eq!(ProtocolSizeOption::<i32, 1>::read_variant(&mut cursor).unwrap(), Some(7));
+        assert!(cursor.is_empty());
+    }
+
+    #[test]
+    fn nbt_bytes_skip_test() {
+        // compound with a string and an int array, the skipped bytes are a part of the nbt
+        let bytes = [10, 0, 0, 8, 0, 1, b's', 0, 2, b'h', b'i', 11, 0, 1, b'a', 0, 0, 0, 1, 0, 0, 0, 7, 0, 1];
+        let mut cursor = bytes.as_slice();
+        let nbt: &[u8] = NbtBytes::read_variant(&mut cursor).unwrap();
+        assert_eq!(nbt, &bytes[..bytes.len() - 1]);
+        assert_eq!(cursor, [1]);
+    }
+
+    #[test]
+    fn nbt_bytes_nested_compound_test() {
+        // nested compounds have no root name, so they are skipped from their first entry
+        let bytes = [10, 0, 0, 10, 0, 1, b'c', 1, 0, 1, b'b', 5, 0, 9, 0, 1, b'l', 10, 0, 0, 0, 1, 0, 0, 1];
+        let mut cursor = bytes.as_slice();
+        let nbt: &[u8] = NbtBytes::read_variant(&mut cursor).unwrap();
+        assert_eq!(nbt, &bytes[..bytes.len() - 1]);
+        assert_eq!(cursor, [1]);
+    }
+}
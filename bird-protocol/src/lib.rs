@@ -5,18 +5,38 @@ use std::{ops::Range, marker::PhantomData};
 mod impls;
 mod std_impls;
 mod pub_impls;
+pub mod frame;
+pub mod version;
+pub mod budget;
+#[cfg(feature = "compression")]
+pub mod compression;
+#[cfg(feature = "encryption")]
+pub mod encryption;
 #[cfg(feature = "birdnbt")]
 pub mod nbt;
+#[cfg(feature = "tokio")]
+pub mod tokio;
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+pub mod dissect;
+pub mod capture;
 
 pub use pub_impls::*;
 
 pub use crate::std_impls::StdIOReadProtocolCursor as ReadableProtocolCursor;
 
+pub use crate::std_impls::StdIOWriteProtocolWriter as WritableProtocolWriter;
+
 pub use anyhow;
 
+pub use uuid;
+
+pub use bird_util::IntoOwned;
+
 #[doc(hidden)]
 pub mod __private {
     pub use crate::impls::*;
+    pub use crate::dissect::{DissectDebug, DissectNotDebug, DissectValue};
 }
 
 pub use crate::impls::add_protocol_sizes;
@@ -60,16 +80,45 @@ pub struct ConstLengthRawArray<T, const LENGTH: usize>(PhantomData<T>);
 
 pub struct ProtocolVariantOption<V, VV>(PhantomData<(V, VV)>);
 
+/// Variant `V` which reads and writes at most `MAX` elements (UTF-16 code units for strings as in vanilla)
+pub struct LengthLimited<V, const MAX: usize>(PhantomData<V>);
+
+/// Variant which length can be limited by [`LengthLimited`]
+pub trait ProtocolLengthLimitSize {
+    const PREFIX_SIZE: Range<u32>;
+    const ELEMENT_SIZE: Range<u32>;
+
+    fn too_long(length: usize, limit: usize) -> ProtocolError {
+        ProtocolError::ArrayTooLong { length, limit }
+    }
+}
+
+pub trait ProtocolLengthLimit<V: ?Sized>: ProtocolLengthLimitSize {
+    fn length_of(value: &V) -> usize;
+}
+
+/// Length prefix of a limited variant, so too long values are rejected before their elements are read
+pub trait ProtocolLengthLimitPrefix<'a>: ProtocolLengthLimitSize {
+    /// The smallest length which the prefix at the cursor allows. None if it is unknown before reading.
+    /// The cursor is not moved
+    fn peek_length<C: ProtocolCursor<'a>>(cursor: &C) -> ProtocolResult<Option<usize>>;
+}
+
 pub struct Json;
 
 pub struct Nbt;
 
+/// Nbt variant which is read and written by fastnbt instead of the nbt module
+pub struct FastNbt;
+
 pub struct NbtBytes;
 
 pub struct Angle;
 
 pub struct BlockPosition;
 
+pub struct SectionPosition;
+
 pub struct FixedPointNumber<T, const N: u8>(PhantomData<T>,);
 
 pub trait ProtocolLengthDeterminer<'a>: ProtocolVariantReadable<'a, usize> + ProtocolVariantWritable<usize> + ProtocolSize {
@@ -106,34 +155,172 @@ pub trait ProtocolLength {
     fn from_usize(size: usize) -> Self;
 }
 
+/// Array which length is stored in an earlier field (`len_from` field attribute). `VV` is the variant of elements
+pub trait ProtocolLengthFromArray<V, VV>: Sized {
+    fn read_with_length<'a, C: ProtocolCursor<'a>>(length: usize, cursor: &mut C) -> ProtocolResult<Self>
+        where V: 'a, VV: ProtocolVariantReadable<'a, V>;
+
+    /// Fails if the length of the array is not equal to the length which is stored in the other field
+    fn write_with_length<W: ProtocolWriter>(&self, length: usize, writer: &mut W) -> anyhow::Result<()>
+        where VV: ProtocolVariantWritable<V>;
+
+    fn encoded_len_without_length(&self, version: Option<i32>) -> usize
+        where VV: ProtocolVariantWritable<V>;
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum ProtocolError {
     #[error("Tried to take too many bytes")]
     End,
+    #[error("Bad key {key} of {ty}")]
+    BadEnumKey {
+        ty: &'static str,
+        key: String,
+    },
+    #[error("Too long string: length {length} is bigger than limit {limit}")]
+    StringTooLong {
+        length: usize,
+        limit: usize,
+    },
+    #[error("Array length {length} is bigger than limit {limit}")]
+    ArrayTooLong {
+        length: usize,
+        limit: usize,
+    },
+    #[error("Allocation of {requested} bytes is bigger than remaining budget of {remaining} bytes")]
+    AllocationBudgetExceeded {
+        requested: usize,
+        remaining: usize,
+    },
+    #[error("Invalid UTF-8: {0}")]
+    InvalidUtf8(#[from] std::str::Utf8Error),
+    /// Error which happened while reading a field. `offset` is the byte offset of the field from the start of `ty`
+    #[error("{} @ byte {offset}: {source}", format_error_path(ty, path))]
+    Context {
+        ty: &'static str,
+        path: Vec<String>,
+        offset: usize,
+        source: Box<ProtocolError>,
+    },
     #[error("Any: {0:?}")]
     Any(#[from] anyhow::Error),
 }
 
+impl ProtocolError {
+    /// The error without context
+    pub fn root(&self) -> &ProtocolError {
+        match self {
+            ProtocolError::Context { source, .. } => source.root(),
+            error => error,
+        }
+    }
+
+    pub fn is_end(&self) -> bool {
+        matches!(self.root(), ProtocolError::End)
+    }
+
+    /// Adds the field of `ty` in which the error happened. Used by the readable derive
+    pub fn with_field(self, ty: &'static str, field: &str, offset: usize) -> Self {
+        self.with_path_segment(Some(ty), field.to_string(), offset)
+    }
+
+    /// Adds the index of the array element in which the error happened.
+    /// `offset` is the byte offset of the element from the start of the array
+    pub fn with_index(self, index: usize, offset: usize) -> Self {
+        self.with_path_segment(None, format!("[{}]", index), offset)
+    }
+
+    fn with_path_segment(self, new_ty: Option<&'static str>, segment: String, new_offset: usize) -> Self {
+        match self {
+            ProtocolError::Context { ty, mut path, offset, source } => {
+                path.insert(0, segment);
+                ProtocolError::Context { ty: new_ty.unwrap_or(ty), path, offset: new_offset + offset, source }
+            }
+            error => ProtocolError::Context {
+                ty: new_ty.unwrap_or(""),
+                path: vec![segment],
+                offset: new_offset,
+                source: Box::new(error),
+            },
+        }
+    }
+}
+
+fn format_error_path(ty: &str, path: &[String]) -> String {
+    let mut result = ty.to_string();
+    for segment in path {
+        if !result.is_empty() && !segment.starts_with('[') {
+            result.push('.');
+        }
+        result.push_str(segment);
+    }
+    result
+}
+
 pub type ProtocolResult<T> = Result<T, ProtocolError>;
 
+/// Discriminants are written in capture files, so they should not be changed
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum ProtocolPacketBound {
-    Client,
-    Server,
+    Client = 0,
+    Server = 1,
 }
 
+/// Discriminants are written in capture files, so they should not be changed
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum ProtocolPacketState {
-    Handshake,
-    Status,
-    Login,
-    Play,
+    Handshake = 0,
+    Status = 1,
+    Login = 2,
+    Play = 3,
 }
 
 pub trait ProtocolPacket {
     const ID: i32;
     const BOUND: ProtocolPacketBound;
     const STATE: ProtocolPacketState;
+
+    /// Ids which replace [`ProtocolPacket::ID`] in some protocol versions
+    const VERSION_IDS: &'static [version::ProtocolVersionId] = &[];
+
+    /// Id of the packet in the given protocol version
+    fn id_for_version(version: Option<i32>) -> i32 {
+        version::packet_id_for_version(Self::ID, Self::VERSION_IDS, version)
+    }
+}
+
+/// Enum of every packet of one state and bound
+pub trait ProtocolPacketRegistry<'a>: Sized {
+    const STATE: ProtocolPacketState;
+    const BOUND: ProtocolPacketBound;
+
+    fn packet_id(&self) -> i32;
+
+    fn packet_id_for_version(&self, version: Option<i32>) -> i32;
+
+    /// Id is compared with ids of packets in the protocol version of the cursor
+    fn read_by_id<C: ProtocolCursor<'a>>(id: i32, cursor: &mut C) -> ProtocolResult<Self>;
+
+    /// Writes packet without id
+    fn write_packet<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()>;
+
+    /// Exact length of the packet without id in the given protocol version
+    fn packet_encoded_len(&self, version: Option<i32>) -> usize;
+
+    fn read_with_id<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let id: i32 = VarInt::read_variant(cursor)?;
+        Self::read_by_id(id, cursor)
+    }
+
+    fn write_with_id<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        VarInt::write_variant(&self.packet_id_for_version(writer.protocol_version()), writer)?;
+        self.write_packet(writer)
+    }
+
+    /// Exact length of the packet with id in the given protocol version
+    fn encoded_len_with_id(&self, version: Option<i32>) -> usize {
+        VarInt::encoded_len_variant(&self.packet_id_for_version(version), version) + self.packet_encoded_len(version)
+    }
 }
 
 pub unsafe trait ProtocolRaw {}
@@ -164,6 +351,32 @@ pub trait ProtocolCursor<'a> {
     fn has_bytes(&self, length: usize) -> bool {
         length <= self.remaining_bytes()
     }
+
+    /// Protocol version of the read bytes. None means [`version::CURRENT_PROTOCOL_VERSION`]
+    fn protocol_version(&self) -> Option<i32> {
+        None
+    }
+
+    /// Checks allocation of `count` elements with size of `element_size` against the budget of the cursor.
+    /// Should be called before allocation of anything which length is read.
+    /// Without a budget every element should take at least one of the remaining bytes
+    fn reserve_allocation(&mut self, count: usize, element_size: usize) -> ProtocolResult<()> {
+        let _ = element_size;
+        match self.has_bytes(count) {
+            true => Ok(()),
+            false => Err(ProtocolError::End),
+        }
+    }
+
+    /// Called by derived readers before field `path` of `ty` is read
+    fn enter_field(&mut self, ty: &'static str, path: &'static str) {
+        let _ = (ty, path);
+    }
+
+    /// Called by derived readers after the entered field is read. Value is None if it is not printable
+    fn leave_field(&mut self, value: Option<&dyn std::fmt::Debug>) {
+        let _ = value;
+    }
 }
 
 pub trait ProtocolWriter {
@@ -180,10 +393,45 @@ pub trait ProtocolWriter {
     fn write_vec_bytes(&mut self, bytes: Vec<u8>) {
         self.write_bytes(bytes.as_slice())
     }
+
+    /// Protocol version of the written bytes. None means [`version::CURRENT_PROTOCOL_VERSION`]
+    fn protocol_version(&self) -> Option<i32> {
+        None
+    }
+
+    /// Hint that `additional` bytes are going to be written
+    fn reserve(&mut self, additional: usize) {
+        let _ = additional;
+    }
+
+    /// Count of bytes in the writer. None if the writer can not be rolled back by [`ProtocolWriter::truncate`]
+    fn position(&self) -> Option<usize> {
+        None
+    }
+
+    /// Drops the bytes written after the given position. Does nothing if the writer does not support it
+    fn truncate(&mut self, position: usize) {
+        let _ = position;
+    }
 }
 
 pub trait ProtocolWritable: ProtocolSize {
     fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()>;
+
+    /// Exact count of bytes which are written in the given protocol version.
+    /// By default the object is written into [`ProtocolLengthCounter`].
+    /// The write error is ignored, because writing the object fails the same way,
+    /// and [`frame::ProtocolFrameCodec::write_packet`] checks the length which is really written
+    fn encoded_len_for_version(&self, version: Option<i32>) -> usize {
+        let mut counter = ProtocolLengthCounter::new(version);
+        let _ = self.write(&mut counter);
+        counter.length
+    }
+
+    /// Exact count of bytes which are written if the protocol version is unknown
+    fn encoded_len(&self) -> usize {
+        self.encoded_len_for_version(None)
+    }
 }
 
 pub trait ProtocolVariantWritable<V: ?Sized>: ProtocolSize {
@@ -191,6 +439,13 @@ pub trait ProtocolVariantWritable<V: ?Sized>: ProtocolSize {
         object: &V,
         writer: &mut W,
     ) -> anyhow::Result<()>;
+
+    /// Exact count of bytes which are written by [`ProtocolVariantWritable::write_variant`] in the given protocol version
+    fn encoded_len_variant(object: &V, version: Option<i32>) -> usize {
+        let mut counter = ProtocolLengthCounter::new(version);
+        let _ = Self::write_variant(object, &mut counter);
+        counter.length
+    }
 }
 
 pub trait ProtocolReadable<'a>: ProtocolSize + Sized + 'a {
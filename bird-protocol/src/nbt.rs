@@ -1,7 +1,17 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::marker::PhantomData;
 use euclid::Vector3D;
-use crate::{ProtocolCursor, ProtocolError, ProtocolResult, ProtocolWriter, write_compound};
+use crate::{IntoOwned, ProtocolCursor, ProtocolError, ProtocolResult, ProtocolWriter, write_compound};
+use crate::budget::array_byte_length;
+
+mod value;
+pub mod snbt;
+pub mod serde;
+#[cfg(feature = "compression")]
+pub mod file;
+
+pub use value::{NbtCompound, NbtValue};
 
 #[derive(Debug)]
 pub enum NbtBorrowedArray<'a, T, const SIZE: usize = 0> {
@@ -26,6 +36,15 @@ impl<'a, T, const SIZE: usize> Clone for NbtBorrowedArray<'a, T, SIZE> {
     }
 }
 
+/// Raw arrays are decoded, so the owned array is a vector of elements
+impl<'a, T: Clone + NbtTag<'a> + 'static, const SIZE: usize> IntoOwned for NbtBorrowedArray<'a, T, SIZE> {
+    type Owned = Vec<T>;
+
+    fn into_owned(self) -> Vec<T> {
+        self.collect()
+    }
+}
+
 impl<'a, T: Clone + NbtTag<'a>, const SIZE: usize> Iterator for NbtBorrowedArray<'a, T, SIZE> {
     type Item = T;
 
@@ -79,7 +98,7 @@ impl<'a, T: NbtTag<'a>, const SIZE: usize> NbtBorrowedArray<'a, T, SIZE> {
                 let raw_len = T::skip_nbt(&mut skip_cursor, len)?;
                 Ok(Self::Raw(cursor.take_bytes(raw_len)?))
             },
-            size => Ok(Self::Raw(cursor.take_bytes(size * len)?))
+            size => Ok(Self::Raw(cursor.take_bytes(array_byte_length(len, size)?)?))
         }
     }
 
@@ -103,6 +122,9 @@ pub const NBT_TAG_COMPOUND: u8 = 10;
 pub const NBT_TAG_INT_ARRAY: u8 = 11;
 pub const NBT_TAG_LONG_ARRAY: u8 = 12;
 
+/// Maximum nesting of lists and compounds in [`NbtValue`] and SNBT, as in vanilla
+pub const NBT_MAX_DEPTH: usize = 512;
+
 pub trait NbtTagVariant<'a, T> {
     fn default_nbt_variant_value() -> Option<T> {
         None
@@ -122,6 +144,17 @@ pub trait NbtTagVariant<'a, T> {
     fn read_nbt_variant<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<T>;
 
     fn skip_nbt_variant<C: ProtocolCursor<'a>>(cursor: &mut C, amount: usize) -> ProtocolResult<usize>;
+
+    /// Reads the value when its tag is already read
+    #[allow(unused_variables)]
+    fn read_nbt_variant_of<C: ProtocolCursor<'a>>(tag: u8, cursor: &mut C) -> ProtocolResult<T> {
+        Self::read_nbt_variant(cursor)
+    }
+
+    #[allow(unused_variables)]
+    fn skip_nbt_variant_of<C: ProtocolCursor<'a>>(tag: u8, cursor: &mut C, amount: usize) -> ProtocolResult<usize> {
+        Self::skip_nbt_variant(cursor, amount)
+    }
 }
 
 pub trait NbtTag<'a>: Sized {
@@ -140,6 +173,26 @@ pub trait NbtTag<'a>: Sized {
     fn read_nbt<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self>;
 
     fn skip_nbt<C: ProtocolCursor<'a>>(cursor: &mut C, amount: usize) -> ProtocolResult<usize>;
+
+    /// Tag of the value. It differs from `NBT_TAG` only for values with dynamic tag
+    fn nbt_tag(&self) -> u8 {
+        Self::NBT_TAG
+    }
+
+    fn check_nbt_tag(tag: u8) -> bool {
+        tag == Self::NBT_TAG
+    }
+
+    /// Reads the value when its tag is already read
+    #[allow(unused_variables)]
+    fn read_nbt_of<C: ProtocolCursor<'a>>(tag: u8, cursor: &mut C) -> ProtocolResult<Self> {
+        Self::read_nbt(cursor)
+    }
+
+    #[allow(unused_variables)]
+    fn skip_nbt_of<C: ProtocolCursor<'a>>(tag: u8, cursor: &mut C, amount: usize) -> ProtocolResult<usize> {
+        Self::skip_nbt(cursor, amount)
+    }
 }
 
 impl<'a, T: NbtTag<'a>> NbtTagVariant<'a, T> for T {
@@ -151,8 +204,8 @@ impl<'a, T: NbtTag<'a>> NbtTagVariant<'a, T> for T {
         value.should_write_nbt()
     }
 
-    fn get_nbt_tag(_: &T) -> anyhow::Result<u8> {
-        Ok(Self::NBT_TAG)
+    fn get_nbt_tag(value: &T) -> anyhow::Result<u8> {
+        Ok(value.nbt_tag())
     }
 
     fn write_nbt_variant<W: ProtocolWriter>(value: &T, writer: &mut W) -> anyhow::Result<()> {
@@ -160,7 +213,7 @@ impl<'a, T: NbtTag<'a>> NbtTagVariant<'a, T> for T {
     }
 
     fn check_nbt_tag(tag: u8) -> bool {
-        tag == Self::NBT_TAG
+        <T as NbtTag<'a>>::check_nbt_tag(tag)
     }
 
     fn read_nbt_variant<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<T> {
@@ -170,6 +223,14 @@ impl<'a, T: NbtTag<'a>> NbtTagVariant<'a, T> for T {
     fn skip_nbt_variant<C: ProtocolCursor<'a>>(cursor: &mut C, amount: usize) -> ProtocolResult<usize> {
         T::skip_nbt(cursor, amount)
     }
+
+    fn read_nbt_variant_of<C: ProtocolCursor<'a>>(tag: u8, cursor: &mut C) -> ProtocolResult<T> {
+        T::read_nbt_of(tag, cursor)
+    }
+
+    fn skip_nbt_variant_of<C: ProtocolCursor<'a>>(tag: u8, cursor: &mut C, amount: usize) -> ProtocolResult<usize> {
+        T::skip_nbt_of(tag, cursor, amount)
+    }
 }
 
 macro_rules! inherit_from_default_protocol {
@@ -211,11 +272,11 @@ inherit_from_default_protocol!(
 );
 
 pub fn write_nbt_str<W: ProtocolWriter>(str: &str, writer: &mut W) -> anyhow::Result<()> {
-    (str.len() as u16).write_nbt(writer)?;
-    match cesu8::to_java_cesu8(str) {
-        Cow::Owned(owned) => writer.write_bytes(&owned),
-        Cow::Borrowed(borrowed) => writer.write_bytes(borrowed),
-    }
+    // the length is the length of the encoded string, which can be longer than the length of the utf-8 one
+    let bytes = cesu8::to_java_cesu8(str);
+    let length = u16::try_from(bytes.len()).map_err(|_| anyhow::Error::msg("Too big string"))?;
+    length.write_nbt(writer)?;
+    writer.write_bytes(&bytes);
     Ok(())
 }
 
@@ -324,8 +385,13 @@ impl<'a, T: NbtTag<'a>> NbtTag<'a> for Vec<T> {
     const NBT_TAG: u8 = NBT_TAG_LIST;
 
     fn write_nbt<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
-        T::NBT_TAG.write_nbt(writer)?;
-        (self.len() as i32).write_nbt(writer)?;
+        // the tag of elements is taken from the first one if elements have dynamic tags
+        let tag = self.first().map(T::nbt_tag).unwrap_or(T::NBT_TAG);
+        if self.iter().any(|element| element.nbt_tag() != tag) {
+            return Err(anyhow::Error::msg("Nbt list contains elements of different types"));
+        }
+        tag.write_nbt(writer)?;
+        i32::try_from(self.len()).map_err(|_| anyhow::Error::msg("Too big nbt list"))?.write_nbt(writer)?;
         for tag in self { tag.write_nbt(writer)? }
         Ok(())
     }
@@ -334,10 +400,11 @@ impl<'a, T: NbtTag<'a>> NbtTag<'a> for Vec<T> {
         let tag = u8::read_nbt(cursor)?;
         let len = i32::read_nbt(cursor)?;
         if len <= 0 { return Ok(Vec::new()) }
-        if tag != T::NBT_TAG { return Err(ProtocolError::Any(anyhow::Error::msg("Bad nbt tag"))) }
+        if !T::check_nbt_tag(tag) { return Err(ProtocolError::Any(anyhow::Error::msg("Bad nbt tag"))) }
+        cursor.reserve_allocation(len as usize, std::mem::size_of::<T>())?;
         let mut result = Vec::new();
         for _ in 0..len {
-            result.push(T::read_nbt(cursor)?);
+            result.push(T::read_nbt_of(tag, cursor)?);
         }
         Ok(result)
     }
@@ -348,13 +415,50 @@ impl<'a, T: NbtTag<'a>> NbtTag<'a> for Vec<T> {
             let tag = u8::read_nbt(cursor)?;
             let len = i32::read_nbt(cursor)?;
             if len <= 0 { result += 5; continue; }
-            if tag != T::NBT_TAG { return Err(ProtocolError::Any(anyhow::Error::msg("Bad nbt tag"))) }
-            result += 5 + T::skip_nbt(cursor, len as _)?;
+            if !T::check_nbt_tag(tag) { return Err(ProtocolError::Any(anyhow::Error::msg("Bad nbt tag"))) }
+            result += 5 + T::skip_nbt_of(tag, cursor, len as _)?;
         }
         Ok(result)
     }
 }
 
+impl<'a, T: NbtTag<'a>> NbtTag<'a> for HashMap<Cow<'a, str>, T> {
+    const NBT_TAG: u8 = NBT_TAG_COMPOUND;
+
+    fn write_nbt<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        for (name, value) in self {
+            if value.should_write_nbt() {
+                value.nbt_tag().write_nbt(writer)?;
+                write_nbt_str(name, writer)?;
+                value.write_nbt(writer)?;
+            }
+        }
+        NBT_TAG_END.write_nbt(writer)
+    }
+
+    fn read_nbt<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let mut result = HashMap::new();
+        compound::read_nbt_compound(cursor, |tag, name, cursor| {
+            if !T::check_nbt_tag(tag) { return Err(ProtocolError::Any(anyhow::Error::msg("Bad nbt tag"))) }
+            cursor.reserve_allocation(1, std::mem::size_of::<(Cow<str>, T)>())?;
+            result.insert(name, T::read_nbt_of(tag, cursor)?);
+            Ok(())
+        })?;
+        Ok(result)
+    }
+
+    fn skip_nbt<C: ProtocolCursor<'a>>(cursor: &mut C, amount: usize) -> ProtocolResult<usize> {
+        let start = cursor.remaining_bytes();
+        for _ in 0..amount {
+            compound::read_nbt_compound(cursor, |tag, _, cursor| {
+                if !T::check_nbt_tag(tag) { return Err(ProtocolError::Any(anyhow::Error::msg("Bad nbt tag"))) }
+                T::skip_nbt_of(tag, cursor, 1).map(|_| ())
+            })?;
+        }
+        Ok(start - cursor.remaining_bytes())
+    }
+}
+
 pub struct NbtByteArray;
 pub struct NbtIntArray;
 pub struct NbtLongArray;
@@ -553,16 +657,31 @@ pub mod compound {
         Ok(())
     }
 
+    /// Reads the tag and the name of the root compound and returns the name
+    pub fn read_nbt_root<'a, C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Cow<'a, str>> {
+        if u8::read_nbt(cursor)? != NBT_TAG_COMPOUND {
+            return Err(ProtocolError::Any(anyhow::Error::msg("Root nbt tag is not a compound")));
+        }
+        Cow::read_nbt(cursor)
+    }
+
+    /// Writes the tag and the name of the root compound. Then the compound should be written
+    pub fn write_nbt_root<W: ProtocolWriter>(name: &str, writer: &mut W) -> anyhow::Result<()> {
+        NBT_TAG_COMPOUND.write_nbt(writer)?;
+        write_nbt_str(name, writer)
+    }
+
     #[macro_export]
     macro_rules! write_compound {
         ($writer: ident, $($name: expr => $ty: ty, $val: expr$(,)*)*) => {
             $(
             if $val.should_write_nbt() {
+                <$ty>::nbt_tag(&$val).write_nbt($writer)?;
                 write_nbt_str($name, $writer)?;
-                <$ty>::NBT_TAG.write_nbt($writer)?;
                 $val.write_nbt($writer)?;
             }
             )*
+            NBT_TAG_END.write_nbt($writer)?;
         }
     }
 
@@ -579,6 +698,22 @@ impl<'a, T: NbtTag<'a>> NbtTag<'a> for Option<T> {
         self.is_some()
     }
 
+    fn nbt_tag(&self) -> u8 {
+        self.as_ref().map(T::nbt_tag).unwrap_or(T::NBT_TAG)
+    }
+
+    fn check_nbt_tag(tag: u8) -> bool {
+        T::check_nbt_tag(tag)
+    }
+
+    fn read_nbt_of<C: ProtocolCursor<'a>>(tag: u8, cursor: &mut C) -> ProtocolResult<Self> {
+        T::read_nbt_of(tag, cursor).map(Some)
+    }
+
+    fn skip_nbt_of<C: ProtocolCursor<'a>>(tag: u8, cursor: &mut C, amount: usize) -> ProtocolResult<usize> {
+        T::skip_nbt_of(tag, cursor, amount)
+    }
+
     fn write_nbt<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
         match self {
             Some(value) => value.write_nbt(writer),
@@ -649,4 +784,34 @@ impl<'a, T: NbtTag<'a>, U> NbtTag<'a> for Vector3D<T, U> {
         }
         Ok(result)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borrowed_array_into_owned_test() {
+        let raw: NbtBorrowedI32Array = NbtBorrowedArray::Raw(&[0, 0, 0, 1, 0xFF, 0xFF, 0xFF, 0xFE]);
+        assert_eq!(raw.into_owned(), vec![1, -2]);
+        let native: NbtBorrowedI64Array = NbtBorrowedArray::Native(&[i64::MIN, 3]);
+        assert_eq!(native.into_owned(), vec![i64::MIN, 3]);
+    }
+
+    #[test]
+    fn write_compound_test() {
+        // every entry is the tag, the name and the value, and the compound ends with the end tag
+        let position: Vector3D<i32, ()> = Vector3D::new(1, 2, -1);
+        let mut bytes = Vec::new();
+        position.write_nbt(&mut bytes).unwrap();
+        assert_eq!(bytes, [
+            NBT_TAG_INT, 0, 1, b'X', 0, 0, 0, 1,
+            NBT_TAG_INT, 0, 1, b'Y', 0, 0, 0, 2,
+            NBT_TAG_INT, 0, 1, b'Z', 0xFF, 0xFF, 0xFF, 0xFF,
+            NBT_TAG_END,
+        ]);
+        let mut cursor = bytes.as_slice();
+        assert_eq!(Vector3D::<i32, ()>::read_nbt(&mut cursor).unwrap(), position);
+        assert!(cursor.is_empty());
+    }
+}
@@ -0,0 +1,144 @@
+use std::borrow::Cow;
+use std::io::{Read, Write};
+use std::path::Path;
+use flate2::Compression;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use crate::{ProtocolError, ProtocolResult};
+use super::*;
+
+/// The biggest decompressed file which is read by default
+pub const DEFAULT_MAX_NBT_FILE_LENGTH: usize = 64 * 1024 * 1024;
+
+/// Compression of nbt files. Files of the world, such as level.dat, are compressed with gzip
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NbtFileCompression {
+    None,
+    Gzip,
+    Zlib,
+}
+
+impl NbtFileCompression {
+    /// Detects the compression by the first bytes. Uncompressed files start with the tag of the root compound
+    pub fn detect(bytes: &[u8]) -> Self {
+        match bytes {
+            [0x1f, 0x8b, ..] => Self::Gzip,
+            [cmf, flg, ..] if cmf & 0x0f == 8 && u16::from_be_bytes([*cmf, *flg]).rem_euclid(31) == 0 => Self::Zlib,
+            _ => Self::None,
+        }
+    }
+}
+
+/// Decompressed nbt file. Values which are read from it can borrow its bytes
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct NbtFile {
+    pub compression: NbtFileCompression,
+    pub bytes: Vec<u8>,
+}
+
+impl NbtFile {
+    pub fn decompress(bytes: Vec<u8>) -> ProtocolResult<Self> {
+        Self::decompress_with_limit(bytes, DEFAULT_MAX_NBT_FILE_LENGTH)
+    }
+
+    /// Decompresses the file which is compressed in any way. Files larger than `max_length` are not read
+    pub fn decompress_with_limit(bytes: Vec<u8>, max_length: usize) -> ProtocolResult<Self> {
+        let compression = NbtFileCompression::detect(&bytes);
+        let bytes = match compression {
+            NbtFileCompression::None => bytes,
+            NbtFileCompression::Gzip => read_limited(GzDecoder::new(bytes.as_slice()), max_length)?,
+            NbtFileCompression::Zlib => read_limited(ZlibDecoder::new(bytes.as_slice()), max_length)?,
+        };
+        match bytes.len() > max_length {
+            true => Err(ProtocolError::Any(anyhow::Error::msg(format!(
+                "Nbt file is larger than maximum of {}", max_length
+            )))),
+            false => Ok(Self { compression, bytes }),
+        }
+    }
+
+    pub fn read_from<R: Read>(mut reader: R) -> ProtocolResult<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).map_err(anyhow::Error::from)?;
+        Self::decompress(bytes)
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> ProtocolResult<Self> {
+        Self::decompress(std::fs::read(path).map_err(anyhow::Error::from)?)
+    }
+
+    /// Reads the name of the root compound and the root compound as any [`NbtTag`] compound,
+    /// for example [`NbtValue`] or a type with the derived implementation
+    pub fn read_root<'a, T: NbtTag<'a>>(&'a self) -> ProtocolResult<(Cow<'a, str>, T)> {
+        if !T::check_nbt_tag(NBT_TAG_COMPOUND) {
+            return Err(ProtocolError::Any(anyhow::Error::msg("Root nbt tag is always a compound")));
+        }
+        let mut cursor = self.bytes.as_slice();
+        let name = compound::read_nbt_root(&mut cursor)?;
+        let root = T::read_nbt_of(NBT_TAG_COMPOUND, &mut cursor)?;
+        Ok((name, root))
+    }
+}
+
+/// Reads at most one byte more than `max_length`, so too large files are detected without reading them fully
+fn read_limited<R: Read>(reader: R, max_length: usize) -> ProtocolResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.take(max_length as u64 + 1).read_to_end(&mut bytes).map_err(anyhow::Error::from)?;
+    Ok(bytes)
+}
+
+/// Writes the named root compound and compresses it
+pub fn write_nbt_file<'a, T: NbtTag<'a>, W: Write>(
+    mut writer: W, compression: NbtFileCompression, name: &str, root: &T,
+) -> anyhow::Result<()> {
+    if root.nbt_tag() != NBT_TAG_COMPOUND {
+        return Err(anyhow::Error::msg("Root nbt tag is always a compound"));
+    }
+    let mut bytes = Vec::new();
+    compound::write_nbt_root(name, &mut bytes)?;
+    root.write_nbt(&mut bytes)?;
+    match compression {
+        NbtFileCompression::None => writer.write_all(&bytes)?,
+        NbtFileCompression::Gzip => {
+            let mut encoder = GzEncoder::new(writer, Compression::default());
+            encoder.write_all(&bytes)?;
+            encoder.finish()?;
+        }
+        NbtFileCompression::Zlib => {
+            let mut encoder = ZlibEncoder::new(writer, Compression::default());
+            encoder.write_all(&bytes)?;
+            encoder.finish()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use euclid::default::Vector3D;
+    use super::*;
+
+    #[test]
+    fn nbt_file_round_trip_test() {
+        let root = NbtValue::Compound(HashMap::from([
+            (Cow::Borrowed("Data"), NbtValue::Compound(HashMap::from([
+                (Cow::Borrowed("LevelName"), NbtValue::String(Cow::Borrowed("world"))),
+            ]))),
+        ]));
+        for compression in [NbtFileCompression::None, NbtFileCompression::Gzip, NbtFileCompression::Zlib] {
+            let mut bytes = Vec::new();
+            write_nbt_file(&mut bytes, compression, "", &root).unwrap();
+            let file = NbtFile::decompress(bytes).unwrap();
+            assert_eq!(file.compression, compression);
+            assert_eq!(file.read_root().unwrap(), (Cow::Borrowed(""), root.clone()));
+        }
+        let mut bytes = Vec::new();
+        write_nbt_file(&mut bytes, NbtFileCompression::Gzip, "pos", &Vector3D::new(1, 2, 3)).unwrap();
+        let file = NbtFile::read_from(bytes.as_slice()).unwrap();
+        assert_eq!(file.read_root().unwrap(), (Cow::Borrowed("pos"), Vector3D::new(1, 2, 3)));
+        assert!(file.read_root::<i32>().is_err());
+        assert!(NbtFile::decompress_with_limit(bytes, 8).is_err());
+        assert!(write_nbt_file(Vec::new(), NbtFileCompression::None, "", &NbtValue::Int(1)).is_err());
+    }
+}
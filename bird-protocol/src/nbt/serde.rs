@@ -0,0 +1,631 @@
+//! Serde data format of nbt. Values are serialized into [`NbtValue`] and deserialized from it,
+//! so deserialized values can borrow strings and byte arrays of the read bytes
+use std::borrow::Cow;
+use std::collections::{hash_map, HashMap};
+use std::fmt::Display;
+use ::serde::{de, ser, Deserialize, Serialize};
+use ::serde::de::{DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor};
+use crate::{ProtocolCursor, ProtocolError, ProtocolResult, ProtocolWriter};
+use super::*;
+
+const BYTE_ARRAY_NAME: &str = "__bird_nbt_byte_array";
+const INT_ARRAY_NAME: &str = "__bird_nbt_int_array";
+const LONG_ARRAY_NAME: &str = "__bird_nbt_long_array";
+
+#[derive(thiserror::Error, Debug)]
+#[error("{0}")]
+pub struct NbtSerdeError(String);
+
+pub type NbtSerdeResult<T> = Result<T, NbtSerdeError>;
+
+impl ser::Error for NbtSerdeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+impl de::Error for NbtSerdeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+macro_rules! nbt_array_serde {
+    ($($module: ident = $name: ident, $doc: literal$(,)*)*) => {
+        $(
+        #[doc = $doc]
+        pub mod $module {
+            use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+            pub fn serialize<T: Serialize + ?Sized, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_newtype_struct(super::$name, value)
+            }
+
+            pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+                T::deserialize(deserializer)
+            }
+        }
+        )*
+    }
+}
+
+nbt_array_serde!(
+    byte_array = BYTE_ARRAY_NAME, "Serializes a sequence of bytes as the byte array tag: `#[serde(with = \"byte_array\")]`",
+    int_array = INT_ARRAY_NAME, "Serializes a sequence of ints as the int array tag: `#[serde(with = \"int_array\")]`",
+    long_array = LONG_ARRAY_NAME, "Serializes a sequence of longs as the long array tag: `#[serde(with = \"long_array\")]`",
+);
+
+pub fn to_nbt_value<T: Serialize + ?Sized>(value: &T) -> NbtSerdeResult<NbtValue<'static>> {
+    value.serialize(NbtValueSerializer)?
+        .ok_or_else(|| NbtSerdeError("None can not be serialized as nbt".into()))
+}
+
+pub fn from_nbt_value<'a, T: Deserialize<'a>>(value: NbtValue<'a>) -> NbtSerdeResult<T> {
+    T::deserialize(value)
+}
+
+/// Writes the value as the root compound with the empty name as in the protocol
+pub fn write_nbt_serde<T: Serialize + ?Sized, W: ProtocolWriter>(value: &T, writer: &mut W) -> anyhow::Result<()> {
+    let value = to_nbt_value(value)?;
+    if value.tag() != NBT_TAG_COMPOUND {
+        return Err(anyhow::Error::msg("Root nbt tag is always a compound"));
+    }
+    compound::write_nbt_root("", writer)?;
+    value.write_nbt(writer)
+}
+
+/// Reads the root compound and deserializes the value from it
+pub fn read_nbt_serde<'a, T: Deserialize<'a>, C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<T> {
+    compound::read_nbt_root(cursor)?;
+    let value = NbtValue::read_nbt_of(NBT_TAG_COMPOUND, cursor)?;
+    from_nbt_value(value).map_err(|err| ProtocolError::Any(err.into()))
+}
+
+/// Converts the list which was serialized inside of the array newtype into the array
+fn into_nbt_array(name: &'static str, value: Option<NbtValue<'static>>) -> NbtSerdeResult<NbtValue<'static>> {
+    let list = match value {
+        Some(NbtValue::List(list)) => list,
+        Some(NbtValue::ByteArray(array)) if name == BYTE_ARRAY_NAME => return Ok(NbtValue::ByteArray(array)),
+        _ => return Err(NbtSerdeError(format!("{} must be serialized from a sequence", name))),
+    };
+    let not_integer = || NbtSerdeError(format!("{} contains values of other type", name));
+    let list = list.into_iter();
+    Ok(match name {
+        BYTE_ARRAY_NAME => NbtValue::ByteArray(Cow::Owned(list
+            .map(|value| match value {
+                NbtValue::Byte(value) => Ok(value as u8),
+                _ => Err(not_integer()),
+            })
+            .collect::<NbtSerdeResult<_>>()?)),
+        INT_ARRAY_NAME => NbtValue::IntArray(Cow::Owned(list
+            .map(|value| match value {
+                NbtValue::Int(value) => Ok(value),
+                _ => Err(not_integer()),
+            })
+            .collect::<NbtSerdeResult<_>>()?)),
+        _ => NbtValue::LongArray(Cow::Owned(list
+            .map(|value| match value {
+                NbtValue::Long(value) => Ok(value),
+                _ => Err(not_integer()),
+            })
+            .collect::<NbtSerdeResult<_>>()?)),
+    })
+}
+
+fn nbt_variant(variant: &'static str, value: NbtValue<'static>) -> NbtValue<'static> {
+    NbtValue::Compound(HashMap::from([(Cow::Borrowed(variant), value)]))
+}
+
+/// Serializes into the nbt value. None is returned for `None` options, so they are skipped in compounds
+struct NbtValueSerializer;
+
+impl ser::Serializer for NbtValueSerializer {
+    type Ok = Option<NbtValue<'static>>;
+    type Error = NbtSerdeError;
+    type SerializeSeq = NbtListSerializer;
+    type SerializeTuple = NbtListSerializer;
+    type SerializeTupleStruct = NbtListSerializer;
+    type SerializeTupleVariant = NbtVariantSerializer<NbtListSerializer>;
+    type SerializeMap = NbtCompoundSerializer;
+    type SerializeStruct = NbtCompoundSerializer;
+    type SerializeStructVariant = NbtVariantSerializer<NbtCompoundSerializer>;
+
+    fn serialize_bool(self, v: bool) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(NbtValue::Byte(v as i8)))
+    }
+
+    fn serialize_i8(self, v: i8) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(NbtValue::Byte(v)))
+    }
+
+    fn serialize_i16(self, v: i16) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(NbtValue::Short(v)))
+    }
+
+    fn serialize_i32(self, v: i32) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(NbtValue::Int(v)))
+    }
+
+    fn serialize_i64(self, v: i64) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(NbtValue::Long(v)))
+    }
+
+    fn serialize_u8(self, v: u8) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(NbtValue::Byte(v as i8)))
+    }
+
+    fn serialize_u16(self, v: u16) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(NbtValue::Short(v as i16)))
+    }
+
+    fn serialize_u32(self, v: u32) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(NbtValue::Int(v as i32)))
+    }
+
+    fn serialize_u64(self, v: u64) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(NbtValue::Long(v as i64)))
+    }
+
+    fn serialize_f32(self, v: f32) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(NbtValue::Float(v)))
+    }
+
+    fn serialize_f64(self, v: f64) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(NbtValue::Double(v)))
+    }
+
+    fn serialize_char(self, v: char) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(NbtValue::String(Cow::Owned(v.to_string()))))
+    }
+
+    fn serialize_str(self, v: &str) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(NbtValue::String(Cow::Owned(v.to_string()))))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(NbtValue::ByteArray(Cow::Owned(v.to_vec()))))
+    }
+
+    fn serialize_none(self) -> NbtSerdeResult<Self::Ok> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> NbtSerdeResult<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> NbtSerdeResult<Self::Ok> {
+        Err(NbtSerdeError("Unit can not be serialized as nbt".into()))
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(NbtValue::Compound(HashMap::new())))
+    }
+
+    fn serialize_unit_variant(self, _: &'static str, _: u32, variant: &'static str) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(NbtValue::String(Cow::Borrowed(variant))))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, name: &'static str, value: &T) -> NbtSerdeResult<Self::Ok> {
+        match name {
+            BYTE_ARRAY_NAME | INT_ARRAY_NAME | LONG_ARRAY_NAME => into_nbt_array(name, value.serialize(self)?).map(Some),
+            _ => value.serialize(self),
+        }
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self, _: &'static str, _: u32, variant: &'static str, value: &T,
+    ) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(nbt_variant(variant, to_nbt_value(value)?)))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> NbtSerdeResult<Self::SerializeSeq> {
+        Ok(NbtListSerializer(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, len: usize) -> NbtSerdeResult<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, len: usize) -> NbtSerdeResult<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self, _: &'static str, _: u32, variant: &'static str, len: usize,
+    ) -> NbtSerdeResult<Self::SerializeTupleVariant> {
+        Ok(NbtVariantSerializer { variant, inner: NbtListSerializer(Vec::with_capacity(len)) })
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> NbtSerdeResult<Self::SerializeMap> {
+        Ok(NbtCompoundSerializer { compound: HashMap::new(), key: None })
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> NbtSerdeResult<Self::SerializeStruct> {
+        self.serialize_map(None)
+    }
+
+    fn serialize_struct_variant(
+        self, _: &'static str, _: u32, variant: &'static str, _: usize,
+    ) -> NbtSerdeResult<Self::SerializeStructVariant> {
+        Ok(NbtVariantSerializer { variant, inner: NbtCompoundSerializer { compound: HashMap::new(), key: None } })
+    }
+}
+
+struct NbtListSerializer(Vec<NbtValue<'static>>);
+
+impl ser::SerializeSeq for NbtListSerializer {
+    type Ok = Option<NbtValue<'static>>;
+    type Error = NbtSerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtSerdeResult<()> {
+        let value = to_nbt_value(value)?;
+        if self.0.first().map(|first| first.tag() != value.tag()).unwrap_or(false) {
+            return Err(NbtSerdeError("Nbt list contains elements of different types".into()));
+        }
+        self.0.push(value);
+        Ok(())
+    }
+
+    fn end(self) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(NbtValue::List(self.0)))
+    }
+}
+
+impl ser::SerializeTuple for NbtListSerializer {
+    type Ok = Option<NbtValue<'static>>;
+    type Error = NbtSerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtSerdeResult<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> NbtSerdeResult<Self::Ok> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for NbtListSerializer {
+    type Ok = Option<NbtValue<'static>>;
+    type Error = NbtSerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtSerdeResult<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> NbtSerdeResult<Self::Ok> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+struct NbtCompoundSerializer {
+    compound: NbtCompound<'static>,
+    key: Option<Cow<'static, str>>,
+}
+
+impl ser::SerializeMap for NbtCompoundSerializer {
+    type Ok = Option<NbtValue<'static>>;
+    type Error = NbtSerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> NbtSerdeResult<()> {
+        match to_nbt_value(key)? {
+            NbtValue::String(key) => {
+                self.key = Some(key);
+                Ok(())
+            }
+            _ => Err(NbtSerdeError("Keys of nbt compounds must be strings".into())),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtSerdeResult<()> {
+        let key = self.key.take().ok_or_else(|| NbtSerdeError("Value is serialized before its key".into()))?;
+        if let Some(value) = value.serialize(NbtValueSerializer)? {
+            self.compound.insert(key, value);
+        }
+        Ok(())
+    }
+
+    fn end(self) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(NbtValue::Compound(self.compound)))
+    }
+}
+
+impl ser::SerializeStruct for NbtCompoundSerializer {
+    type Ok = Option<NbtValue<'static>>;
+    type Error = NbtSerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> NbtSerdeResult<()> {
+        if let Some(value) = value.serialize(NbtValueSerializer)? {
+            self.compound.insert(Cow::Borrowed(key), value);
+        }
+        Ok(())
+    }
+
+    fn end(self) -> NbtSerdeResult<Self::Ok> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+/// Variants with data are compounds with the only entry
+struct NbtVariantSerializer<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl ser::SerializeTupleVariant for NbtVariantSerializer<NbtListSerializer> {
+    type Ok = Option<NbtValue<'static>>;
+    type Error = NbtSerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtSerdeResult<()> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(nbt_variant(self.variant, NbtValue::List(self.inner.0))))
+    }
+}
+
+impl ser::SerializeStructVariant for NbtVariantSerializer<NbtCompoundSerializer> {
+    type Ok = Option<NbtValue<'static>>;
+    type Error = NbtSerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> NbtSerdeResult<()> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> NbtSerdeResult<Self::Ok> {
+        Ok(Some(nbt_variant(self.variant, NbtValue::Compound(self.inner.compound))))
+    }
+}
+
+impl<'de> de::Deserializer<'de> for NbtValue<'de> {
+    type Error = NbtSerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> NbtSerdeResult<V::Value> {
+        match self {
+            NbtValue::Byte(value) => visitor.visit_i8(value),
+            NbtValue::Short(value) => visitor.visit_i16(value),
+            NbtValue::Int(value) => visitor.visit_i32(value),
+            NbtValue::Long(value) => visitor.visit_i64(value),
+            NbtValue::Float(value) => visitor.visit_f32(value),
+            NbtValue::Double(value) => visitor.visit_f64(value),
+            NbtValue::ByteArray(array) => visitor.visit_seq(NbtSeqAccess(
+                array.into_owned().into_iter().map(|value| NbtValue::Byte(value as i8))
+            )),
+            NbtValue::String(Cow::Borrowed(str)) => visitor.visit_borrowed_str(str),
+            NbtValue::String(Cow::Owned(str)) => visitor.visit_string(str),
+            NbtValue::List(list) => visitor.visit_seq(NbtSeqAccess(list.into_iter())),
+            NbtValue::Compound(compound) => visitor.visit_map(NbtMapAccess { entries: compound.into_iter(), value: None }),
+            NbtValue::IntArray(array) => visitor.visit_seq(NbtSeqAccess(array.into_owned().into_iter().map(NbtValue::Int))),
+            NbtValue::LongArray(array) => visitor.visit_seq(NbtSeqAccess(array.into_owned().into_iter().map(NbtValue::Long))),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> NbtSerdeResult<V::Value> {
+        match self {
+            NbtValue::Byte(value) => visitor.visit_bool(value != 0),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    // unsigned numbers are serialized with the same bits as signed ones
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> NbtSerdeResult<V::Value> {
+        match self {
+            NbtValue::Byte(value) => visitor.visit_u8(value as u8),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> NbtSerdeResult<V::Value> {
+        match self {
+            NbtValue::Short(value) => visitor.visit_u16(value as u16),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> NbtSerdeResult<V::Value> {
+        match self {
+            NbtValue::Int(value) => visitor.visit_u32(value as u32),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> NbtSerdeResult<V::Value> {
+        match self {
+            NbtValue::Long(value) => visitor.visit_u64(value as u64),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> NbtSerdeResult<V::Value> {
+        match self {
+            NbtValue::ByteArray(Cow::Borrowed(bytes)) => visitor.visit_borrowed_bytes(bytes),
+            NbtValue::ByteArray(Cow::Owned(bytes)) => visitor.visit_byte_buf(bytes),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> NbtSerdeResult<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    /// There is no null in nbt, so options which are present are always some
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> NbtSerdeResult<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _: &'static str, visitor: V) -> NbtSerdeResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self, _: &'static str, _: &'static [&'static str], visitor: V,
+    ) -> NbtSerdeResult<V::Value> {
+        match self {
+            NbtValue::String(variant) => visitor.visit_enum(NbtEnumAccess { variant, value: None }),
+            NbtValue::Compound(compound) if compound.len() == 1 => {
+                let (variant, value) = compound.into_iter().next().unwrap();
+                visitor.visit_enum(NbtEnumAccess { variant, value: Some(value) })
+            }
+            _ => Err(NbtSerdeError("Enum must be a string or a compound with the only entry".into())),
+        }
+    }
+
+    ::serde::forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u128 f32 f64 char str string unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+struct NbtSeqAccess<I>(I);
+
+impl<'de, I: ExactSizeIterator<Item=NbtValue<'de>>> SeqAccess<'de> for NbtSeqAccess<I> {
+    type Error = NbtSerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> NbtSerdeResult<Option<T::Value>> {
+        self.0.next().map(|value| seed.deserialize(value)).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct NbtMapAccess<'de> {
+    entries: hash_map::IntoIter<Cow<'de, str>, NbtValue<'de>>,
+    value: Option<NbtValue<'de>>,
+}
+
+impl<'de> MapAccess<'de> for NbtMapAccess<'de> {
+    type Error = NbtSerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> NbtSerdeResult<Option<K::Value>> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(NbtValue::String(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> NbtSerdeResult<V::Value> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(value),
+            None => Err(NbtSerdeError("Value is deserialized before its key".into())),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct NbtEnumAccess<'de> {
+    variant: Cow<'de, str>,
+    value: Option<NbtValue<'de>>,
+}
+
+impl<'de> EnumAccess<'de> for NbtEnumAccess<'de> {
+    type Error = NbtSerdeError;
+    type Variant = NbtVariantAccess<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> NbtSerdeResult<(V::Value, Self::Variant)> {
+        Ok((seed.deserialize(NbtValue::String(self.variant))?, NbtVariantAccess(self.value)))
+    }
+}
+
+struct NbtVariantAccess<'de>(Option<NbtValue<'de>>);
+
+impl<'de> VariantAccess<'de> for NbtVariantAccess<'de> {
+    type Error = NbtSerdeError;
+
+    fn unit_variant(self) -> NbtSerdeResult<()> {
+        match self.0 {
+            None => Ok(()),
+            Some(_) => Err(NbtSerdeError("Unit variant must be a string".into())),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> NbtSerdeResult<T::Value> {
+        match self.0 {
+            Some(value) => seed.deserialize(value),
+            None => Err(NbtSerdeError("Newtype variant must be a compound".into())),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _: usize, visitor: V) -> NbtSerdeResult<V::Value> {
+        match self.0 {
+            Some(value) => de::Deserializer::deserialize_any(value, visitor),
+            None => Err(NbtSerdeError("Tuple variant must be a compound".into())),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _: &'static [&'static str], visitor: V) -> NbtSerdeResult<V::Value> {
+        self.tuple_variant(0, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::serde::{Deserialize, Serialize};
+    use super::*;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum TestEnum<'a> {
+        Unit,
+        Newtype(i16),
+        Struct {
+            #[serde(borrow)]
+            name: Cow<'a, str>,
+        },
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct TestSerde<'a> {
+        #[serde(borrow)]
+        name: Cow<'a, str>,
+        flag: bool,
+        unsigned: u32,
+        missing: Option<f64>,
+        present: Option<f32>,
+        #[serde(with = "int_array")]
+        ints: Vec<i32>,
+        #[serde(with = "long_array")]
+        longs: Vec<i64>,
+        list: Vec<HashMap<String, u8>>,
+        enums: Vec<TestEnum<'a>>,
+    }
+
+    #[test]
+    fn nbt_serde_round_trip_test() {
+        let value = TestSerde {
+            name: Cow::Borrowed("name"),
+            flag: true,
+            unsigned: u32::MAX,
+            missing: None,
+            present: Some(0.5),
+            ints: vec![1, 2],
+            longs: vec![],
+            list: vec![HashMap::from([("a".into(), 200)])],
+            enums: vec![TestEnum::Newtype(1), TestEnum::Struct { name: Cow::Borrowed("x") }],
+        };
+        let mut bytes = Vec::new();
+        write_nbt_serde(&value, &mut bytes).unwrap();
+        let mut cursor = bytes.as_slice();
+        let read: TestSerde = read_nbt_serde(&mut cursor).unwrap();
+        assert!(cursor.is_empty());
+        assert_eq!(read, value);
+        assert!(matches!(read.name, Cow::Borrowed(_)));
+        let tree = to_nbt_value(&value).unwrap();
+        let NbtValue::Compound(ref compound) = tree else { panic!("Struct is not a compound") };
+        assert_eq!(compound.get("ints"), Some(&NbtValue::IntArray(Cow::Borrowed(&[1, 2]))));
+        assert_eq!(compound.get("longs"), Some(&NbtValue::LongArray(Cow::Borrowed(&[]))));
+        assert_eq!(compound.get("missing"), None);
+        assert!(write_nbt_serde(&1, &mut Vec::new()).is_err());
+        assert_eq!(from_nbt_value::<TestEnum>(to_nbt_value(&TestEnum::Unit).unwrap()).unwrap(), TestEnum::Unit);
+        assert!(to_nbt_value(&[TestEnum::Unit, TestEnum::Newtype(1)]).is_err());
+        assert!(to_nbt_value(&(1, "a")).is_err());
+    }
+}
@@ -0,0 +1,465 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Write};
+use crate::{ProtocolError, ProtocolResult};
+use super::*;
+
+/// Characters of strings which can be written without quotes
+fn is_unquoted_char(char: char) -> bool {
+    char.is_ascii_alphanumeric() || matches!(char, '_' | '-' | '.' | '+')
+}
+
+fn is_number_like(str: &str) -> bool {
+    str.bytes().any(|byte| byte.is_ascii_digit())
+        && str.bytes().all(|byte| byte.is_ascii_digit() || matches!(byte, b'+' | b'-' | b'.' | b'e' | b'E'))
+}
+
+fn snbt_error(position: usize, message: impl Display) -> ProtocolError {
+    ProtocolError::Any(anyhow::Error::msg(format!("{} at {}", message, position)))
+}
+
+struct SnbtParser<'a> {
+    snbt: &'a str,
+    position: usize,
+    depth: usize,
+}
+
+impl<'a> SnbtParser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.snbt[self.position..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.rest().chars().next()
+    }
+
+    fn expect(&mut self, expected: char) -> ProtocolResult<()> {
+        match self.peek() {
+            Some(char) if char == expected => {
+                self.position += char.len_utf8();
+                Ok(())
+            }
+            _ => Err(snbt_error(self.position, format_args!("Expected '{}'", expected))),
+        }
+    }
+
+    /// Returns true and skips the separator if the next character is it
+    fn separator(&mut self, separator: char) -> bool {
+        let found = self.peek() == Some(separator);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn parse_unquoted(&mut self) -> ProtocolResult<&'a str> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let length = rest.find(|char| !is_unquoted_char(char)).unwrap_or(rest.len());
+        if length == 0 {
+            return Err(snbt_error(self.position, "Expected value"));
+        }
+        self.position += length;
+        Ok(&rest[..length])
+    }
+
+    fn parse_quoted(&mut self, quote: char) -> ProtocolResult<Cow<'a, str>> {
+        self.position += 1;
+        let start = self.position;
+        let mut result: Option<String> = None;
+        let mut chars = self.rest().char_indices();
+        while let Some((index, char)) = chars.next() {
+            match char {
+                '\\' => {
+                    let escaped = match chars.next() {
+                        Some((_, escaped @ ('\\' | '\'' | '"'))) => escaped,
+                        _ => return Err(snbt_error(start + index, "Bad escape sequence")),
+                    };
+                    result.get_or_insert_with(|| self.snbt[start..start + index].to_string()).push(escaped);
+                }
+                char if char == quote => {
+                    self.position = start + index + 1;
+                    return Ok(match result {
+                        Some(owned) => Cow::Owned(owned),
+                        None => Cow::Borrowed(&self.snbt[start..start + index]),
+                    });
+                }
+                char => if let Some(ref mut owned) = result {
+                    owned.push(char);
+                },
+            }
+        }
+        Err(snbt_error(start - 1, "Unclosed string"))
+    }
+
+    fn parse_string(&mut self) -> ProtocolResult<Cow<'a, str>> {
+        match self.peek() {
+            Some(quote @ ('"' | '\'')) => self.parse_quoted(quote),
+            _ => self.parse_unquoted().map(Cow::Borrowed),
+        }
+    }
+
+    fn parse_value(&mut self) -> ProtocolResult<NbtValue<'a>> {
+        match self.peek() {
+            Some('{') => self.parse_nested(|parser| parser.parse_compound().map(NbtValue::Compound)),
+            Some('[') => self.parse_nested(Self::parse_list),
+            Some(quote @ ('"' | '\'')) => self.parse_quoted(quote).map(NbtValue::String),
+            _ => self.parse_unquoted().map(parse_unquoted_value),
+        }
+    }
+
+    /// Lists and compounds can be nested up to [`NBT_MAX_DEPTH`]
+    fn parse_nested(&mut self, parse: impl FnOnce(&mut Self) -> ProtocolResult<NbtValue<'a>>) -> ProtocolResult<NbtValue<'a>> {
+        if self.depth == NBT_MAX_DEPTH {
+            return Err(snbt_error(self.position, "Too deep value"));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn parse_compound(&mut self) -> ProtocolResult<NbtCompound<'a>> {
+        self.expect('{')?;
+        let mut result = HashMap::new();
+        if self.separator('}') {
+            return Ok(result);
+        }
+        loop {
+            let key = self.parse_string()?;
+            self.expect(':')?;
+            result.insert(key, self.parse_value()?);
+            if !self.separator(',') {
+                break;
+            }
+        }
+        self.expect('}')?;
+        Ok(result)
+    }
+
+    fn parse_list(&mut self) -> ProtocolResult<NbtValue<'a>> {
+        self.expect('[')?;
+        let rest = self.rest().trim_start();
+        if let Some(array_type @ ('B' | 'I' | 'L')) = rest.chars().next() {
+            if rest[1..].trim_start().starts_with(';') {
+                self.expect(array_type)?;
+                self.expect(';')?;
+                return self.parse_array(array_type);
+            }
+        }
+        let mut result: Vec<NbtValue> = Vec::new();
+        if !self.separator(']') {
+            loop {
+                let position = self.position;
+                let value = self.parse_value()?;
+                if result.first().map(|first| first.tag() != value.tag()).unwrap_or(false) {
+                    return Err(snbt_error(position, "List contains values of different types"));
+                }
+                result.push(value);
+                if !self.separator(',') {
+                    break;
+                }
+            }
+            self.expect(']')?;
+        }
+        Ok(NbtValue::List(result))
+    }
+
+    /// Elements of typed arrays can be any integers which fit into the type of the array
+    fn parse_array(&mut self, array_type: char) -> ProtocolResult<NbtValue<'a>> {
+        let (min, max) = match array_type {
+            'B' => (i8::MIN as i64, i8::MAX as i64),
+            'I' => (i32::MIN as i64, i32::MAX as i64),
+            _ => (i64::MIN, i64::MAX),
+        };
+        let mut values: Vec<i64> = Vec::new();
+        if !self.separator(']') {
+            loop {
+                let position = self.position;
+                let value = match self.parse_value()? {
+                    NbtValue::Byte(value) => value as i64,
+                    NbtValue::Short(value) => value as i64,
+                    NbtValue::Int(value) => value as i64,
+                    NbtValue::Long(value) => value,
+                    _ => return Err(snbt_error(position, "Typed array contains not an integer")),
+                };
+                if value < min || value > max {
+                    return Err(snbt_error(position, "Value does not fit into the type of the array"));
+                }
+                values.push(value);
+                if !self.separator(',') {
+                    break;
+                }
+            }
+            self.expect(']')?;
+        }
+        Ok(match array_type {
+            'B' => NbtValue::ByteArray(Cow::Owned(values.into_iter().map(|value| value as i8 as u8).collect())),
+            'I' => NbtValue::IntArray(Cow::Owned(values.into_iter().map(|value| value as i32).collect())),
+            _ => NbtValue::LongArray(Cow::Owned(values)),
+        })
+    }
+}
+
+/// Numbers with suffixes, numbers, booleans or strings
+fn parse_unquoted_value(token: &str) -> NbtValue<'_> {
+    match token {
+        "true" => return NbtValue::Byte(1),
+        "false" => return NbtValue::Byte(0),
+        _ => {}
+    }
+    if is_number_like(token) {
+        if let Ok(value) = token.parse() {
+            return NbtValue::Int(value);
+        }
+        // as in vanilla, a number without a suffix is a double only if it contains a dot
+        if token.contains('.') {
+            if let Some(value) = token.parse().ok().filter(|value: &f64| value.is_finite()) {
+                return NbtValue::Double(value);
+            }
+        }
+    }
+    let (number, suffix) = token.split_at(token.len() - 1);
+    if is_number_like(number) {
+        let value = match suffix {
+            "b" | "B" => number.parse().ok().map(NbtValue::Byte),
+            "s" | "S" => number.parse().ok().map(NbtValue::Short),
+            "l" | "L" => number.parse().ok().map(NbtValue::Long),
+            // too big floats are strings, because infinities can not be printed back
+            "f" | "F" => number.parse().ok().filter(|value: &f32| value.is_finite()).map(NbtValue::Float),
+            "d" | "D" => number.parse().ok().filter(|value: &f64| value.is_finite()).map(NbtValue::Double),
+            _ => None,
+        };
+        if let Some(value) = value {
+            return value;
+        }
+    }
+    NbtValue::String(Cow::Borrowed(token))
+}
+
+/// Parses the whole string as one value
+pub fn parse_snbt(snbt: &str) -> ProtocolResult<NbtValue<'_>> {
+    let mut parser = SnbtParser { snbt, position: 0, depth: 0 };
+    let value = parser.parse_value()?;
+    match parser.peek() {
+        None => Ok(value),
+        Some(_) => Err(snbt_error(parser.position, "Unexpected characters after the value")),
+    }
+}
+
+/// Parses the value and reads it by `read` from its binary form
+pub fn read_snbt_with<T>(
+    snbt: &str,
+    tag: u8,
+    read: impl for<'b> FnOnce(&mut &'b [u8]) -> ProtocolResult<T>,
+) -> ProtocolResult<T> {
+    let value = parse_snbt(snbt)?;
+    if value.tag() != tag {
+        return Err(ProtocolError::Any(anyhow::Error::msg("Bad nbt tag")));
+    }
+    let mut bytes = Vec::new();
+    value.write_nbt(&mut bytes)?;
+    read(&mut bytes.as_slice())
+}
+
+/// Parses a type which implements [`NbtTag`] for every lifetime, such as derived types without borrowed fields.
+/// Types with borrowed fields can be read with [`read_snbt_with`] and converted into owned ones
+pub fn from_snbt<T: for<'b> NbtTag<'b>>(snbt: &str) -> ProtocolResult<T> {
+    read_snbt_with(snbt, T::NBT_TAG, |cursor| T::read_nbt(cursor))
+}
+
+/// Prints the value which implements [`NbtTag`] through its dynamic form.
+/// Values with NaN or infinite floats can not be printed
+pub fn to_snbt<'a, T: NbtTag<'a>>(value: &T, pretty: bool) -> ProtocolResult<String> {
+    let mut bytes = Vec::new();
+    value.write_nbt(&mut bytes)?;
+    let value = NbtValue::read_nbt_of(value.nbt_tag(), &mut bytes.as_slice())?;
+    let mut result = String::new();
+    match pretty {
+        true => write!(result, "{:#}", value),
+        false => write!(result, "{}", value),
+    }.map_err(|_| ProtocolError::Any(anyhow::Error::msg("Non-finite floats can not be written in SNBT")))?;
+    Ok(result)
+}
+
+const SNBT_INDENT: &str = "    ";
+
+fn write_snbt_string(f: &mut Formatter<'_>, str: &str) -> std::fmt::Result {
+    let quote = match str.contains('"') && !str.contains('\'') {
+        true => '\'',
+        false => '"',
+    };
+    f.write_char(quote)?;
+    for char in str.chars() {
+        if char == quote || char == '\\' {
+            f.write_char('\\')?;
+        }
+        f.write_char(char)?;
+    }
+    f.write_char(quote)
+}
+
+fn write_snbt_key(f: &mut Formatter<'_>, key: &str) -> std::fmt::Result {
+    match !key.is_empty() && key.chars().all(is_unquoted_char) {
+        true => f.write_str(key),
+        false => write_snbt_string(f, key),
+    }
+}
+
+fn write_snbt_indent(f: &mut Formatter<'_>, depth: usize) -> std::fmt::Result {
+    for _ in 0..depth {
+        f.write_str(SNBT_INDENT)?;
+    }
+    Ok(())
+}
+
+fn write_snbt_array<T: Display>(f: &mut Formatter<'_>, prefix: char, values: impl Iterator<Item=T>, suffix: &str) -> std::fmt::Result {
+    write!(f, "[{};", prefix)?;
+    for (index, value) in values.enumerate() {
+        match (index, f.alternate()) {
+            (0, true) => f.write_char(' ')?,
+            (0, false) => {}
+            (_, true) => f.write_str(", ")?,
+            (_, false) => f.write_char(',')?,
+        }
+        write!(f, "{}{}", value, suffix)?;
+    }
+    f.write_char(']')
+}
+
+fn write_snbt(f: &mut Formatter<'_>, value: &NbtValue, depth: usize) -> std::fmt::Result {
+    let pretty = f.alternate();
+    match value {
+        NbtValue::Byte(value) => write!(f, "{}b", value),
+        NbtValue::Short(value) => write!(f, "{}s", value),
+        NbtValue::Int(value) => write!(f, "{}", value),
+        NbtValue::Long(value) => write!(f, "{}L", value),
+        // SNBT has no syntax for NaN and infinities
+        NbtValue::Float(value) if !value.is_finite() => Err(std::fmt::Error),
+        NbtValue::Double(value) if !value.is_finite() => Err(std::fmt::Error),
+        NbtValue::Float(value) => write!(f, "{:?}f", value),
+        NbtValue::Double(value) => write!(f, "{:?}d", value),
+        NbtValue::ByteArray(array) => write_snbt_array(f, 'B', array.iter().map(|&value| value as i8), "b"),
+        NbtValue::String(str) => write_snbt_string(f, str),
+        NbtValue::IntArray(array) => write_snbt_array(f, 'I', array.iter(), ""),
+        NbtValue::LongArray(array) => write_snbt_array(f, 'L', array.iter(), "L"),
+        NbtValue::List(list) if list.is_empty() => f.write_str("[]"),
+        NbtValue::List(list) => {
+            f.write_char('[')?;
+            for (index, element) in list.iter().enumerate() {
+                if index != 0 {
+                    f.write_char(',')?;
+                }
+                if pretty {
+                    f.write_char('\n')?;
+                    write_snbt_indent(f, depth + 1)?;
+                }
+                write_snbt(f, element, depth + 1)?;
+            }
+            if pretty {
+                f.write_char('\n')?;
+                write_snbt_indent(f, depth)?;
+            }
+            f.write_char(']')
+        }
+        NbtValue::Compound(compound) if compound.is_empty() => f.write_str("{}"),
+        NbtValue::Compound(compound) => {
+            // keys are sorted, so the same compounds are printed the same way
+            let mut entries: Vec<_> = compound.iter().collect();
+            entries.sort_unstable_by_key(|(key, _)| *key);
+            f.write_char('{')?;
+            for (index, (key, element)) in entries.into_iter().enumerate() {
+                if index != 0 {
+                    f.write_char(',')?;
+                }
+                if pretty {
+                    f.write_char('\n')?;
+                    write_snbt_indent(f, depth + 1)?;
+                }
+                write_snbt_key(f, key)?;
+                f.write_str(if pretty { ": " } else { ":" })?;
+                write_snbt(f, element, depth + 1)?;
+            }
+            if pretty {
+                f.write_char('\n')?;
+                write_snbt_indent(f, depth)?;
+            }
+            f.write_char('}')
+        }
+    }
+}
+
+impl<'a> Display for NbtValue<'a> {
+    /// Compact SNBT or indented SNBT with the alternate flag.
+    /// Fails on NaN and infinite floats, so [`to_snbt`] should be used for values which can contain them
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_snbt(f, self, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_snbt_test() {
+        let value = parse_snbt(r#"{display:{Name:'"x"'},Count:1b, "a key": [1.5, -2e3], list:[I; 1, 2b], l: 3L, bool: true, id: minecraft:stone}"#);
+        assert!(value.is_err());
+        let value = parse_snbt(r#"{display:{Name:'"x"'},Count:1b, "a key": [1.5, -2.0e3], list:[I; 1, 2b], l: 3L, bool: true, id: "minecraft:stone", s: 'it\'s'}"#).unwrap();
+        assert_eq!(value, NbtValue::Compound(HashMap::from([
+            (Cow::Borrowed("display"), NbtValue::Compound(HashMap::from([
+                (Cow::Borrowed("Name"), NbtValue::String(Cow::Borrowed("\"x\""))),
+            ]))),
+            (Cow::Borrowed("Count"), NbtValue::Byte(1)),
+            (Cow::Borrowed("a key"), NbtValue::List(vec![NbtValue::Double(1.5), NbtValue::Double(-2000.0)])),
+            (Cow::Borrowed("list"), NbtValue::IntArray(Cow::Borrowed(&[1, 2]))),
+            (Cow::Borrowed("l"), NbtValue::Long(3)),
+            (Cow::Borrowed("bool"), NbtValue::Byte(1)),
+            (Cow::Borrowed("id"), NbtValue::String(Cow::Borrowed("minecraft:stone"))),
+            (Cow::Borrowed("s"), NbtValue::String(Cow::Borrowed("it's"))),
+        ])));
+        assert_eq!(parse_snbt("1.5f").unwrap(), NbtValue::Float(1.5));
+        assert_eq!(parse_snbt("1e5").unwrap(), NbtValue::String(Cow::Borrowed("1e5")));
+        assert_eq!(parse_snbt("1e5d").unwrap(), NbtValue::Double(100000.0));
+        assert_eq!(parse_snbt("2.").unwrap(), NbtValue::Double(2.0));
+        assert_eq!(parse_snbt("1e999").unwrap(), NbtValue::String(Cow::Borrowed("1e999")));
+        assert_eq!(parse_snbt("1.0e999").unwrap(), NbtValue::String(Cow::Borrowed("1.0e999")));
+        assert_eq!(parse_snbt("1e999d").unwrap(), NbtValue::String(Cow::Borrowed("1e999d")));
+        assert_eq!(parse_snbt("1e39f").unwrap(), NbtValue::String(Cow::Borrowed("1e39f")));
+        assert_eq!(parse_snbt("3000000000").unwrap(), NbtValue::String(Cow::Borrowed("3000000000")));
+        assert!(parse_snbt("[1, 2b]").is_err());
+        assert!(parse_snbt("[B; 300]").is_err());
+        assert!(parse_snbt("{a:1").is_err());
+        assert!(parse_snbt("{a:1} b").is_err());
+    }
+
+    #[test]
+    fn print_snbt_test() {
+        let value = parse_snbt(r#"{b:[B;1b,-2b],l:[L;1L],list:[{},{x:"y'"}],s:'"',"with space":0.5d}"#).unwrap();
+        let compact = value.to_string();
+        assert_eq!(compact, r#"{b:[B;1b,-2b],l:[L;1L],list:[{},{x:"y'"}],s:'"',"with space":0.5d}"#);
+        let pretty = format!("{:#}", value);
+        assert_eq!(pretty, "{\n    b: [B; 1b, -2b],\n    l: [L; 1L],\n    list: [\n        {},\n        {\n            x: \"y'\"\n        }\n    ],\n    s: '\"',\n    \"with space\": 0.5d\n}");
+        assert_eq!(parse_snbt(&compact).unwrap(), value);
+        assert_eq!(parse_snbt(&pretty).unwrap(), value);
+    }
+
+    #[test]
+    fn snbt_limits_test() {
+        assert!(to_snbt(&NbtValue::Float(f32::NAN), false).is_err());
+        assert!(to_snbt(&NbtValue::List(vec![NbtValue::Double(f64::INFINITY)]), true).is_err());
+        assert_eq!(to_snbt(&NbtValue::Double(-0.5), false).unwrap(), "-0.5d");
+        let nested = |depth: usize| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(parse_snbt(&nested(NBT_MAX_DEPTH)).is_ok());
+        assert!(parse_snbt(&nested(NBT_MAX_DEPTH + 1)).is_err());
+        let nested = "{a:".repeat(NBT_MAX_DEPTH) + "{}" + &"}".repeat(NBT_MAX_DEPTH);
+        assert!(parse_snbt(&nested).is_err());
+        assert!(parse_snbt(&nested[3..nested.len() - 1]).is_ok());
+    }
+}
@@ -0,0 +1,344 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use bird_util::IntoOwned;
+use crate::{ProtocolCursor, ProtocolError, ProtocolResult, ProtocolWriter};
+use super::*;
+
+pub type NbtCompound<'a> = HashMap<Cow<'a, str>, NbtValue<'a>>;
+
+/// Any nbt value. Strings and byte arrays are borrowed from the cursor while reading,
+/// [`IntoOwned`] converts the value into the one which does not borrow anything
+#[derive(Clone, PartialEq, Debug)]
+pub enum NbtValue<'a> {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Cow<'a, [u8]>),
+    String(Cow<'a, str>),
+    List(Vec<NbtValue<'a>>),
+    Compound(NbtCompound<'a>),
+    IntArray(Cow<'a, [i32]>),
+    LongArray(Cow<'a, [i64]>),
+}
+
+impl<'a> NbtValue<'a> {
+    pub fn tag(&self) -> u8 {
+        match self {
+            Self::Byte(_) => NBT_TAG_BYTE,
+            Self::Short(_) => NBT_TAG_SHORT,
+            Self::Int(_) => NBT_TAG_INT,
+            Self::Long(_) => NBT_TAG_LONG,
+            Self::Float(_) => NBT_TAG_FLOAT,
+            Self::Double(_) => NBT_TAG_DOUBLE,
+            Self::ByteArray(_) => NBT_TAG_BYTE_ARRAY,
+            Self::String(_) => NBT_TAG_STRING,
+            Self::List(_) => NBT_TAG_LIST,
+            Self::Compound(_) => NBT_TAG_COMPOUND,
+            Self::IntArray(_) => NBT_TAG_INT_ARRAY,
+            Self::LongArray(_) => NBT_TAG_LONG_ARRAY,
+        }
+    }
+}
+
+impl<'a> IntoOwned for NbtValue<'a> {
+    type Owned = NbtValue<'static>;
+
+    fn into_owned(self) -> NbtValue<'static> {
+        match self {
+            Self::Byte(value) => NbtValue::Byte(value),
+            Self::Short(value) => NbtValue::Short(value),
+            Self::Int(value) => NbtValue::Int(value),
+            Self::Long(value) => NbtValue::Long(value),
+            Self::Float(value) => NbtValue::Float(value),
+            Self::Double(value) => NbtValue::Double(value),
+            Self::ByteArray(array) => NbtValue::ByteArray(Cow::Owned(array.into_owned())),
+            Self::String(str) => NbtValue::String(IntoOwned::into_owned(str)),
+            Self::List(list) => NbtValue::List(list.into_owned()),
+            Self::Compound(compound) => NbtValue::Compound(compound.into_owned()),
+            Self::IntArray(array) => NbtValue::IntArray(Cow::Owned(array.into_owned())),
+            Self::LongArray(array) => NbtValue::LongArray(Cow::Owned(array.into_owned())),
+        }
+    }
+}
+
+impl<'a> NbtTag<'a> for NbtValue<'a> {
+    /// The tag depends on the value, so lists of values are written with the tag of the first one
+    const NBT_TAG: u8 = NBT_TAG_END;
+
+    fn write_nbt<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        match self {
+            Self::Byte(value) => value.write_nbt(writer),
+            Self::Short(value) => value.write_nbt(writer),
+            Self::Int(value) => value.write_nbt(writer),
+            Self::Long(value) => value.write_nbt(writer),
+            Self::Float(value) => value.write_nbt(writer),
+            Self::Double(value) => value.write_nbt(writer),
+            Self::ByteArray(array) => NbtByteArray::write_nbt_variant(array, writer),
+            Self::String(str) => write_nbt_str(str, writer),
+            Self::List(list) => list.write_nbt(writer),
+            Self::Compound(compound) => compound.write_nbt(writer),
+            Self::IntArray(array) => NbtIntArray::write_nbt_variant(array, writer),
+            Self::LongArray(array) => NbtLongArray::write_nbt_variant(array, writer),
+        }
+    }
+
+    fn read_nbt<C: ProtocolCursor<'a>>(_: &mut C) -> ProtocolResult<Self> {
+        Err(ProtocolError::Any(anyhow::Error::msg("Nbt value can not be read without its tag")))
+    }
+
+    fn skip_nbt<C: ProtocolCursor<'a>>(_: &mut C, _: usize) -> ProtocolResult<usize> {
+        Err(ProtocolError::Any(anyhow::Error::msg("Nbt value can not be skipped without its tag")))
+    }
+
+    fn nbt_tag(&self) -> u8 {
+        self.tag()
+    }
+
+    fn check_nbt_tag(tag: u8) -> bool {
+        (NBT_TAG_BYTE..=NBT_TAG_LONG_ARRAY).contains(&tag)
+    }
+
+    fn read_nbt_of<C: ProtocolCursor<'a>>(tag: u8, cursor: &mut C) -> ProtocolResult<Self> {
+        read_nbt_value(tag, cursor, 0)
+    }
+
+    fn skip_nbt_of<C: ProtocolCursor<'a>>(tag: u8, cursor: &mut C, amount: usize) -> ProtocolResult<usize> {
+        skip_nbt_values(tag, cursor, amount, 0)
+    }
+}
+
+fn check_nbt_depth(depth: usize) -> ProtocolResult<()> {
+    match depth > NBT_MAX_DEPTH {
+        true => Err(ProtocolError::Any(anyhow::Error::msg("Too deep nbt value"))),
+        false => Ok(()),
+    }
+}
+
+/// Reads lists and compounds as their [`NbtTag`] implementations, but counts their nesting
+fn read_nbt_value<'a, C: ProtocolCursor<'a>>(tag: u8, cursor: &mut C, depth: usize) -> ProtocolResult<NbtValue<'a>> {
+    Ok(match tag {
+        NBT_TAG_BYTE => NbtValue::Byte(i8::read_nbt(cursor)?),
+        NBT_TAG_SHORT => NbtValue::Short(i16::read_nbt(cursor)?),
+        NBT_TAG_INT => NbtValue::Int(i32::read_nbt(cursor)?),
+        NBT_TAG_LONG => NbtValue::Long(i64::read_nbt(cursor)?),
+        NBT_TAG_FLOAT => NbtValue::Float(f32::read_nbt(cursor)?),
+        NBT_TAG_DOUBLE => NbtValue::Double(f64::read_nbt(cursor)?),
+        NBT_TAG_BYTE_ARRAY => NbtValue::ByteArray(NbtByteArray::read_nbt_variant(cursor)?),
+        NBT_TAG_STRING => NbtValue::String(Cow::read_nbt(cursor)?),
+        NBT_TAG_LIST => NbtValue::List(read_nbt_list(cursor, depth + 1)?),
+        NBT_TAG_COMPOUND => NbtValue::Compound(read_nbt_compound(cursor, depth + 1)?),
+        NBT_TAG_INT_ARRAY => NbtValue::IntArray(NbtIntArray::read_nbt_variant(cursor)?),
+        NBT_TAG_LONG_ARRAY => NbtValue::LongArray(NbtLongArray::read_nbt_variant(cursor)?),
+        _ => return Err(ProtocolError::Any(anyhow::Error::msg("Only tags from 1 to 12 are supported"))),
+    })
+}
+
+fn read_nbt_list<'a, C: ProtocolCursor<'a>>(cursor: &mut C, depth: usize) -> ProtocolResult<Vec<NbtValue<'a>>> {
+    check_nbt_depth(depth)?;
+    let tag = u8::read_nbt(cursor)?;
+    let len = i32::read_nbt(cursor)?;
+    if len <= 0 { return Ok(Vec::new()) }
+    if !<NbtValue as NbtTag>::check_nbt_tag(tag) { return Err(ProtocolError::Any(anyhow::Error::msg("Bad nbt tag"))) }
+    cursor.reserve_allocation(len as usize, std::mem::size_of::<NbtValue>())?;
+    let mut result = Vec::new();
+    for _ in 0..len {
+        result.push(read_nbt_value(tag, cursor, depth)?);
+    }
+    Ok(result)
+}
+
+fn read_nbt_compound<'a, C: ProtocolCursor<'a>>(cursor: &mut C, depth: usize) -> ProtocolResult<NbtCompound<'a>> {
+    check_nbt_depth(depth)?;
+    let mut result = HashMap::new();
+    compound::read_nbt_compound(cursor, |tag, name, cursor| {
+        if !<NbtValue as NbtTag>::check_nbt_tag(tag) { return Err(ProtocolError::Any(anyhow::Error::msg("Bad nbt tag"))) }
+        cursor.reserve_allocation(1, std::mem::size_of::<(Cow<str>, NbtValue)>())?;
+        result.insert(name, read_nbt_value(tag, cursor, depth)?);
+        Ok(())
+    })?;
+    Ok(result)
+}
+
+fn skip_nbt_values<'a, C: ProtocolCursor<'a>>(tag: u8, cursor: &mut C, amount: usize, depth: usize) -> ProtocolResult<usize> {
+    match tag {
+        NBT_TAG_BYTE => i8::skip_nbt(cursor, amount),
+        NBT_TAG_SHORT => i16::skip_nbt(cursor, amount),
+        NBT_TAG_INT => i32::skip_nbt(cursor, amount),
+        NBT_TAG_LONG => i64::skip_nbt(cursor, amount),
+        NBT_TAG_FLOAT => f32::skip_nbt(cursor, amount),
+        NBT_TAG_DOUBLE => f64::skip_nbt(cursor, amount),
+        NBT_TAG_BYTE_ARRAY => <NbtByteArray as NbtTagVariant<&[u8]>>::skip_nbt_variant(cursor, amount),
+        NBT_TAG_STRING => Cow::<str>::skip_nbt(cursor, amount),
+        NBT_TAG_LIST => {
+            check_nbt_depth(depth + 1)?;
+            let mut result = 0;
+            for _ in 0..amount {
+                let tag = u8::read_nbt(cursor)?;
+                let len = i32::read_nbt(cursor)?;
+                if len <= 0 { result += 5; continue; }
+                if !<NbtValue as NbtTag>::check_nbt_tag(tag) { return Err(ProtocolError::Any(anyhow::Error::msg("Bad nbt tag"))) }
+                result += 5 + skip_nbt_values(tag, cursor, len as _, depth + 1)?;
+            }
+            Ok(result)
+        }
+        NBT_TAG_COMPOUND => {
+            check_nbt_depth(depth + 1)?;
+            let start = cursor.remaining_bytes();
+            for _ in 0..amount {
+                compound::read_nbt_compound(cursor, |tag, _, cursor| {
+                    if !<NbtValue as NbtTag>::check_nbt_tag(tag) { return Err(ProtocolError::Any(anyhow::Error::msg("Bad nbt tag"))) }
+                    skip_nbt_values(tag, cursor, 1, depth + 1).map(|_| ())
+                })?;
+            }
+            Ok(start - cursor.remaining_bytes())
+        }
+        NBT_TAG_INT_ARRAY => <NbtIntArray as NbtTagVariant<NbtBorrowedI32Array>>::skip_nbt_variant(cursor, amount),
+        NBT_TAG_LONG_ARRAY => <NbtLongArray as NbtTagVariant<NbtBorrowedI64Array>>::skip_nbt_variant(cursor, amount),
+        _ => Err(ProtocolError::Any(anyhow::Error::msg("Only tags from 1 to 12 are supported"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(value: &NbtValue) -> NbtValue<'static> {
+        let mut bytes = Vec::new();
+        value.write_nbt(&mut bytes).unwrap();
+        let mut cursor = bytes.as_slice();
+        let read = NbtValue::read_nbt_of(value.tag(), &mut cursor).unwrap();
+        assert!(cursor.is_empty());
+        let mut cursor = bytes.as_slice();
+        assert_eq!(NbtValue::skip_nbt_of(value.tag(), &mut cursor, 1).unwrap(), bytes.len());
+        read.into_owned()
+    }
+
+    #[test]
+    fn nbt_value_round_trip_test() {
+        let compound = NbtValue::Compound(HashMap::from([
+            (Cow::Borrowed("byte"), NbtValue::Byte(-1)),
+            (Cow::Borrowed("string"), NbtValue::String(Cow::Borrowed("\u{1F426}"))),
+            (Cow::Borrowed("bytes"), NbtValue::ByteArray(Cow::Borrowed(&[1, 2, 3]))),
+            (Cow::Borrowed("ints"), NbtValue::IntArray(Cow::Borrowed(&[1, -2]))),
+            (Cow::Borrowed("longs"), NbtValue::LongArray(Cow::Borrowed(&[i64::MIN]))),
+            (Cow::Borrowed("empty"), NbtValue::List(Vec::new())),
+            (Cow::Borrowed("list"), NbtValue::List(vec![
+                NbtValue::Compound(HashMap::from([(Cow::Borrowed("a"), NbtValue::Double(0.5))])),
+                NbtValue::Compound(HashMap::new()),
+            ])),
+        ]));
+        assert_eq!(round_trip(&compound), compound);
+        let list = NbtValue::List(vec![NbtValue::List(vec![NbtValue::Short(1)]), NbtValue::List(Vec::new())]);
+        assert_eq!(round_trip(&list), list);
+    }
+
+    #[test]
+    fn nbt_value_write_error_test() {
+        let mut bytes = Vec::new();
+        assert!(NbtValue::List(vec![NbtValue::Byte(1), NbtValue::Int(1)]).write_nbt(&mut bytes).is_err());
+        assert!(NbtValue::read_nbt_of(NBT_TAG_END, &mut [0u8].as_slice()).is_err());
+    }
+
+    #[test]
+    fn nbt_value_tag_dispatch_test() {
+        let values = [
+            (NBT_TAG_BYTE, vec![0xFF], NbtValue::Byte(-1)),
+            (NBT_TAG_SHORT, vec![0, 2], NbtValue::Short(2)),
+            (NBT_TAG_INT, vec![0, 0, 0, 3], NbtValue::Int(3)),
+            (NBT_TAG_LONG, vec![0, 0, 0, 0, 0, 0, 0, 4], NbtValue::Long(4)),
+            (NBT_TAG_FLOAT, 0.5f32.to_be_bytes().to_vec(), NbtValue::Float(0.5)),
+            (NBT_TAG_DOUBLE, 0.25f64.to_be_bytes().to_vec(), NbtValue::Double(0.25)),
+            (NBT_TAG_BYTE_ARRAY, vec![0, 0, 0, 2, 1, 2], NbtValue::ByteArray(Cow::Borrowed(&[1, 2]))),
+            (NBT_TAG_STRING, vec![0, 2, b'h', b'i'], NbtValue::String(Cow::Borrowed("hi"))),
+            (NBT_TAG_LIST, vec![NBT_TAG_INT, 0, 0, 0, 1, 0, 0, 0, 5], NbtValue::List(vec![NbtValue::Int(5)])),
+            (
+                NBT_TAG_COMPOUND,
+                vec![NBT_TAG_BYTE, 0, 1, b'b', 1, NBT_TAG_END],
+                NbtValue::Compound(HashMap::from([(Cow::Borrowed("b"), NbtValue::Byte(1))])),
+            ),
+            (NBT_TAG_INT_ARRAY, vec![0, 0, 0, 1, 0, 0, 0, 6], NbtValue::IntArray(Cow::Borrowed(&[6]))),
+            (NBT_TAG_LONG_ARRAY, vec![0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 7], NbtValue::LongArray(Cow::Borrowed(&[7]))),
+        ];
+        for (tag, bytes, value) in values {
+            assert!(<NbtValue as NbtTag>::check_nbt_tag(tag));
+            let mut cursor = bytes.as_slice();
+            let read = NbtValue::read_nbt_of(tag, &mut cursor).unwrap();
+            assert_eq!((read.tag(), &read), (tag, &value));
+            assert!(cursor.is_empty());
+            let mut cursor = bytes.as_slice();
+            assert_eq!(NbtValue::skip_nbt_of(tag, &mut cursor, 1).unwrap(), bytes.len());
+            assert!(cursor.is_empty());
+        }
+    }
+
+    #[test]
+    fn nbt_value_borrowed_test() {
+        // strings and byte arrays are borrowed from the cursor
+        let bytes = [0, 2, b'h', b'i'];
+        let read = NbtValue::read_nbt_of(NBT_TAG_STRING, &mut bytes.as_slice()).unwrap();
+        assert!(matches!(read, NbtValue::String(Cow::Borrowed("hi"))));
+        assert!(matches!(read.into_owned(), NbtValue::String(Cow::Owned(ref str)) if str == "hi"));
+        let bytes = [0, 0, 0, 1, 9];
+        let read = NbtValue::read_nbt_of(NBT_TAG_BYTE_ARRAY, &mut bytes.as_slice()).unwrap();
+        assert!(matches!(read, NbtValue::ByteArray(Cow::Borrowed([9]))));
+        assert!(matches!(read.into_owned(), NbtValue::ByteArray(Cow::Owned(ref array)) if array == &[9]));
+        // strings which differ in modified utf-8 and int arrays are converted while reading
+        let mut bytes = Vec::new();
+        NbtValue::String(Cow::Borrowed("\u{1F426}")).write_nbt(&mut bytes).unwrap();
+        let read = NbtValue::read_nbt_of(NBT_TAG_STRING, &mut bytes.as_slice()).unwrap();
+        assert!(matches!(read, NbtValue::String(Cow::Owned(ref str)) if str == "\u{1F426}"));
+        let bytes = [0, 0, 0, 1, 0, 0, 0, 9];
+        let read = NbtValue::read_nbt_of(NBT_TAG_INT_ARRAY, &mut bytes.as_slice()).unwrap();
+        assert!(matches!(read, NbtValue::IntArray(Cow::Owned(ref array)) if array == &[9]));
+    }
+
+    #[test]
+    fn nbt_value_mismatched_tag_test() {
+        for tag in [NBT_TAG_END, NBT_TAG_LONG_ARRAY + 1] {
+            assert!(!<NbtValue as NbtTag>::check_nbt_tag(tag));
+            assert!(NbtValue::read_nbt_of(tag, &mut [0u8; 8].as_slice()).is_err());
+            assert!(NbtValue::skip_nbt_of(tag, &mut [0u8; 8].as_slice(), 1).is_err());
+        }
+        assert!(NbtValue::read_nbt(&mut [0u8; 8].as_slice()).is_err());
+        // lists and compounds of values do not accept unknown tags
+        assert!(Vec::<NbtValue>::read_nbt(&mut [NBT_TAG_END, 0, 0, 0, 1].as_slice()).is_err());
+        assert!(NbtCompound::read_nbt(&mut [NBT_TAG_LONG_ARRAY + 1, 0, 1, b'a', 0].as_slice()).is_err());
+        // values are rejected by typed readers of other tags
+        let mut bytes = Vec::new();
+        NbtValue::List(vec![NbtValue::String(Cow::Borrowed("1"))]).write_nbt(&mut bytes).unwrap();
+        assert!(Vec::<i32>::read_nbt(&mut bytes.as_slice()).is_err());
+        assert!(Vec::<NbtValue>::read_nbt(&mut bytes.as_slice()).is_ok());
+    }
+
+    #[test]
+    fn nbt_value_depth_test() {
+        // unoptimized builds use much bigger stack frames than the default stack of tests fits
+        std::thread::Builder::new().stack_size(16 << 20).spawn(|| {
+            fn nested_lists(depth: usize) -> Vec<u8> {
+                let mut bytes = Vec::new();
+                for _ in 1..depth {
+                    bytes.extend([NBT_TAG_LIST, 0, 0, 0, 1]);
+                }
+                bytes.extend([NBT_TAG_END, 0, 0, 0, 0]);
+                bytes
+            }
+            let bytes = nested_lists(NBT_MAX_DEPTH);
+            assert!(NbtValue::read_nbt_of(NBT_TAG_LIST, &mut bytes.as_slice()).is_ok());
+            assert_eq!(NbtValue::skip_nbt_of(NBT_TAG_LIST, &mut bytes.as_slice(), 1).unwrap(), bytes.len());
+            let bytes = nested_lists(NBT_MAX_DEPTH + 1);
+            assert!(NbtValue::read_nbt_of(NBT_TAG_LIST, &mut bytes.as_slice()).is_err());
+            assert!(NbtValue::skip_nbt_of(NBT_TAG_LIST, &mut bytes.as_slice(), 1).is_err());
+            let mut bytes = Vec::new();
+            for _ in 0..NBT_MAX_DEPTH {
+                bytes.extend([NBT_TAG_COMPOUND, 0, 0]);
+            }
+            bytes.extend([NBT_TAG_END; NBT_MAX_DEPTH + 1]);
+            assert!(NbtValue::read_nbt_of(NBT_TAG_COMPOUND, &mut bytes.as_slice()).is_err());
+            assert!(NbtValue::skip_nbt_of(NBT_TAG_COMPOUND, &mut bytes.as_slice(), 1).is_err());
+            assert!(NbtValue::read_nbt_of(NBT_TAG_COMPOUND, &mut &bytes[3..]).is_ok());
+        }).unwrap().join().unwrap();
+    }
+}
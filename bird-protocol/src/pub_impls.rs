@@ -44,4 +44,187 @@ impl ProtocolWriter for Vec<u8> {
     fn write_byte(&mut self, byte: u8) {
         self.push(byte)
     }
-}
\ No newline at end of file
+
+    fn reserve(&mut self, additional: usize) {
+        Vec::reserve(self, additional)
+    }
+
+    fn position(&self) -> Option<usize> {
+        Some(self.len())
+    }
+
+    fn truncate(&mut self, position: usize) {
+        Vec::truncate(self, position)
+    }
+}
+
+/// Writer which only counts written bytes. Used to compute the exact encoded length
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct ProtocolLengthCounter {
+    pub length: usize,
+    pub protocol_version: Option<i32>,
+}
+
+impl ProtocolLengthCounter {
+    pub const fn new(protocol_version: Option<i32>) -> Self {
+        Self { length: 0, protocol_version }
+    }
+}
+
+impl ProtocolWriter for ProtocolLengthCounter {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.length += bytes.len()
+    }
+
+    fn write_byte(&mut self, _byte: u8) {
+        self.length += 1
+    }
+
+    fn protocol_version(&self) -> Option<i32> {
+        self.protocol_version
+    }
+
+    fn position(&self) -> Option<usize> {
+        Some(self.length)
+    }
+
+    fn truncate(&mut self, position: usize) {
+        self.length = self.length.min(position)
+    }
+}
+
+/// Writes into the fixed buffer.
+/// If the buffer is too small nothing more is written and [`ProtocolSliceWriter::finish`] fails
+pub struct ProtocolSliceWriter<'a> {
+    buffer: &'a mut [u8],
+    position: usize,
+    overflowed: bool,
+}
+
+impl<'a> ProtocolSliceWriter<'a> {
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Self { buffer, position: 0, overflowed: false }
+    }
+
+    pub fn written(&self) -> usize {
+        self.position
+    }
+
+    pub fn is_overflowed(&self) -> bool {
+        self.overflowed
+    }
+
+    /// Returns the written part of the buffer
+    pub fn finish(self) -> ProtocolResult<&'a mut [u8]> {
+        match self.overflowed {
+            true => Err(ProtocolError::End),
+            false => Ok(&mut self.buffer[..self.position]),
+        }
+    }
+}
+
+impl<'a> ProtocolWriter for ProtocolSliceWriter<'a> {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        if self.overflowed {
+            return;
+        }
+        match self.buffer.get_mut(self.position..self.position + bytes.len()) {
+            Some(target) => {
+                target.copy_from_slice(bytes);
+                self.position += bytes.len();
+            }
+            None => self.overflowed = true,
+        }
+    }
+
+    fn position(&self) -> Option<usize> {
+        match self.overflowed {
+            true => None,
+            false => Some(self.position),
+        }
+    }
+
+    fn truncate(&mut self, position: usize) {
+        if position <= self.position {
+            self.position = position;
+            self.overflowed = false;
+        }
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl ProtocolWriter for bytes::BytesMut {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        bytes::BytesMut::reserve(self, additional)
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        bytes::BufMut::put_u8(self, byte)
+    }
+
+    fn position(&self) -> Option<usize> {
+        Some(self.len())
+    }
+
+    fn truncate(&mut self, position: usize) {
+        bytes::BytesMut::truncate(self, position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use crate::*;
+
+    #[test]
+    fn slice_writer_test() {
+        let mut buffer = [0u8; 4];
+        let mut writer = ProtocolSliceWriter::new(&mut buffer);
+        VarInt::write_variant(&300, &mut writer).unwrap();
+        writer.write_byte(7);
+        assert_eq!(writer.written(), 3);
+        assert_eq!(writer.finish().unwrap(), &[0xAC, 0x02, 7]);
+        let mut writer = ProtocolSliceWriter::new(&mut buffer);
+        writer.write_bytes(&[1, 2, 3]);
+        writer.write_bytes(&[4, 5]);
+        writer.write_byte(6);
+        assert!(writer.is_overflowed());
+        assert!(matches!(writer.finish(), Err(ProtocolError::End)));
+    }
+
+    #[test]
+    fn length_counter_test() {
+        let mut counter = ProtocolLengthCounter::new(None);
+        VarInt::write_variant(&300, &mut counter).unwrap();
+        "hello".write(&mut counter).unwrap();
+        assert_eq!(counter.length, 8);
+        for value in [0, 1, 127, 128, 16383, 16384, i32::MAX, -1] {
+            let mut bytes = Vec::new();
+            VarInt::write_variant(&value, &mut bytes).unwrap();
+            assert_eq!(VarInt::encoded_len_variant(&value, None), bytes.len());
+        }
+        let values = vec![1, 300, -1];
+        let mut bytes = Vec::new();
+        LengthProvidedArray::<i32, VarInt, i32, VarInt>::write_variant(&values, &mut bytes).unwrap();
+        assert_eq!(LengthProvidedArray::<i32, VarInt, i32, VarInt>::encoded_len_variant(&values, None), bytes.len());
+        assert_eq!(Some("é").encoded_len(), 4);
+        assert_eq!(7u64.encoded_len(), 8);
+    }
+
+    #[test]
+    fn io_writer_test() {
+        let mut writer = WritableProtocolWriter::new(Vec::new());
+        VarInt::write_variant(&300, &mut writer).unwrap();
+        assert_eq!(writer.finish().unwrap(), vec![0xAC, 0x02]);
+        let mut buffer = [0u8; 1];
+        let mut writer = WritableProtocolWriter::new(io::Cursor::new(buffer.as_mut_slice()));
+        writer.write_bytes(&[1, 2]);
+        writer.write_byte(3);
+        assert_eq!(writer.error().unwrap().kind(), io::ErrorKind::WriteZero);
+        assert!(writer.finish().is_err());
+    }
+}
@@ -2,7 +2,7 @@ use std::io;
 use std::io::ErrorKind;
 use std::marker::PhantomData;
 
-use crate::{ProtocolCursor, ProtocolError};
+use crate::{ProtocolCursor, ProtocolError, ProtocolWriter};
 
 impl From<ProtocolError> for io::Error {
     fn from(value: ProtocolError) -> Self {
@@ -31,4 +31,39 @@ impl<'a, 'c, C: ProtocolCursor<'c>> io::Read for StdIOReadProtocolCursor<'a, 'c,
         }
         Ok(length)
     }
-}
\ No newline at end of file
+}
+
+/// Writes everything to the inner [`io::Write`].
+/// The first error is kept and everything written after it is ignored
+pub struct StdIOWriteProtocolWriter<W: io::Write> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> StdIOWriteProtocolWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer, error: None }
+    }
+
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    /// Returns the inner writer or the first error which happened while writing
+    pub fn finish(self) -> io::Result<W> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.writer),
+        }
+    }
+}
+
+impl<W: io::Write> ProtocolWriter for StdIOWriteProtocolWriter<W> {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        if self.error.is_none() {
+            if let Err(error) = self.writer.write_all(bytes) {
+                self.error = Some(error);
+            }
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::*;
+use crate::capture::ProtocolCapture;
+use crate::frame::{packet_payload, ProtocolFrameCodec, ProtocolFrameError, ProtocolFrameResult};
+use crate::version::ProtocolVersionedWriter;
+#[cfg(feature = "compression")]
+use crate::compression::ProtocolCompression;
+#[cfg(feature = "encryption")]
+use crate::encryption::{protocol_ciphers, ProtocolDecryptor, ProtocolEncryptor, SHARED_SECRET_LENGTH};
+
+/// Tokio codec which decodes frame payloads (packet id and packet body) and encodes packets.
+/// Compression and encryption can be turned on while the connection is running
+#[derive(Default)]
+pub struct ProtocolTokioCodec {
+    pub frame: ProtocolFrameCodec,
+    /// Protocol version which is used to encode packets
+    pub protocol_version: Option<i32>,
+    #[cfg(feature = "compression")]
+    pub compression: Option<ProtocolCompression>,
+    #[cfg(feature = "encryption")]
+    encryptor: Option<ProtocolEncryptor>,
+    #[cfg(feature = "encryption")]
+    decryptor: Option<ProtocolDecryptor>,
+    /// Count of bytes at the start of the read buffer which are already decrypted
+    #[cfg(feature = "encryption")]
+    decrypted: usize,
+    /// Records every decoded and encoded frame
+    pub capture: Option<ProtocolCapture>,
+}
+
+impl ProtocolTokioCodec {
+    pub fn new(frame: ProtocolFrameCodec) -> Self {
+        Self { frame, ..Default::default() }
+    }
+
+    /// Encrypts every following byte in both directions.
+    /// Bytes which are already in the read buffer, but were not decoded yet, are decrypted too
+    #[cfg(feature = "encryption")]
+    pub fn enable_encryption(&mut self, shared_secret: &[u8; SHARED_SECRET_LENGTH]) {
+        let (encryptor, decryptor) = protocol_ciphers(shared_secret);
+        self.encryptor = Some(encryptor);
+        self.decryptor = Some(decryptor);
+    }
+
+    /// Changes the state which is recorded by the capture.
+    /// Should be called whenever the state of the connection changes, otherwise the records are labeled with a wrong state
+    pub fn set_state(&mut self, state: ProtocolPacketState) {
+        if let Some(ref mut capture) = self.capture {
+            capture.state = state;
+        }
+    }
+
+    /// Encodes already written packet id and packet body
+    pub fn encode_payload(&mut self, payload: &[u8], dst: &mut BytesMut) -> ProtocolFrameResult<()> {
+        let start = dst.len();
+        #[cfg(feature = "compression")]
+        self.frame.write_compressed_frame(self.compression.as_ref(), payload, dst)?;
+        #[cfg(not(feature = "compression"))]
+        self.frame.write_frame(payload, dst)?;
+        self.capture_encoded(&dst[start..])?;
+        #[cfg(feature = "encryption")]
+        if let Some(ref mut encryptor) = self.encryptor {
+            encryptor.encrypt(&mut dst[start..]);
+        }
+        Ok(())
+    }
+
+    fn is_compressed(&self) -> bool {
+        #[cfg(feature = "compression")]
+        return self.compression.is_some();
+        #[cfg(not(feature = "compression"))]
+        return false;
+    }
+
+    /// Records the payload of the encoded frame
+    fn capture_encoded(&mut self, mut frame: &[u8]) -> ProtocolFrameResult<()> {
+        let compressed = self.is_compressed();
+        if let Some(ref mut capture) = self.capture {
+            let payload = self.frame.read_frame(&mut frame)?;
+            capture.record_encoded(compressed, payload)?;
+        }
+        Ok(())
+    }
+}
+
+impl Decoder for ProtocolTokioCodec {
+    type Item = BytesMut;
+    type Error = ProtocolFrameError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> ProtocolFrameResult<Option<BytesMut>> {
+        #[cfg(feature = "encryption")]
+        if let Some(ref mut decryptor) = self.decryptor {
+            decryptor.decrypt(&mut src[self.decrypted..]);
+            self.decrypted = src.len();
+        }
+        let mut cursor = &src[..];
+        let payload_length = match self.frame.read_frame(&mut cursor) {
+            Ok(payload) => payload.len(),
+            Err(ProtocolFrameError::Incomplete(needed)) => {
+                src.reserve(needed);
+                return Ok(None);
+            }
+            Err(err) => return Err(err),
+        };
+        let frame_length = src.len() - cursor.len();
+        #[cfg(feature = "encryption")]
+        {
+            self.decrypted = self.decrypted.saturating_sub(frame_length);
+        }
+        let mut payload = src.split_to(frame_length);
+        payload.advance(frame_length - payload_length);
+        let compressed = self.is_compressed();
+        if let Some(ref mut capture) = self.capture {
+            capture.record_decoded(compressed, &payload)?;
+        }
+        #[cfg(feature = "compression")]
+        if let Some(ref compression) = self.compression {
+            let length = match compression.decompress(&payload)? {
+                std::borrow::Cow::Borrowed(bytes) => bytes.len(),
+                std::borrow::Cow::Owned(bytes) => return Ok(Some(BytesMut::from(bytes.as_slice()))),
+            };
+            payload.advance(payload.len() - length);
+        }
+        Ok(Some(payload))
+    }
+}
+
+impl<'p, P: ProtocolPacket + ProtocolWritable> Encoder<&'p P> for ProtocolTokioCodec {
+    type Error = ProtocolFrameError;
+
+    fn encode(&mut self, packet: &'p P, dst: &mut BytesMut) -> ProtocolFrameResult<()> {
+        #[cfg(feature = "compression")]
+        if self.compression.is_some() {
+            let payload = packet_payload(packet, self.protocol_version)?;
+            return self.encode_payload(&payload, dst);
+        }
+        // without compression the frame is written directly into the buffer, which is rolled back on failure
+        let start = dst.len();
+        match self.protocol_version {
+            Some(version) => {
+                let mut writer = ProtocolVersionedWriter::new(std::mem::take(dst), version);
+                let result = self.frame.write_packet(packet, &mut writer);
+                *dst = writer.into_inner();
+                result
+            }
+            None => self.frame.write_packet(packet, dst),
+        }?;
+        self.capture_encoded(&dst[start..])?;
+        #[cfg(feature = "encryption")]
+        if let Some(ref mut encryptor) = self.encryptor {
+            encryptor.encrypt(&mut dst[start..]);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestPacket(Vec<u8>);
+
+    impl ProtocolPacket for TestPacket {
+        const ID: i32 = 0x5;
+        const BOUND: ProtocolPacketBound = ProtocolPacketBound::Client;
+        const STATE: ProtocolPacketState = ProtocolPacketState::Play;
+    }
+
+    impl ProtocolSize for TestPacket {
+        const SIZE: Range<u32> = (0..u32::MAX);
+    }
+
+    impl ProtocolWritable for TestPacket {
+        fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+            writer.write_bytes(&self.0);
+            Ok(())
+        }
+    }
+
+    fn codec() -> ProtocolTokioCodec {
+        #[allow(unused_mut)]
+        let mut codec = ProtocolTokioCodec::default();
+        #[cfg(feature = "compression")]
+        {
+            codec.compression = Some(ProtocolCompression::new(64));
+        }
+        #[cfg(feature = "encryption")]
+        codec.enable_encryption(&[3u8; SHARED_SECRET_LENGTH]);
+        codec
+    }
+
+    #[test]
+    fn tokio_codec_round_trip_test() {
+        let small = TestPacket(vec![1, 2, 3]);
+        let big = TestPacket(vec![9; 1024]);
+        let mut encoder = codec();
+        let mut stream = BytesMut::new();
+        encoder.encode(&small, &mut stream).unwrap();
+        encoder.encode(&big, &mut stream).unwrap();
+        let mut decoder = codec();
+        let mut src = BytesMut::new();
+        src.extend_from_slice(&stream[..2]);
+        assert_eq!(decoder.decode(&mut src).unwrap(), None);
+        src.extend_from_slice(&stream[2..]);
+        assert_eq!(decoder.decode(&mut src).unwrap().unwrap(), packet_payload(&small, None).unwrap().as_slice());
+        assert_eq!(decoder.decode(&mut src).unwrap().unwrap(), packet_payload(&big, None).unwrap().as_slice());
+        assert_eq!(decoder.decode(&mut src).unwrap(), None);
+        assert!(src.is_empty());
+    }
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn tokio_codec_capture_test() {
+        use crate::capture::{ProtocolCaptureReader, ProtocolCaptureWriter};
+
+        let buffer = SharedBuffer::default();
+        let mut encoder = codec();
+        let writer: Box<dyn std::io::Write + Send> = Box::new(buffer.clone());
+        let writer = ProtocolCaptureWriter::new(writer, None).unwrap();
+        encoder.capture = Some(ProtocolCapture::new(writer, ProtocolPacketBound::Server));
+        encoder.set_state(ProtocolPacketState::Play);
+        let packet = TestPacket(vec![7; 128]);
+        let mut stream = BytesMut::new();
+        encoder.encode(&packet, &mut stream).unwrap();
+        let mut decoder = codec();
+        assert_eq!(decoder.decode(&mut stream).unwrap().unwrap(), packet_payload(&packet, None).unwrap().as_slice());
+        let bytes = buffer.0.lock().unwrap().clone();
+        let mut records = ProtocolCaptureReader::new(bytes.as_slice()).unwrap();
+        let mut record = records.next().unwrap().unwrap();
+        assert!(records.next().is_none());
+        assert_eq!(record.bound, ProtocolPacketBound::Client);
+        assert_eq!(record.state, ProtocolPacketState::Play);
+        record.decompress().unwrap();
+        assert_eq!(record.payload, packet_payload(&packet, None).unwrap());
+    }
+}
@@ -0,0 +1,218 @@
+use crate::*;
+
+/// Protocol version of Minecraft 1.19.3 which is supported by the crate. It is used if version is unknown
+pub const CURRENT_PROTOCOL_VERSION: i32 = 761;
+
+/// Checks whether something which exists in versions from `since` to `until` (both inclusive) is present.
+/// If version is unknown [`CURRENT_PROTOCOL_VERSION`] is used
+pub const fn protocol_version_matches(version: Option<i32>, since: Option<i32>, until: Option<i32>) -> bool {
+    let version = match version {
+        Some(version) => version,
+        None => CURRENT_PROTOCOL_VERSION,
+    };
+    let after_since = match since {
+        Some(since) => version >= since,
+        None => true,
+    };
+    let before_until = match until {
+        Some(until) => version <= until,
+        None => true,
+    };
+    after_since && before_until
+}
+
+/// Id of a packet in versions from `since` to `until` (both inclusive)
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ProtocolVersionId {
+    pub since: Option<i32>,
+    pub until: Option<i32>,
+    pub id: i32,
+}
+
+/// Id of the first matching version id, or the default `id` if there is no such one
+pub const fn packet_id_for_version(id: i32, version_ids: &[ProtocolVersionId], version: Option<i32>) -> i32 {
+    let mut index = 0;
+    while index < version_ids.len() {
+        let version_id = version_ids[index];
+        if protocol_version_matches(version, version_id.since, version_id.until) {
+            return version_id.id;
+        }
+        index += 1;
+    }
+    id
+}
+
+/// Checks that no other packet has the id of the packet with `index` in any version.
+/// Packets are default ids with version ids. Ids change only at the bounds of version ranges,
+/// so only the bounds are checked
+pub const fn packet_id_is_unique(packets: &[(i32, &[ProtocolVersionId])], index: usize) -> bool {
+    if !packet_id_is_unique_in(packets, index, None) || !packet_id_is_unique_in(packets, index, Some(i32::MIN)) {
+        return false;
+    }
+    let mut packet = 0;
+    while packet < packets.len() {
+        let version_ids = packets[packet].1;
+        let mut version_index = 0;
+        while version_index < version_ids.len() {
+            let ProtocolVersionId { since, until, .. } = version_ids[version_index];
+            if let Some(since) = since {
+                if !packet_id_is_unique_in(packets, index, Some(since)) {
+                    return false;
+                }
+            }
+            if let Some(until) = until {
+                if until != i32::MAX && !packet_id_is_unique_in(packets, index, Some(until + 1)) {
+                    return false;
+                }
+            }
+            version_index += 1;
+        }
+        packet += 1;
+    }
+    true
+}
+
+const fn packet_id_is_unique_in(packets: &[(i32, &[ProtocolVersionId])], index: usize, version: Option<i32>) -> bool {
+    let id = packet_id_for_version(packets[index].0, packets[index].1, version);
+    let mut packet = 0;
+    while packet < packets.len() {
+        if packet != index && packet_id_for_version(packets[packet].0, packets[packet].1, version) == id {
+            return false;
+        }
+        packet += 1;
+    }
+    true
+}
+
+/// Cursor which reads version dependent objects as they are in the given protocol version
+pub struct ProtocolVersionedCursor<C> {
+    pub cursor: C,
+    pub version: i32,
+}
+
+impl<C> ProtocolVersionedCursor<C> {
+    pub const fn new(cursor: C, version: i32) -> Self {
+        Self { cursor, version }
+    }
+
+    pub fn into_inner(self) -> C {
+        self.cursor
+    }
+}
+
+impl<'a, C: ProtocolCursor<'a>> ProtocolCursor<'a> for ProtocolVersionedCursor<C> {
+    fn take_byte(&mut self) -> ProtocolResult<u8> {
+        self.cursor.take_byte()
+    }
+
+    fn take_bytes(&mut self, length: usize) -> ProtocolResult<&'a [u8]> {
+        self.cursor.take_bytes(length)
+    }
+
+    fn remaining_bytes(&self) -> usize {
+        self.cursor.remaining_bytes()
+    }
+
+    fn take_cursor(&self) -> Self {
+        Self::new(self.cursor.take_cursor(), self.version)
+    }
+
+    fn has_bytes(&self, length: usize) -> bool {
+        self.cursor.has_bytes(length)
+    }
+
+    fn protocol_version(&self) -> Option<i32> {
+        Some(self.version)
+    }
+
+    fn reserve_allocation(&mut self, count: usize, element_size: usize) -> ProtocolResult<()> {
+        self.cursor.reserve_allocation(count, element_size)
+    }
+
+    fn enter_field(&mut self, ty: &'static str, path: &'static str) {
+        self.cursor.enter_field(ty, path)
+    }
+
+    fn leave_field(&mut self, value: Option<&dyn std::fmt::Debug>) {
+        self.cursor.leave_field(value)
+    }
+}
+
+/// Writer which writes version dependent objects as they are in the given protocol version
+pub struct ProtocolVersionedWriter<W> {
+    pub writer: W,
+    pub version: i32,
+}
+
+impl<W> ProtocolVersionedWriter<W> {
+    pub const fn new(writer: W, version: i32) -> Self {
+        Self { writer, version }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: ProtocolWriter> ProtocolWriter for ProtocolVersionedWriter<W> {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.writer.write_bytes(bytes)
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        self.writer.write_byte(byte)
+    }
+
+    fn write_vec_bytes(&mut self, bytes: Vec<u8>) {
+        self.writer.write_vec_bytes(bytes)
+    }
+
+    fn protocol_version(&self) -> Option<i32> {
+        Some(self.version)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.writer.reserve(additional)
+    }
+
+    fn position(&self) -> Option<usize> {
+        self.writer.position()
+    }
+
+    fn truncate(&mut self, position: usize) {
+        self.writer.truncate(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protocol_version_matches_test() {
+        assert!(protocol_version_matches(None, None, None));
+        assert!(protocol_version_matches(None, Some(760), None));
+        assert!(!protocol_version_matches(None, None, Some(760)));
+        assert!(protocol_version_matches(None, None, Some(CURRENT_PROTOCOL_VERSION)));
+        assert!(!protocol_version_matches(None, Some(CURRENT_PROTOCOL_VERSION + 1), None));
+        assert!(protocol_version_matches(Some(760), Some(760), Some(761)));
+        assert!(protocol_version_matches(Some(761), Some(760), Some(761)));
+        assert!(!protocol_version_matches(Some(759), Some(760), Some(761)));
+        assert!(!protocol_version_matches(Some(762), Some(760), Some(761)));
+    }
+
+    #[test]
+    fn packet_id_is_unique_test() {
+        const OLD: &[ProtocolVersionId] = &[ProtocolVersionId { since: None, until: Some(759), id: 0x1 }];
+        const NEW: &[ProtocolVersionId] = &[ProtocolVersionId { since: Some(760), until: None, id: 0x3 }];
+        assert_eq!(packet_id_for_version(0x2, OLD, Some(759)), 0x1);
+        assert_eq!(packet_id_for_version(0x2, OLD, None), 0x2);
+        assert!(packet_id_is_unique(&[(0x1, NEW), (0x2, &[])], 0));
+        assert!(packet_id_is_unique(&[(0x1, NEW), (0x2, &[])], 1));
+        // 0x3 is used by both packets since 760
+        assert!(!packet_id_is_unique(&[(0x1, NEW), (0x3, &[])], 0));
+        // 0x1 is used by both packets until 759
+        assert!(!packet_id_is_unique(&[(0x1, &[]), (0x2, OLD)], 1));
+        assert!(!packet_id_is_unique(&[(0x1, &[]), (0x1, &[])], 0));
+    }
+}
@@ -1,7 +1,6 @@
 #![feature(generic_const_exprs)]
 
 pub mod protocol;
-pub mod nbt;
 
 fn main() {
     println!("Hello, world!");
@@ -9,21 +9,34 @@ use uuid::Uuid;
 use bird_chat::component::Component;
 use bird_chat::identifier::Identifier;
 use bird_protocol::{*, ProtocolPacketState::*, ProtocolPacketBound::*};
-use bird_protocol::derive::{BirdNbt, ProtocolAll, ProtocolPacket, ProtocolSize, ProtocolWritable};
-use bird_protocol::nbt::{NBT_TAG_STRING, NbtTag, NbtByteArray, write_nbt_str};
+use bird_protocol::capture::ProtocolCaptureRecord;
+use bird_protocol::dissect::{Dissection, read_packet_id};
+use bird_protocol::frame::ProtocolFrameResult;
+use bird_protocol::version::ProtocolVersionedCursor;
+use bird_protocol::encryption::{ProtocolKeyPair, SHARED_SECRET_LENGTH, check_salted_verify_token_signature};
+use bird_protocol::derive::{BirdNbt, IntoOwned, ProtocolAll, ProtocolPacket, ProtocolPacketRegistry, ProtocolSize};
+#[cfg(test)]
+use bird_protocol::derive::ProtocolArbitrary;
+#[cfg(test)]
+use bird_protocol::arbitrary::{ArbitraryError, ArbitraryResult, ProtocolArbitrary, ProtocolVariantArbitrary, Unstructured};
+use bird_protocol::nbt::{
+    NBT_TAG_LONG_ARRAY, NBT_TAG_STRING, NbtBorrowedArray, NbtBorrowedI64Array, NbtByteArray, NbtLongArray, NbtTag,
+    NbtTagVariant, NbtValue, compound, write_nbt_str,
+};
 use bird_util::*;
-use crate::nbt::{NbtElement, read_compound_enter, read_named_nbt_tag, write_compound_enter, write_nbt_string};
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct Slot<'a> {
     #[bp(variant = VarInt)]
     pub item_id: i32,
     pub item_count: i8,
     #[bp(variant = NbtBytes)]
-    pub nbt: &'a [u8],
+    pub nbt: Cow<'a, [u8]>,
 }
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = i32, variant = VarInt)]
 pub enum HandshakeNextState {
     #[bp(value = 1)]
@@ -31,17 +44,19 @@ pub enum HandshakeNextState {
     Login,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x0, state = Handshake, bound = Server)]
 pub struct Handshake<'a> {
     #[bp(variant = VarInt)]
     pub protocol_version: i32,
-    pub server_address: &'a str,
+    #[bp(max_len = 255)]
+    pub server_address: Cow<'a, str>,
     pub server_port: u16,
     pub next_state: HandshakeNextState,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, IntoOwned, Clone, PartialEq, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct StatusResponseObject<'a> {
     #[serde(borrow)]
@@ -49,23 +64,23 @@ pub struct StatusResponseObject<'a> {
     #[serde(borrow)]
     pub players: StatusResponsePlayers<'a>,
     #[serde(borrow)]
-    pub description: either::Either<&'a str, Component<'a>>,
+    pub description: either::Either<Cow<'a, str>, Component<'a>>,
     #[serde(borrow)]
-    pub favicon: Option<&'a str>,
+    pub favicon: Option<Cow<'a, str>>,
     #[serde(default)]
     pub previews_chat: bool,
     #[serde(default)]
     pub enforces_secure_chat: bool,
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, IntoOwned, Clone, PartialEq, Debug)]
 pub struct StatusResponseVersion<'a> {
     #[serde(borrow)]
-    pub name: &'a str,
+    pub name: Cow<'a, str>,
     pub protocol: i32,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, IntoOwned, Clone, PartialEq, Debug)]
 pub struct StatusResponsePlayers<'a> {
     pub max: i32,
     #[serde(borrow)]
@@ -73,138 +88,199 @@ pub struct StatusResponsePlayers<'a> {
     pub online: i32,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, IntoOwned, Clone, PartialEq, Debug)]
 pub struct StatusResponsePlayersSample<'a> {
     #[serde(borrow)]
-    pub name: &'a str,
+    pub name: Cow<'a, str>,
     pub id: Uuid,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg(test)]
+impl<'a> ProtocolArbitrary<'a> for StatusResponseObject<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        let version = StatusResponseVersion { name: Cow::arbitrary(u)?, protocol: u.arbitrary()? };
+        let max = u.arbitrary()?;
+        let sample = (0..u.int_in_range(0..=4)?)
+            .map(|_| Ok(StatusResponsePlayersSample { name: Cow::arbitrary(u)?, id: Uuid::arbitrary(u)? }))
+            .collect::<ArbitraryResult<_>>()?;
+        Ok(Self {
+            version,
+            players: StatusResponsePlayers { max, sample, online: u.arbitrary()? },
+            description: match u.arbitrary()? {
+                true => either::Either::Left(Cow::arbitrary(u)?),
+                false => either::Either::Right(Component::arbitrary(u)?),
+            },
+            favicon: Option::arbitrary(u)?,
+            previews_chat: u.arbitrary()?,
+            enforces_secure_chat: u.arbitrary()?,
+        })
+    }
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x0, state = Status, bound = Client)]
 pub struct StatusResponseSS2C<'a>(
     #[bp(variant = Json)]
     pub StatusResponseObject<'a>
 );
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x1, state = Status, bound = Client)]
 pub struct PingResponseSS2C {
     pub payload: u64,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x0, state = Status, bound = Server)]
 pub struct StatusRequest;
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x1, state = Status, bound = Server)]
 pub struct PingRequestSC2S {
     pub payload: u64,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x0, state = Login, bound = Client)]
 pub struct LoginDisconnectLS2C<'a> {
     #[bp(variant = Json)]
     pub reason: Component<'a>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x1, state = Login, bound = Client)]
 pub struct EncryptionRequestLS2C<'a> {
-    pub server_id: &'a str,
+    #[bp(max_len = 20)]
+    pub server_id: Cow<'a, str>,
     #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
-    pub public_key: &'a [u8],
+    pub public_key: Cow<'a, [u8]>,
     #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
-    pub verify_token: &'a [u8],
+    pub verify_token: Cow<'a, [u8]>,
 }
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct Property<'a> {
-    pub name: &'a str,
-    pub value: &'a str,
-    pub signature: Option<&'a str>,
+    pub name: Cow<'a, str>,
+    pub value: Cow<'a, str>,
+    pub signature: Option<Cow<'a, str>>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x2, state = Login, bound = Client)]
 pub struct LoginSuccessLS2C<'a> {
     pub uuid: Uuid,
-    pub username: &'a str,
+    #[bp(max_len = 16)]
+    pub username: Cow<'a, str>,
     #[bp(variant = "LengthProvidedArray<i32, VarInt, Property<'a>, Property<'a>>")]
     pub properties: Cow<'a, [Property<'a>]>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x3, state = Login, bound = Client)]
 pub struct SetCompressionLS2C {
     #[bp(variant = VarInt)]
     pub threshold: i32,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x4, state = Login, bound = Client)]
 pub struct LoginPluginRequestLS2C<'a> {
     #[bp(variant = VarInt)]
     pub message_id: i32,
     pub channel: Identifier<'a>,
     #[bp(variant = RemainingBytesArray)]
-    pub data: &'a [u8],
+    pub data: Cow<'a, [u8]>,
 }
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct LoginStartSignatureData<'a> {
     pub timestamp: u64,
     #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
-    pub public_key: &'a [u8],
+    pub public_key: Cow<'a, [u8]>,
     #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
-    pub signature: &'a [u8],
+    pub signature: Cow<'a, [u8]>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x0, state = Login, bound = Server)]
 pub struct LoginStartLC2S<'a> {
-    pub name: &'a str,
+    #[bp(max_len = 16)]
+    pub name: Cow<'a, str>,
     pub signature_data: Option<LoginStartSignatureData<'a>>,
     pub uuid: Option<Uuid>,
 }
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = bool)]
 pub enum EncryptionResponseVariant<'a> {
     #[bp(value = true)]
     VerifyToken {
         #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
-        verify_token: &'a [u8]
+        verify_token: Cow<'a, [u8]>
     },
     #[bp(value = false)]
     Otherwise {
         salt: i64,
         #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
-        message_signature: &'a [u8],
+        message_signature: Cow<'a, [u8]>,
     },
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x1, state = Login, bound = Server)]
 pub struct EncryptionResponseLC2S<'a> {
     #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
-    pub shared_secret: &'a [u8],
+    pub shared_secret: Cow<'a, [u8]>,
     pub variant: EncryptionResponseVariant<'a>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+impl<'a> EncryptionResponseLC2S<'a> {
+    /// Checks the verify token (or its signature made with the player public key from the login start)
+    /// and returns the decrypted shared secret
+    pub fn decrypt_shared_secret(
+        &self, key_pair: &ProtocolKeyPair, verify_token: &[u8], player_public_key: Option<&[u8]>,
+    ) -> ProtocolResult<[u8; SHARED_SECRET_LENGTH]> {
+        match &self.variant {
+            EncryptionResponseVariant::VerifyToken { verify_token: encrypted } =>
+                key_pair.check_verify_token(encrypted, verify_token)?,
+            EncryptionResponseVariant::Otherwise { salt, message_signature } => match player_public_key {
+                Some(player_public_key) => check_salted_verify_token_signature(
+                    player_public_key, verify_token, *salt, message_signature,
+                )?,
+                None => return Err(anyhow::Error::msg("Player public key is required to check the signature").into()),
+            },
+        }
+        key_pair.decrypt_shared_secret(&self.shared_secret)
+    }
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x2, state = Login, bound = Server)]
 pub struct LoginPluginResponseLC2S<'a> {
     #[bp(variant = VarInt)]
     pub message_id: i32,
     pub successful: bool,
     #[bp(variant = RemainingBytesArray)]
-    pub data: &'a [u8],
+    pub data: Cow<'a, [u8]>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x0, state = Play, bound = Client)]
 pub struct SpawnEntityPS2C {
     #[bp(variant = VarInt)]
@@ -224,7 +300,8 @@ pub struct SpawnEntityPS2C {
     pub velocity: Vector3D<i16>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x1, state = Play, bound = Client)]
 pub struct SpawnExperienceOrbPS2C {
     #[bp(variant = VarInt)]
@@ -233,7 +310,8 @@ pub struct SpawnExperienceOrbPS2C {
     pub count: i16,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x2, state = Play, bound = Client)]
 pub struct SpawnPlayerPS2C {
     #[bp(variant = VarInt)]
@@ -246,7 +324,8 @@ pub struct SpawnPlayerPS2C {
     pub pitch: f32,
 }
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = u8)]
 pub enum EntityAnimation {
     SwingMainArm,
@@ -257,7 +336,8 @@ pub enum EntityAnimation {
     MagicCriticalEffect,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x3, state = Play, bound = Client)]
 pub struct EntityAnimationPS2C {
     #[bp(variant = VarInt)]
@@ -274,7 +354,8 @@ pub type AwardStatisticItem = i32;
 // Identifier entity id in award statistics
 pub type AwardStatisticEntity = i32;
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = i32, variant = VarInt)]
 pub enum AwardStatisticCustom {
     LeaveGame,
@@ -353,7 +434,8 @@ pub enum AwardStatisticCustom {
     InteractWithSmithingTable,
 }
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = i32, variant = VarInt)]
 pub enum AwardStatistic {
     Mined(
@@ -391,7 +473,8 @@ pub enum AwardStatistic {
     Custom(AwardStatisticCustom),
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x4, state = Play, bound = Client)]
 pub struct AwardStatisticsPS2C<'a> {
     #[bp(variant = "LengthProvidedArray<i32, VarInt, AwardStatistic, AwardStatistic>")]
@@ -400,14 +483,16 @@ pub struct AwardStatisticsPS2C<'a> {
     pub value: i32,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x5, state = Play, bound = Client)]
 pub struct AcknowledgeBlockChangePS2C {
     #[bp(variant = VarInt)]
     pub sequence_id: i32,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x6, state = Play, bound = Client)]
 pub struct SetBlockDestroyStagePS2C {
     #[bp(variant = VarInt)]
@@ -417,7 +502,8 @@ pub struct SetBlockDestroyStagePS2C {
     pub destroy_stage: u8,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x7, state = Play, bound = Client)]
 pub struct BlockEntityDataPS2C<'a> {
     #[bp(variant = BlockPosition)]
@@ -425,10 +511,11 @@ pub struct BlockEntityDataPS2C<'a> {
     #[bp(variant = VarInt)]
     pub ty: i32,
     #[bp(variant = NbtBytes)]
-    pub nbt_data: &'a [u8],
+    pub nbt_data: Cow<'a, [u8]>,
 }
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = u8)]
 pub enum BlockActionVariantPistonDirection {
     Down,
@@ -439,7 +526,8 @@ pub enum BlockActionVariantPistonDirection {
     East,
 }
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = u8)]
 pub enum BlockActionVariantBellDirection {
     Down,
@@ -450,7 +538,8 @@ pub enum BlockActionVariantBellDirection {
     East,
 }
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = i32, variant = VarInt, key_reverse = true)]
 pub enum BlockActionVariant {
     #[bp(value = "(bird_data::block_data::NOTE_BLOCK.id) as i32", ghost = [(order = begin, value = 0u8), (order = end, value = 0u8)])]
@@ -484,7 +573,8 @@ pub enum BlockActionVariant {
     },
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x8, state = Play, bound = Client)]
 pub struct BlockActionPS2C {
     #[bp(variant = BlockPosition)]
@@ -492,7 +582,8 @@ pub struct BlockActionPS2C {
     pub variant: BlockActionVariant,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x9, state = Play, bound = Client)]
 pub struct BlockUpdatePS2C {
     #[bp(variant = BlockPosition)]
@@ -501,7 +592,8 @@ pub struct BlockUpdatePS2C {
     pub block_id: i32,
 }
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = i32, variant = VarInt)]
 pub enum BossBarColor {
     Pink,
@@ -513,7 +605,8 @@ pub enum BossBarColor {
     White,
 }
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = i32, variant = VarInt)]
 pub enum BossBarDivision {
     Zero,
@@ -524,7 +617,8 @@ pub enum BossBarDivision {
 }
 
 #[bitfield(u8)]
-#[derive(ProtocolAll, PartialEq)]
+#[derive(ProtocolAll, IntoOwned, PartialEq)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct BossBarFlags {
     pub dark_sky: bool,
     pub dragon_bar: bool,
@@ -533,7 +627,8 @@ pub struct BossBarFlags {
     _pad: u8,
 }
 
-#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = i32, variant = VarInt)]
 pub enum BossBarAction<'a> {
     Add {
@@ -559,14 +654,16 @@ pub enum BossBarAction<'a> {
     },
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0xA, state = Play, bound = Client)]
 pub struct BossBarPS2C<'a> {
     pub uuid: Uuid,
     pub action: BossBarAction<'a>,
 }
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = u8)]
 pub enum Difficulty {
     Peaceful,
@@ -575,33 +672,30 @@ pub enum Difficulty {
     Hard,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0xB, state = Play, bound = Client)]
 pub struct ChangeDifficultyPS2C {
     pub difficulty: Difficulty,
     pub locked: bool,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0xC, state = Play, bound = Client)]
 pub struct ClearTitles {
     pub reset: bool,
 }
 
-// #[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
-// #[bp(id = 0xC, state = Play, bound = Client)]
-// pub struct ChatPreviewPS2C<'a> {
-//     pub query_id: i32,
-//     pub message: Option<Component<'a>>,
-// }
-
-#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct CommandSuggestionsMatch<'a> {
-    pub insert: &'a str,
+    pub insert: Cow<'a, str>,
     pub tooltip: Option<Component<'a>>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0xD, state = Play, bound = Client)]
 pub struct CommandSuggestionsResponsePS2C<'a> {
     #[bp(variant = VarInt)]
@@ -619,7 +713,8 @@ pub const LITERAL_NODE_TYPE: u8 = 1;
 pub const ARGUMENT_NODE_TYPE: u8 = 2;
 
 #[bitfield(i8)]
-#[derive(ProtocolAll, PartialEq)]
+#[derive(ProtocolAll, IntoOwned, PartialEq)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct BrigadierNodeFlags {
     #[bits(2)]
     pub node_type: u8,
@@ -630,7 +725,7 @@ pub struct BrigadierNodeFlags {
     _pad: u8,
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(IntoOwned, Clone, Copy, PartialEq, Debug)]
 pub struct BrigadierNodeRangeProperties<T> {
     pub min: Option<T>,
     pub max: Option<T>,
@@ -660,11 +755,11 @@ impl<'a, T> ProtocolReadable<'a> for BrigadierNodeRangeProperties<T>
     where T: ProtocolReadable<'a> {
     fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
         let flags = u8::read(cursor)?;
-        let min = match flags & 0x2 != 0 {
+        let min = match flags & 0x1 != 0 {
             true => Some(T::read(cursor)?),
             false => None,
         };
-        let max = match flags & 0x1 != 0 {
+        let max = match flags & 0x2 != 0 {
             true => Some(T::read(cursor)?),
             false => None,
         };
@@ -672,7 +767,16 @@ impl<'a, T> ProtocolReadable<'a> for BrigadierNodeRangeProperties<T>
     }
 }
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg(test)]
+impl<'a, T> ProtocolArbitrary<'a> for BrigadierNodeRangeProperties<T>
+    where T: ProtocolArbitrary<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        Ok(Self { min: Option::arbitrary(u)?, max: Option::arbitrary(u)? })
+    }
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = i32, variant = VarInt)]
 pub enum BrigadierNodeParserString {
     SingleWord,
@@ -681,7 +785,8 @@ pub enum BrigadierNodeParserString {
 }
 
 #[bitfield(u8)]
-#[derive(ProtocolAll, PartialEq)]
+#[derive(ProtocolAll, IntoOwned, PartialEq)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct BrigadierNodeParseEntity {
     pub single: bool,
     pub only_players: bool,
@@ -689,7 +794,8 @@ pub struct BrigadierNodeParseEntity {
     _gap: u8,
 }
 
-#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = i32, variant = VarInt)]
 pub enum BrigadierNodeParser<'a> {
     Bool,
@@ -750,85 +856,24 @@ pub enum BrigadierNodeParser<'a> {
     Uuid,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct BrigadierNode<'a> {
-    pub executable: bool,
+    pub flags: BrigadierNodeFlags,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, i32, VarInt>")]
     pub children: Cow<'a, [i32]>,
+    #[bp(flag = flags.redirect, variant = VarInt)]
     pub redirect_node: Option<i32>,
-    pub name: Option<&'a str>,
+    #[bp(if = "flags.node_type() != ROOT_NODE_TYPE")]
+    pub name: Option<Cow<'a, str>>,
+    #[bp(if = "flags.node_type() == ARGUMENT_NODE_TYPE")]
     pub parser: Option<BrigadierNodeParser<'a>>,
+    #[bp(flag = flags.suggestions_type)]
     pub suggestions_type: Option<Identifier<'a>>,
 }
 
-impl<'a> ProtocolSize for BrigadierNode<'a> {
-    const SIZE: Range<u32> = (
-        add_protocol_sizes_ty!(
-            u8,
-            LengthProvidedArray<i32, VarInt, i32, i32>,
-        ).start
-            ..
-            add_protocol_sizes_ty!(
-            u8,
-            LengthProvidedArray<i32, VarInt, i32, i32>,
-            VarInt,
-            &'a str,
-            BrigadierNodeParser<'a>,
-            Identifier<'a>,
-        ).end
-    );
-}
-
-impl<'a> ProtocolWritable for BrigadierNode<'a> {
-    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
-        let flags = BrigadierNodeFlags::new()
-            .with_node_type(match self.name {
-                Some(_) => match self.parser {
-                    Some(_) => ARGUMENT_NODE_TYPE,
-                    None => LITERAL_NODE_TYPE,
-                },
-                None => ROOT_NODE_TYPE,
-            })
-            .with_executable(self.executable)
-            .with_redirect(self.redirect_node.is_some())
-            .with_suggestions_type(self.suggestions_type.is_some());
-        flags.write(writer)?;
-        LengthProvidedArray::<i32, VarInt, i32, i32>::write_variant(&self.children, writer)?;
-        if let Some(ref to_write) = self.redirect_node { to_write.write(writer)? };
-        if let Some(ref to_write) = self.parser { to_write.write(writer)? };
-        if let Some(ref to_write) = self.suggestions_type { to_write.write(writer)? };
-        Ok(())
-    }
-}
-
-impl<'a> ProtocolReadable<'a> for BrigadierNode<'a> {
-    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
-        let flags = BrigadierNodeFlags::read(cursor)?;
-        let children = LengthProvidedArray::<i32, VarInt, i32, i32>::read_variant(cursor)?;
-        let redirect_node = match flags.redirect() {
-            true => Some(VarInt::read_variant(cursor)?),
-            false => None,
-        };
-        let (name, parser) = match flags.node_type() {
-            ROOT_NODE_TYPE => (None, None),
-            LITERAL_NODE_TYPE => (Some(<&'a str>::read(cursor)?), None),
-            _ => (Some(<&'a str>::read(cursor)?), Some(BrigadierNodeParser::read(cursor)?)),
-        };
-        let suggestions_type = match flags.suggestions_type() {
-            true => Some(Identifier::read(cursor)?),
-            false => None,
-        };
-        Ok(Self {
-            executable: flags.executable(),
-            children,
-            redirect_node,
-            name,
-            parser,
-            suggestions_type,
-        })
-    }
-}
-
-#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0xE, state = Play, bound = Client)]
 pub struct CommandsPS2C<'a> {
     #[bp(variant = "LengthProvidedArray<i32, VarInt, BrigadierNode<'a>, BrigadierNode<'a>>")]
@@ -839,13 +884,15 @@ pub struct CommandsPS2C<'a> {
 
 pub const PLAYER_INVENTORY_ID: u8 = 0;
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0xF, state = Play, bound = Client)]
 pub struct CloseContainerPS2C {
     pub window_id: u8,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x10, state = Play, bound = Client)]
 pub struct SetContainerContentPS2C<'a> {
     pub window_id: u8,
@@ -856,7 +903,7 @@ pub struct SetContainerContentPS2C<'a> {
     pub carried_item: Option<Slot<'a>>,
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(IntoOwned, Clone, Copy, PartialEq, Debug)]
 pub enum FurnaceProperty {
     FireIcon,
     MaximumFuelBurnTime,
@@ -864,14 +911,14 @@ pub enum FurnaceProperty {
     MaximumProgress,
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(IntoOwned, Clone, Copy, PartialEq, Debug)]
 pub enum EnchantmentTableSlot {
     Top,
     Middle,
     Bottom,
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(IntoOwned, Clone, Copy, PartialEq, Debug)]
 pub enum EnchantmentTableProperty {
     LevelRequirement(EnchantmentTableSlot),
     Seed,
@@ -879,20 +926,21 @@ pub enum EnchantmentTableProperty {
     EnchantmentLevel(EnchantmentTableSlot),
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(IntoOwned, Clone, Copy, PartialEq, Debug)]
 pub enum BeaconProperty {
     PowerLevel,
     FirstPotionEffect,
     SecondPotionEffect,
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(IntoOwned, Clone, Copy, PartialEq, Debug)]
 pub enum BrewingStandProperty {
     BrewTime,
     FuelTime,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x11, state = Play, bound = Client)]
 pub struct SetContainerPropertyPS2C {
     pub window_id: u8,
@@ -1009,7 +1057,8 @@ impl From<BrewingStandProperty> for i16 {
 pub const CURSOR_SLOT_ID: i16 = -1;
 pub const CURSOR_WINDOW_ID: i8 = -1;
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x12, state = Play, bound = Client)]
 pub struct SetContainerSlotPS2C<'a> {
     pub window_id: i8,
@@ -1019,7 +1068,8 @@ pub struct SetContainerSlotPS2C<'a> {
     pub slot_data: Option<Slot<'a>>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x13, state = Play, bound = Client)]
 pub struct SetCooldownPS2C {
     #[bp(variant = VarInt)]
@@ -1028,7 +1078,8 @@ pub struct SetCooldownPS2C {
     pub cooldown_ticks: i32,
 }
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = i32, variant = VarInt)]
 pub enum ChatSuggestionAction {
     Add,
@@ -1036,36 +1087,41 @@ pub enum ChatSuggestionAction {
     Set,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x14, state = Play, bound = Client)]
 pub struct ChatSuggestionsPS2C<'a> {
     pub action: ChatSuggestionAction,
-    #[bp(variant = "LengthProvidedArray<i32, VarInt, &'a str, &'a str>")]
-    pub entries: Cow<'a, [&'a str]>,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, Cow<'a, str>, Cow<'a, str>>")]
+    pub entries: Cow<'a, [Cow<'a, str>]>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x15, state = Play, bound = Client)]
 pub struct PluginMessagePS2C<'a> {
     pub channel: Identifier<'a>,
     #[bp(variant = RemainingBytesArray)]
-    pub data: &'a [u8],
+    pub data: Cow<'a, [u8]>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x16, state = Play, bound = Client)]
 pub struct DeleteMessagePS2C<'a> {
     #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
-    pub signature: &'a [u8],
+    pub signature: Cow<'a, [u8]>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x17, state = Play, bound = Client)]
 pub struct DisconnectPS2C<'a> {
     pub reason: Component<'a>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x18, state = Play, bound = Client)]
 pub struct DisguisedChatMessagePS2C<'a> {
     pub message: Component<'a>,
@@ -1075,9 +1131,10 @@ pub struct DisguisedChatMessagePS2C<'a> {
     pub target_name: Option<Component<'a>>,
 }
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = i32, variant = VarInt)]
-pub enum CustomSoundCategory {
+pub enum SoundCategory {
     Master,
     Music,
     Record,
@@ -1090,59 +1147,41 @@ pub enum CustomSoundCategory {
     Voice,
 }
 
-// #[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
-// #[bp(id = 0x17, state = Play, bound = Client)]
-// pub struct CustomSoundEffectPS2C<'a> {
-//     pub sound_name: Identifier<'a>,
-//     pub sound_category: CustomSoundCategory,
-//     #[bp(variant = "FixedPointNumber<i32, 3>")]
-//     pub effect_position_x: f32,
-//     #[bp(variant = "FixedPointNumber<i32, 3>")]
-//     pub effect_position_y: f32,
-//     #[bp(variant = "FixedPointNumber<i32, 3>")]
-//     pub effect_position_z: f32,
-//     pub volume: f32,
-//     pub pitch: f32,
-//     pub seed: i64,
-// }
-
-// #[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
-// #[bp(id = 0x18, state = Play, bound = Client)]
-// pub struct HideMessagePS2C<'a> {
-//     #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
-//     pub signature: &'a [u8],
-// }
-
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = i8)]
 pub enum EntityEventStatus {
     // TODO
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x19, state = Play, bound = Client)]
 pub struct EntityEventPS2C {
     pub entity_id: i32,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x1A, state = Play, bound = Client)]
 pub struct ExplosionPS2C<'a> {
     pub location: Vector3D<f32>,
     pub strength: f32,
     #[bp(variant = "LengthProvidedRawArray<i32, VarInt, Vector3D<i8>, Vector3D<i8>>")]
-    pub records: &'a [Vector3D<i8>],
+    pub records: Cow<'a, [Vector3D<i8>]>,
     pub motion: Vector3D<f32>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x1B, state = Play, bound = Client)]
 pub struct UnloadChunkPS2C {
     pub chunk_x: i32,
     pub chunk_z: i32,
 }
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = f32)]
 pub enum GameEventGameMode {
     Survival,
@@ -1151,7 +1190,8 @@ pub enum GameEventGameMode {
     Spectator,
 }
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = f32)]
 pub enum GameEventDemo {
     ShowWelcome,
@@ -1162,21 +1202,24 @@ pub enum GameEventDemo {
     TellDemoIsOver,
 }
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = f32)]
 pub enum GameEventWinGame {
     RespawnPlayer,
     RollTheCredits,
 }
 
-#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = f32)]
 pub enum GameEventRespawnScreen {
     EnableScreen,
     ImmediatelyRespawn,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x1C, state = Play, bound = Client, ty = u8)]
 pub enum GameEventPS2C {
     #[bp(ghost = [(order = begin, value = 0f32)])]
@@ -1199,7 +1242,8 @@ pub enum GameEventPS2C {
     EnableRespawnScreen(GameEventRespawnScreen),
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x1D, state = Play, bound = Client)]
 pub struct OpenHorseScreenPS2C {
     pub window_id: u8,
@@ -1208,7 +1252,8 @@ pub struct OpenHorseScreenPS2C {
     pub entity_id: i32,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x1E, state = Play, bound = Client)]
 pub struct InitializeWorldBorderPS2C {
     pub x: f64,
@@ -1225,7 +1270,8 @@ pub struct InitializeWorldBorderPS2C {
     pub warning_seconds: i32,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x1F, state = Play, bound = Client)]
 pub struct KeepAlivePS2C {
     pub keep_alive_id: i64,
@@ -1371,9 +1417,25 @@ pub const CHUNK_DATA_HEIGHT_MAP_KEY: &'static str = "MOTION_BLOCKING";
 
 // TODO should it be only MOTION_BLOCKING or WORLD_SURFACE also?
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, IntoOwned)]
 #[repr(transparent)]
-pub struct ChunkDataHeightMap<'a>(BorrowedLongArray<'a>);
+pub struct ChunkDataHeightMap<'a>(LongArray<'a>);
+
+#[derive(Clone, PartialEq, Debug, IntoOwned)]
+#[doc(hidden)]
+pub enum LongArray<'a> {
+    Raw(Cow<'a, [u8]>),
+    Longs(Cow<'a, [u64]>),
+}
+
+impl<'a> LongArray<'a> {
+    pub fn as_borrowed(&self) -> BorrowedLongArray {
+        match self {
+            Self::Raw(raw) => BorrowedLongArray::Raw(raw),
+            Self::Longs(longs) => BorrowedLongArray::Longs(longs),
+        }
+    }
+}
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 #[doc(hidden)]
@@ -1397,13 +1459,13 @@ impl<'a> Iterator for BorrowedLongArray<'a> {
     }
 }
 
-impl<'a> IntoIterator for ChunkDataHeightMap<'a> {
+impl<'a, 'b> IntoIterator for &'b ChunkDataHeightMap<'a> {
     type Item = u64;
-    type IntoIter = GapCompactLongsReader<BorrowedLongArray<'a>, 256>;
+    type IntoIter = GapCompactLongsReader<BorrowedLongArray<'b>, 256>;
 
     fn into_iter(self) -> Self::IntoIter {
         // SAFETY: It is sure that array of inner struct is not empty.
-        unsafe { Self::IntoIter::new(self.0, 9).unwrap_unchecked() }
+        unsafe { Self::IntoIter::new(self.0.as_borrowed(), 9).unwrap_unchecked() }
     }
 }
 
@@ -1412,14 +1474,14 @@ impl<'a> ChunkDataHeightMap<'a> {
     /// The caller must ensure that the length of data slice is 37 * 8
     pub const unsafe fn new_raw(data: &'a [u8]) -> Self {
         debug_assert!(data.len() == 37 * 8);
-        Self(BorrowedLongArray::Raw(data))
+        Self(LongArray::Raw(Cow::Borrowed(data)))
     }
 
     /// # Safety.
     /// The caller must ensure that the length of data is 37
     pub const unsafe fn new_longs(data: &'a [u64]) -> Self {
         debug_assert!(data.len() == 37);
-        Self(BorrowedLongArray::Longs(data))
+        Self(LongArray::Longs(Cow::Borrowed(data)))
     }
 }
 
@@ -1429,23 +1491,29 @@ impl<'a> ProtocolSize for ChunkDataHeightMap<'a> {
 
 impl<'a> ProtocolReadable<'a> for ChunkDataHeightMap<'a> {
     fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
-        read_compound_enter(cursor)?;
-        match read_named_nbt_tag(CHUNK_DATA_HEIGHT_MAP_KEY, cursor)? {
-            Some(NbtElement::LongArray(data)) => match data.len() == 37 * 8 {
-                true => Ok(Self(BorrowedLongArray::Raw(data))),
-                false => Err(ProtocolError::Any(anyhow::Error::msg("MOTION_BLOCKING must be NbtLongArray with exactly 37 length")))
-            },
-            _ => Err(ProtocolError::Any(anyhow::Error::msg("MOTION_BLOCKING is not NbtLongArray or not present"))),
+        compound::read_nbt_root(cursor)?;
+        let mut data = None;
+        compound::read_nbt_compound(cursor, |tag, name, cursor| {
+            match tag == NBT_TAG_LONG_ARRAY && name == CHUNK_DATA_HEIGHT_MAP_KEY && data.is_none() {
+                true => data = Some(<NbtLongArray as NbtTagVariant<NbtBorrowedI64Array>>::read_nbt_variant(cursor)?),
+                false => { NbtValue::skip_nbt_of(tag, cursor, 1)?; }
+            }
+            Ok(())
+        })?;
+        match data {
+            Some(NbtBorrowedArray::Raw(data)) if data.len() == 37 * 8 => Ok(Self(LongArray::Raw(Cow::Borrowed(data)))),
+            Some(_) => Err(ProtocolError::Any(anyhow::Error::msg("MOTION_BLOCKING must be NbtLongArray with exactly 37 length"))),
+            None => Err(ProtocolError::Any(anyhow::Error::msg("MOTION_BLOCKING is not NbtLongArray or not present"))),
         }
     }
 }
 
 impl<'a> ProtocolWritable for ChunkDataHeightMap<'a> {
     fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
-        write_compound_enter(writer)?;
-        12i8.write(writer)?;
-        write_nbt_string(CHUNK_DATA_HEIGHT_MAP_KEY, writer)?;
-        match self.0 {
+        compound::write_nbt_root("_", writer)?;
+        NBT_TAG_LONG_ARRAY.write(writer)?;
+        write_nbt_str(CHUNK_DATA_HEIGHT_MAP_KEY, writer)?;
+        match self.0.as_borrowed() {
             BorrowedLongArray::Raw(raw) => {
                 37i32.write(writer)?; // the length of raw
                 writer.write_bytes(raw)
@@ -1456,17 +1524,32 @@ impl<'a> ProtocolWritable for ChunkDataHeightMap<'a> {
     }
 }
 
+#[cfg(test)]
+impl<'a> ProtocolArbitrary<'a> for ChunkDataHeightMap<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        Ok(Self(LongArray::Raw(Cow::Borrowed(u.bytes(37 * 8)?))))
+    }
+}
+
 pub trait PalettedContainerBitsDeterminer {
     fn get(values: usize) -> u8;
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct PalettedContainer<T, const MAX_VALUE: i32, const LENGTH: usize> {
     inner: PalettedContainerInner<LENGTH>,
     _marker: PhantomData<T>,
 }
 
-#[derive(Clone, Debug)]
+impl<T: 'static, const MAX_VALUE: i32, const LENGTH: usize> IntoOwned for PalettedContainer<T, MAX_VALUE, LENGTH> {
+    type Owned = Self;
+
+    fn into_owned(self) -> Self {
+        self
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
 enum PalettedContainerInner<const LENGTH: usize> {
     Single(i32),
     Indirect(Vec<i32>, Box<[i32; LENGTH]>),
@@ -1597,16 +1680,44 @@ impl<'a, T, const MAX_VALUE: i32, const LENGTH: usize> ProtocolReadable<'a> for
     }
 }
 
-#[derive(ProtocolAll, Clone, Copy, Debug)]
+#[cfg(test)]
+impl<'a, T, const MAX_VALUE: i32, const LENGTH: usize> ProtocolArbitrary<'a> for PalettedContainer<T, MAX_VALUE, LENGTH>
+    where
+        T: PalettedContainerBitsDeterminer {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        let arbitrary_indexes = |u: &mut Unstructured<'a>, max: i32| -> ArbitraryResult<Box<[i32; LENGTH]>> {
+            (0..LENGTH)
+                .map(|_| u.int_in_range(0..=max))
+                .collect::<ArbitraryResult<Vec<_>>>()?
+                .into_boxed_slice()
+                .try_into()
+                .map_err(|_| ArbitraryError::IncorrectFormat)
+        };
+        Ok(match u.choose_index(3)? {
+            0 => Self::new_single(u.int_in_range(0..=MAX_VALUE - 1)?),
+            1 => {
+                let values: Vec<i32> = (0..u.int_in_range(2..=16)?)
+                    .map(|_| u.int_in_range(0..=MAX_VALUE - 1))
+                    .collect::<ArbitraryResult<_>>()?;
+                let indexes = arbitrary_indexes(u, values.len() as i32 - 1)?;
+                Self::new_indirect(values, indexes)
+            }
+            _ => Self::new_direct(arbitrary_indexes(u, MAX_VALUE - 1)?),
+        })
+    }
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct ChunkSectionsData<'a> {
     #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
-    pub data: &'a [u8],
+    pub data: Cow<'a, [u8]>,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct BlockStatesBits;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct BiomesBits;
 
 impl PalettedContainerBitsDeterminer for BlockStatesBits {
@@ -1624,33 +1735,23 @@ impl PalettedContainerBitsDeterminer for BiomesBits {
     }
 }
 
-#[derive(ProtocolSize, ProtocolWritable, Clone, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct ChunkSectionData {
     pub block_count: i16,
     pub block_states: PalettedContainer<BlockStatesBits, { bird_data::BLOCK_STATE_COUNT as i32 }, 4096>,
     pub biomes: PalettedContainer<BiomesBits, { bird_data::BIOME_COUNT as i32 }, 64>,
 }
 
-// TODO fix issue with ProtocolReadable proc-macro (now it is not working)
-
-impl<'a> ProtocolReadable<'a> for ChunkSectionData {
-    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
-        Ok(Self {
-            block_count: i16::read(cursor)?,
-            block_states: PalettedContainer::read(cursor)?,
-            biomes: PalettedContainer::read(cursor)?,
-        })
-    }
-}
-
-#[derive(ProtocolAll, Clone, Copy, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct ChunkData<'a> {
     pub height_map: ChunkDataHeightMap<'a>,
     pub chunk_sections: ChunkSectionsData<'a>,
 }
 
-#[derive(Clone, Copy, Debug)]
-pub struct BitSet<'a>(BorrowedLongArray<'a>);
+#[derive(IntoOwned, Clone, PartialEq, Debug)]
+pub struct BitSet<'a>(LongArray<'a>);
 
 impl<'a> BitSet<'a> {
     fn get_bit_from_words(words: &[u64], index: usize) -> Option<bool> {
@@ -1681,18 +1782,18 @@ impl<'a> BitSet<'a> {
     }
 
     pub const fn new_words(words: &'a [u64]) -> Self {
-        Self(BorrowedLongArray::Longs(words))
+        Self(LongArray::Longs(Cow::Borrowed(words)))
     }
 
     /// # Safety
     /// The caller must ensure that the length of raw can be divided by 8
     pub const unsafe fn new_raw(raw: &'a [u8]) -> Self {
         debug_assert!(raw.len() % 8 == 0);
-        Self(BorrowedLongArray::Raw(raw))
+        Self(LongArray::Raw(Cow::Borrowed(raw)))
     }
 
     pub fn get(&self, index: usize) -> Option<bool> {
-        match self.0 {
+        match self.0.as_borrowed() {
             BorrowedLongArray::Raw(raw) => unsafe { Self::get_bit_from_raw(raw, index) },
             BorrowedLongArray::Longs(words) => Self::get_bit_from_words(words, index)
         }
@@ -1702,8 +1803,8 @@ impl<'a> BitSet<'a> {
         self.get(index).unwrap_or(false)
     }
 
-    pub fn long_iter(&self) -> impl Iterator<Item=u64> + 'a {
-        self.clone().0
+    pub fn long_iter(&self) -> impl Iterator<Item=u64> + '_ {
+        self.0.as_borrowed()
     }
 }
 
@@ -1713,7 +1814,7 @@ impl<'a> ProtocolSize for BitSet<'a> {
 
 impl<'a> ProtocolWritable for BitSet<'a> {
     fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
-        match self.0 {
+        match self.0.as_borrowed() {
             BorrowedLongArray::Raw(raw) => {
                 VarInt::write_variant(&(raw.len() as i32 / 8), writer)?;
                 writer.write_bytes(raw);
@@ -1728,11 +1829,19 @@ impl<'a> ProtocolWritable for BitSet<'a> {
 impl<'a> ProtocolReadable<'a> for BitSet<'a> {
     fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
         let length: i32 = VarInt::read_variant(cursor)?;
-        Ok(Self(BorrowedLongArray::Raw(cursor.take_bytes((length * 8) as usize)?)))
+        Ok(Self(LongArray::Raw(Cow::Borrowed(cursor.take_bytes((length * 8) as usize)?))))
+    }
+}
+
+#[cfg(test)]
+impl<'a> ProtocolArbitrary<'a> for BitSet<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        let length = bird_protocol::arbitrary::arbitrary_length(u)?;
+        Ok(Self(LongArray::Raw(Cow::Borrowed(u.bytes(length * 8)?))))
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct OwnedBitSet {
     pub words: Vec<u64>,
 }
@@ -1766,10 +1875,10 @@ impl OwnedBitSet {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(IntoOwned, Clone, PartialEq, Debug)]
 pub struct LightArray<'a> {
-    // TODO change it to &'a [u8; 2048]
-    bytes: &'a [u8],
+    // TODO change it to Cow<'a, [u8; 2048]>
+    bytes: Cow<'a, [u8]>,
 }
 
 impl<'a> ProtocolSize for LightArray<'a> {
@@ -1779,7 +1888,7 @@ impl<'a> ProtocolSize for LightArray<'a> {
 impl<'a> ProtocolWritable for LightArray<'a> {
     fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
         VarInt::write_variant(&2048, writer)?;
-        writer.write_bytes(self.bytes);
+        writer.write_bytes(&self.bytes);
         Ok(())
     }
 }
@@ -1790,7 +1899,15 @@ impl<'a> ProtocolReadable<'a> for LightArray<'a> {
         if length != 2048i32 {
             return Err(ProtocolError::Any(anyhow::Error::msg("The length of light array is not 2048")));
         }
-        Ok(Self { bytes: cursor.take_bytes(2048)? })
+        Ok(Self { bytes: Cow::Borrowed(cursor.take_bytes(2048)?) })
+    }
+}
+
+#[cfg(test)]
+impl<'a> ProtocolArbitrary<'a> for LightArray<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        // the arbitrary data is shorter than a light array, so a single light level is repeated
+        Ok(Self { bytes: Cow::Owned(vec![u8::arbitrary(u)?; 2048]) })
     }
 }
 
@@ -1815,21 +1932,21 @@ impl<'a> LightArray<'a> {
     /// The caller must ensure that the length of bytes is 2048
     pub const unsafe fn new(bytes: &'a [u8]) -> Self {
         debug_assert!(bytes.len() == 2048);
-        Self { bytes }
+        Self { bytes: Cow::Borrowed(bytes) }
     }
 
-    pub const fn get_bytes(&self) -> &'a [u8] {
-        self.bytes
+    pub fn get_bytes(&self) -> &[u8] {
+        &self.bytes
     }
 
     /// # Safety
     /// The caller must ensure that each parameter is less than 16
-    pub const unsafe fn get(&self, position: Vector3D<u8>) -> u8 {
-        Self::get_from_array(self.bytes, position)
+    pub unsafe fn get(&self, position: Vector3D<u8>) -> u8 {
+        Self::get_from_array(&self.bytes, position)
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct OwnedLightArray {
     data: [u8; 2048],
     // We are counting not empty bytes
@@ -1870,7 +1987,8 @@ impl OwnedLightArray {
     }
 }
 
-#[derive(ProtocolAll, Clone, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct LightData<'a> {
     pub trust_edges: bool,
     pub sky_light_mask: BitSet<'a>,
@@ -1884,7 +2002,8 @@ pub struct LightData<'a> {
 }
 
 #[bitfield(u8)]
-#[derive(ProtocolAll)]
+#[derive(ProtocolAll, IntoOwned, PartialEq)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct PackedBlockChunkXZ {
     #[bits(4)]
     pub x: u8,
@@ -1892,17 +2011,19 @@ pub struct PackedBlockChunkXZ {
     pub z: u8,
 }
 
-#[derive(ProtocolAll, Clone, Copy, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct ChunkDataAndUpdateLightBlockEntity<'a> {
     pub xz: PackedBlockChunkXZ,
     pub y: i16,
     #[bp(variant = VarInt)]
     pub ty: i32,
     #[bp(variant = NbtBytes)]
-    pub data: &'a [u8],
+    pub data: Cow<'a, [u8]>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x20, state = Play, bound = Client)]
 pub struct ChunkDataAndUpdateLightPS2C<'a> {
     pub chunk: Vector2D<i32>,
@@ -1912,7 +2033,7 @@ pub struct ChunkDataAndUpdateLightPS2C<'a> {
     pub light_data: LightData<'a>,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(IntoOwned, Clone, Copy, PartialEq, Debug)]
 pub enum SmokeDirection {
     Down,
     Up,
@@ -1938,7 +2059,7 @@ impl TryFrom<u8> for SmokeDirection {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(IntoOwned, Clone, Copy, PartialEq, Debug)]
 pub enum WorldEvent {
     // Sounds
     Dispense,
@@ -2152,7 +2273,7 @@ impl WorldEvent {
     }
 }
 
-#[derive(ProtocolPacket, Clone, Copy, Debug)]
+#[derive(ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
 #[bp(id = 0x21, state = Play, bound = Client)]
 pub struct WorldEventPS2C {
     pub event: WorldEvent,
@@ -2161,7 +2282,7 @@ pub struct WorldEventPS2C {
 }
 
 impl ProtocolSize for WorldEventPS2C {
-    const SIZE: Range<u32> = add_protocol_sizes_ty!(i32, i32, Vector3D<i32>, bool);
+    const SIZE: Range<u32> = add_protocol_sizes_ty!(i32, BlockPosition, i32, bool);
 }
 
 impl ProtocolWritable for WorldEventPS2C {
@@ -2189,8 +2310,29 @@ impl<'a> ProtocolReadable<'a> for WorldEventPS2C {
     }
 }
 
+#[cfg(test)]
+impl<'a> ProtocolArbitrary<'a> for WorldEventPS2C {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        let event_id = match u.int_in_range(0..=63)? {
+            index @ 0..=43 => 1000 + index,
+            index @ 44..=47 => 1456 + index,
+            index @ 48..=57 => 1952 + index,
+            index => 2942 + index,
+        };
+        let event_data = match event_id {
+            2000 => u.int_in_range(0..=5)?,
+            _ => u.arbitrary()?,
+        };
+        Ok(Self {
+            event: WorldEvent::new(event_id, event_data).ok_or(ArbitraryError::IncorrectFormat)?,
+            location: BlockPosition::arbitrary_variant(u)?,
+            disable_relative_volume: u.arbitrary()?,
+        })
+    }
+}
+
 #[repr(u8)]
-#[derive(ProtocolSize, Clone, Copy, Debug, PartialEq)]
+#[derive(ProtocolSize, IntoOwned, Clone, Debug, PartialEq)]
 #[bp(variant = VarInt, ty = i32)]
 pub enum Particle<'a> {
     AmbientEntityEffect,
@@ -2330,6 +2472,7 @@ impl<'a> Particle<'a> {
                 to_green: f32::read(cursor)?,
                 to_blue: f32::read(cursor)?,
             },
+            24 => Self::FallingDust { block_state: VarInt::read_variant(cursor)? },
             35 => Self::Item { slot: Option::read(cursor)? },
             36 => Self::Vibration {
                 variant: match <&'a str>::read(cursor)? {
@@ -2338,7 +2481,7 @@ impl<'a> Particle<'a> {
                         entity_id: VarInt::read_variant(cursor)?,
                         entity_eye_height: f32::read(cursor)?,
                     },
-                    other => VibrationVariant::Other { source_type: other },
+                    other => VibrationVariant::Other { source_type: Cow::Borrowed(other) },
                 },
                 ticks: i32::read(cursor)?,
             },
@@ -2350,7 +2493,7 @@ impl<'a> Particle<'a> {
                     arr
                 })
             },
-            _ => Err(ProtocolError::Any(anyhow::Error::msg("Bad particle id")))?,
+            _ => Err(ProtocolError::BadEnumKey { ty: "Particle", key: id.to_string() })?,
         })
     }
 
@@ -2362,6 +2505,7 @@ impl<'a> Particle<'a> {
         match self {
             Self::Block { block_state } => VarInt::write_variant(block_state, writer),
             Self::BlockMarker { block_state } => VarInt::write_variant(block_state, writer),
+            Self::FallingDust { block_state } => VarInt::write_variant(block_state, writer),
             Self::Dust { red, green, blue, scale, } => {
                 red.write(writer)?;
                 green.write(writer)?;
@@ -2401,7 +2545,62 @@ impl<'a> Particle<'a> {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+/// Particle with the id before its data, as it is sent in entity metadata
+impl<'a> ProtocolWritable for Particle<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        VarInt::write_variant(&self.get_id(), writer)?;
+        self.write_data(writer)
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for Particle<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let particle_id = VarInt::read_variant(cursor)?;
+        Particle::read(particle_id, cursor)
+    }
+}
+
+#[cfg(test)]
+impl<'a> ProtocolArbitrary<'a> for Particle<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        Ok(match u.int_in_range(0..=87)? {
+            2 => Self::Block { block_state: u.arbitrary()? },
+            3 => Self::BlockMarker { block_state: u.arbitrary()? },
+            14 => Self::Dust {
+                red: u.arbitrary()?,
+                green: u.arbitrary()?,
+                blue: u.arbitrary()?,
+                scale: u.int_in_range(1..=400)? as f32 / 100.0,
+            },
+            15 => Self::DustColorTransition {
+                from_red: u.arbitrary()?,
+                from_green: u.arbitrary()?,
+                from_blue: u.arbitrary()?,
+                scale: u.int_in_range(1..=400)? as f32 / 100.0,
+                to_red: u.arbitrary()?,
+                to_green: u.arbitrary()?,
+                to_blue: u.arbitrary()?,
+            },
+            24 => Self::FallingDust { block_state: u.arbitrary()? },
+            35 => Self::Item { slot: Option::arbitrary(u)? },
+            36 => Self::Vibration {
+                variant: match u.choose_index(3)? {
+                    0 => VibrationVariant::Block { position: BlockPosition::arbitrary_variant(u)? },
+                    1 => VibrationVariant::Entity { entity_id: u.arbitrary()?, entity_eye_height: u.arbitrary()? },
+                    _ => match Cow::arbitrary(u)? {
+                        source_type if source_type != "minecraft:block" && source_type != "minecraft:entity" =>
+                            VibrationVariant::Other { source_type },
+                        _ => return Err(ArbitraryError::IncorrectFormat),
+                    },
+                },
+                ticks: u.arbitrary()?,
+            },
+            id => Self::read(id, &mut [].as_slice()).map_err(|_| ArbitraryError::IncorrectFormat)?,
+        })
+    }
+}
+
+#[derive(IntoOwned, Clone, Debug, PartialEq)]
 pub enum VibrationVariant<'a> {
     Block {
         position: Vector3D<i32>
@@ -2411,7 +2610,7 @@ pub enum VibrationVariant<'a> {
         entity_eye_height: f32,
     },
     Other {
-        source_type: &'a str,
+        source_type: Cow<'a, str>,
     },
 }
 
@@ -2419,7 +2618,7 @@ impl<'a> ProtocolSize for VibrationVariant<'a> {
     const SIZE: Range<u32> = add_protocol_sizes_ty!(&str).start..add_protocol_sizes_ty!(&str, Vector3D<i32>).end;
 }
 
-#[derive(ProtocolPacket, Clone, Copy, Debug)]
+#[derive(ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
 #[bp(id = 0x22, state = Play, bound = Client)]
 pub struct ParticlePS2C<'a> {
     pub particle: Particle<'a>,
@@ -2460,14 +2659,30 @@ impl<'a> ProtocolReadable<'a> for ParticlePS2C<'a> {
     }
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Debug)]
+#[cfg(test)]
+impl<'a> ProtocolArbitrary<'a> for ParticlePS2C<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        Ok(Self {
+            particle: Particle::arbitrary(u)?,
+            long_distance: u.arbitrary()?,
+            position: Vector3D::arbitrary(u)?,
+            offset: Vector3D::arbitrary(u)?,
+            max_speed: u.arbitrary()?,
+            particle_count: u.arbitrary()?,
+        })
+    }
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x23, state = Play, bound = Client)]
 pub struct UpdateLightPS2C<'a> {
     pub chunk: Vector2D<i32>,
     pub light_data: LightData<'a>,
 }
 
-#[derive(ProtocolAll, Clone, Copy, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = i8)]
 pub enum PreviousLoginGameMode {
     #[bp(value = - 1)]
@@ -2478,7 +2693,8 @@ pub enum PreviousLoginGameMode {
     Spectator,
 }
 
-#[derive(ProtocolAll, Clone, Copy, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = u8)]
 pub enum LoginGameMode {
     Survival,
@@ -2487,14 +2703,16 @@ pub enum LoginGameMode {
     Spectator,
 }
 
-#[derive(ProtocolAll, Clone, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct LoginDeathLocation<'a> {
     pub dimension_name: Identifier<'a>,
     #[bp(variant = BlockPosition)]
     pub location: Vector3D<i32>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x24, state = Play, bound = Client)]
 pub struct LoginPS2C<'a> {
     pub entity_id: i32,
@@ -2504,7 +2722,7 @@ pub struct LoginPS2C<'a> {
     #[bp(variant = "LengthProvidedArray<i32, VarInt, Identifier<'a>, Identifier<'a>>")]
     pub dimensions: Cow<'a, [Identifier<'a>]>,
     #[bp(variant = NbtBytes)]
-    pub registry_codec: &'a [u8],
+    pub registry_codec: Cow<'a, [u8]>,
     pub dimension_type: Identifier<'a>,
     pub dimension_name: Identifier<'a>,
     pub hashed_seed: i64,
@@ -2521,7 +2739,7 @@ pub struct LoginPS2C<'a> {
     pub death_location: Option<LoginDeathLocation<'a>>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, IntoOwned, Clone, PartialEq, Debug)]
 pub struct LoginRegistryCodec<'a> {
     #[serde(rename = "minecraft:dimension_type", borrow)]
     pub dimension_type: LoginRegistryCodecRegistry<'a, LoginRegistryCodecDimension<'a>>,
@@ -2531,7 +2749,7 @@ pub struct LoginRegistryCodec<'a> {
     pub chat_type: LoginRegistryCodecRegistry<'a, LoginRegistryCodecChatType<'a>>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, IntoOwned, Clone, PartialEq, Debug)]
 pub struct LoginRegistryCodecRegistry<'a, T: Clone> {
     #[serde(rename = "type", borrow)]
     pub ty: Cow<'a, str>,
@@ -2539,7 +2757,7 @@ pub struct LoginRegistryCodecRegistry<'a, T: Clone> {
     pub value: Cow<'a, [LoginRegistryCodecValue<'a, T>]>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, IntoOwned, Clone, PartialEq, Debug)]
 pub struct LoginRegistryCodecValue<'a, T: Clone> {
     #[serde(borrow)]
     pub name: Cow<'a, str>,
@@ -2547,7 +2765,7 @@ pub struct LoginRegistryCodecValue<'a, T: Clone> {
     pub element: T,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, IntoOwned, Clone, PartialEq, Debug)]
 pub struct LoginRegistryCodecDimension<'a> {
     pub piglin_safe: bool,
     pub has_raids: bool,
@@ -2571,7 +2789,7 @@ pub struct LoginRegistryCodecDimension<'a> {
     pub has_ceiling: bool,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, IntoOwned, Clone, PartialEq, Debug)]
 pub struct LoginRegistryCodecWorldgenBiome<'a> {
     #[serde(borrow)]
     pub precipitation: Cow<'a, str>,
@@ -2603,7 +2821,7 @@ pub struct LoginRegistryCodecWorldgenBiome<'a> {
     pub particle: Option<LoginRegistryCodecWorldgenBiomeParticle<'a>>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, IntoOwned, Clone, PartialEq, Debug)]
 pub struct LoginRegistryCodecWorldgenBiomeMusic<'a> {
     pub replace_current_music: bool,
     #[serde(borrow)]
@@ -2612,14 +2830,14 @@ pub struct LoginRegistryCodecWorldgenBiomeMusic<'a> {
     pub min_delay: i32,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, IntoOwned, Clone, PartialEq, Debug)]
 pub struct LoginRegistryCodecWorldgenBiomeAdditionsSound<'a> {
     #[serde(borrow)]
     pub sound: Cow<'a, str>,
     pub tick_chance: f64,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, IntoOwned, Clone, PartialEq, Debug)]
 pub struct LoginRegistryCodecWorldgenBiomeMoodSound<'a> {
     pub sound: Cow<'a, str>,
     pub tick_delay: i32,
@@ -2627,20 +2845,20 @@ pub struct LoginRegistryCodecWorldgenBiomeMoodSound<'a> {
     pub block_search_extent: i32,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, IntoOwned, Clone, PartialEq, Debug)]
 pub struct LoginRegistryCodecWorldgenBiomeParticle<'a> {
     pub probability: f32,
     #[serde(borrow)]
     pub ty: LoginRegistryCodecWorldgenBiomeParticleType<'a>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, IntoOwned, Clone, PartialEq, Debug)]
 pub struct LoginRegistryCodecWorldgenBiomeParticleType<'a> {
     #[serde(rename = "type", borrow)]
     pub ty: Cow<'a, str>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, IntoOwned, Clone, PartialEq, Debug)]
 pub struct LoginRegistryCodecChatType<'a> {
     #[serde(borrow)]
     pub translation_key: Cow<'a, str>,
@@ -2650,7 +2868,8 @@ pub struct LoginRegistryCodecChatType<'a> {
     pub parameters: Cow<'a, [Cow<'a, str>]>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x25, state = Play, bound = Client)]
 pub struct MapDataPS2C<'a> {
     #[bp(variant = VarInt)]
@@ -2663,24 +2882,25 @@ pub struct MapDataPS2C<'a> {
     pub col_rows: Option<MapDataColRows<'a>>,
 }
 
-#[derive(ProtocolAll, Clone, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct MapDataColRows<'a> {
     pub columns: u8,
     pub rows: u8,
     pub x: i8,
     pub z: i8,
     #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
-    pub data: &'a [u8],
+    pub data: Cow<'a, [u8]>,
 }
 
-#[derive(BirdNbt, Clone, Debug)]
+#[derive(BirdNbt, IntoOwned, Clone, PartialEq, Debug)]
 pub struct MapDataColRowsDataRoot<'a> {
     pub data: MapDataColRowsData<'a>,
     #[bnbt(name = "DataVersion")]
     pub data_version: Option<i32>,
 }
 
-#[derive(BirdNbt, Clone, Debug)]
+#[derive(BirdNbt, IntoOwned, Clone, PartialEq, Debug)]
 pub struct MapDataColRowsData<'a> {
     pub scale: i8,
     pub dimension: Cow<'a, str>,
@@ -2696,10 +2916,10 @@ pub struct MapDataColRowsData<'a> {
     pub banners: MapDataColRowsBannersData<'a>,
     pub frames: MapDataColRowsFramesData,
     #[bnbt(variant = "NbtByteArray")]
-    pub colors: &'a [u8],
+    pub colors: Cow<'a, [u8]>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(IntoOwned, Clone, PartialEq, Debug)]
 pub enum MapDataColRowsBannersColorData {
     White,
     Orange,
@@ -2744,7 +2964,8 @@ impl<'a> NbtTag<'a> for MapDataColRowsBannersColorData {
     }
 
     fn read_nbt<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
-        Ok(match Cow::read_nbt(cursor)?.as_ref() {
+        let color: Cow<str> = Cow::read_nbt(cursor)?;
+        Ok(match color.as_ref() {
             "white" => Self::White,
             "orange" => Self::Orange,
             "magenta" => Self::Magenta,
@@ -2761,7 +2982,7 @@ impl<'a> NbtTag<'a> for MapDataColRowsBannersColorData {
             "green" => Self::Green,
             "red" => Self::Red,
             "black" => Self::Black,
-            _ => Err(ProtocolError::Any(anyhow::Error::msg("Bad color")))?,
+            _ => Err(ProtocolError::BadEnumKey { ty: "MapDataColRowsBannersColorData", key: color.to_string() })?,
         })
     }
 
@@ -2770,7 +2991,7 @@ impl<'a> NbtTag<'a> for MapDataColRowsBannersColorData {
     }
 }
 
-#[derive(BirdNbt, Clone, Debug)]
+#[derive(BirdNbt, IntoOwned, Clone, PartialEq, Debug)]
 pub struct MapDataColRowsBannersData<'a> {
     #[bnbt(name = "Color")]
     pub color: MapDataColRowsBannersColorData,
@@ -2780,7 +3001,7 @@ pub struct MapDataColRowsBannersData<'a> {
     pub pos: Vector3D<i32>,
 }
 
-#[derive(BirdNbt, Clone, Debug)]
+#[derive(BirdNbt, IntoOwned, Clone, PartialEq, Debug)]
 pub struct MapDataColRowsFramesData {
     #[bnbt(name = "EntityId")]
     pub entity_id: i32,
@@ -2790,7 +3011,8 @@ pub struct MapDataColRowsFramesData {
     pub pos: Vector3D<i32>,
 }
 
-#[derive(ProtocolAll, Clone, Copy, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(variant = VarInt, ty = i32)]
 pub enum MapDataIconType {
     WhiteArrow,
@@ -2822,7 +3044,8 @@ pub enum MapDataIconType {
     TreasureMarker,
 }
 
-#[derive(ProtocolAll, Clone, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct MapDataIcon<'a> {
     pub ty: MapDataIconType,
     pub x: i8,
@@ -2831,7 +3054,8 @@ pub struct MapDataIcon<'a> {
     pub display_name: Option<Component<'a>>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x26, state = Play, bound = Client)]
 pub struct MerchantOffersPS2C<'a> {
     #[bp(variant = VarInt)]
@@ -2846,7 +3070,8 @@ pub struct MerchantOffersPS2C<'a> {
     pub can_restock: bool,
 }
 
-#[derive(ProtocolAll, Clone, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct MerchantOffersTrades<'a> {
     pub input_item1: Option<Slot<'a>>,
     pub output_item: Option<Slot<'a>>,
@@ -2860,7 +3085,8 @@ pub struct MerchantOffersTrades<'a> {
     pub demand: i32,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x27, state = Play, bound = Client)]
 pub struct UpdateEntityPositionPS2C {
     #[bp(variant = VarInt)]
@@ -2869,7 +3095,8 @@ pub struct UpdateEntityPositionPS2C {
     pub on_ground: bool,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x28, state = Play, bound = Client)]
 pub struct UpdateEntityPositionAndRotationPS2C {
     #[bp(variant = VarInt)]
@@ -2882,7 +3109,8 @@ pub struct UpdateEntityPositionAndRotationPS2C {
     pub on_ground: bool,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x29, state = Play, bound = Client)]
 pub struct UpdateEntityRotationPS2C {
     #[bp(variant = VarInt)]
@@ -2894,7 +3122,8 @@ pub struct UpdateEntityRotationPS2C {
     pub on_ground: bool,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x2A, state = Play, bound = Client)]
 pub struct MoveVehiclePS2C {
     pub pos: Vector3D<f64>,
@@ -2904,13 +3133,15 @@ pub struct MoveVehiclePS2C {
     pub pitch: f32,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x2B, state = Play, bound = Client)]
 pub struct OpenBookPS2C {
     pub off_hand: bool,
 }
 
-#[derive(ProtocolAll, Clone, Copy, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(variant = VarInt, ty = i32)]
 pub enum InventorySizeType {
     Inventory1,
@@ -2939,7 +3170,8 @@ pub enum InventorySizeType {
     Stonecutter,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x2C, state = Play, bound = Client)]
 pub struct OpenScreenPS2C<'a> {
     #[bp(variant = VarInt)]
@@ -2948,27 +3180,31 @@ pub struct OpenScreenPS2C<'a> {
     pub window_title: Component<'a>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x2D, state = Play, bound = Client)]
 pub struct OpenSignEditorPS2C {
     #[bp(variant = BlockPosition)]
     pub location: Vector3D<i32>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x2E, state = Play, bound = Client)]
 pub struct PingPS2C {
     pub id: i32,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x2F, state = Play, bound = Client)]
 pub struct PlaceGhostRecipePS2C<'a> {
     pub window_id: i8,
     pub recipe: Identifier<'a>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x30, state = Play, bound = Client)]
 pub struct PlayerAbilitiesPS2C {
     pub flags: PlayerAbilitiesFlags,
@@ -2977,7 +3213,8 @@ pub struct PlayerAbilitiesPS2C {
 }
 
 #[bitfield(u8)]
-#[derive(ProtocolAll)]
+#[derive(ProtocolAll, IntoOwned, PartialEq)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct PlayerAbilitiesFlags {
     pub invulnerable: bool,
     pub flying: bool,
@@ -2987,15 +3224,17 @@ pub struct PlayerAbilitiesFlags {
     _gap: u8,
 }
 
-#[derive(ProtocolAll, Clone, Copy, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct PlayerChatPreviousMessage<'a> {
     #[bp(variant = VarInt)]
     pub message_id: i32,
-    #[bp(variant = "ProtocolVariantOption<&'a [u8; 256], ConstLengthRawArray<u8, 256>>")]
-    pub signature: Option<&'a [u8; 256]>,
+    #[bp(variant = "ProtocolVariantOption<Cow<'a, [u8; 256]>, ConstLengthRawArray<u8, 256>>")]
+    pub signature: Option<Cow<'a, [u8; 256]>>,
 }
 
-#[derive(ProtocolAll, Clone, Copy, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(variant = VarInt, ty = i32)]
 pub enum PlayerChatFilter<'a> {
     PassThrough,
@@ -3003,15 +3242,16 @@ pub enum PlayerChatFilter<'a> {
     PartiallyFiltered { bits: BitSet<'a> },
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x31, state = Play, bound = Client)]
 pub struct PlayerChatMessagePS2C<'a> {
     pub sender: Uuid,
     #[bp(variant = VarInt)]
     pub index: i32,
-    #[bp(variant = "ProtocolVariantOption<&'a [u8; 256], ConstLengthRawArray<u8, 256>>")]
-    pub message_signature_bytes: Option<&'a [u8; 256]>,
-    pub message: &'a str,
+    #[bp(variant = "ProtocolVariantOption<Cow<'a, [u8; 256]>, ConstLengthRawArray<u8, 256>>")]
+    pub message_signature_bytes: Option<Cow<'a, [u8; 256]>>,
+    pub message: Cow<'a, str>,
     pub timestamp: i64,
     pub salt: i64,
     #[bp(variant = "LengthProvidedArray<i32, VarInt, PlayerChatPreviousMessage<'a>, PlayerChatPreviousMessage<'a>>")]
@@ -3024,7 +3264,8 @@ pub struct PlayerChatMessagePS2C<'a> {
     pub network_target_name: Option<Component<'a>>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x32, state = Play, bound = Client)]
 pub struct EndCombatPS2C {
     #[bp(variant = VarInt)]
@@ -3032,11 +3273,13 @@ pub struct EndCombatPS2C {
     pub entity_id: i32,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x33, state = Play, bound = Client)]
 pub struct EnterCombatPS2C;
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x34, state = Play, bound = Client)]
 pub struct CombatDeathPS2C<'a> {
     #[bp(variant = VarInt)]
@@ -3045,31 +3288,35 @@ pub struct CombatDeathPS2C<'a> {
     pub message: Component<'a>,
 }
 
-#[derive(ProtocolAll, ProtocolPacket, Clone, Debug)]
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(id = 0x35, state = Play, bound = Client)]
 pub struct PlayerInfoRemovePS2C<'a> {
     #[bp(variant = "LengthProvidedRawArray<i32, VarInt, Uuid, Uuid>")]
     pub players: Cow<'a, [Uuid]>,
 }
 
-#[derive(ProtocolAll, Clone, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct PlayerInfoUpdateAddAction<'a> {
-    pub name: &'a str,
+    pub name: Cow<'a, str>,
     #[bp(variant = "LengthProvidedArray<i32, VarInt, Property<'a>, Property<'a>>")]
     pub properties: Cow<'a, [Property<'a>]>,
 }
 
-#[derive(ProtocolAll, Clone, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 pub struct PlayerInfoUpdateInitializeChat<'a> {
     pub chat_session_id: Uuid,
     pub public_key_expire_time: i64,
     #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
-    pub encoded_public_key: &'a [u8],
+    pub encoded_public_key: Cow<'a, [u8]>,
     #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
-    pub public_key_signature: &'a [u8],
+    pub public_key_signature: Cow<'a, [u8]>,
 }
 
-#[derive(ProtocolAll, Clone, Copy, Debug)]
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
 #[bp(ty = i32, variant = VarInt)]
 pub enum PlayerInfoUpdateGameMode {
     Survival,
@@ -3078,7 +3325,7 @@ pub enum PlayerInfoUpdateGameMode {
     Spectator,
 }
 
-#[derive(Clone, Debug)]
+#[derive(IntoOwned, Clone, PartialEq, Debug)]
 pub struct PlayerInfoUpdateAction<'a> {
     pub add: Option<PlayerInfoUpdateAddAction<'a>>,
     pub initialize_chat: Option<PlayerInfoUpdateInitializeChat<'a>>,
@@ -3133,7 +3380,7 @@ impl<'a> PlayerInfoUpdateAction<'a> {
     }
 }
 
-#[derive(ProtocolPacket, Clone, Debug)]
+#[derive(ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
 #[bp(id = 0x36, state = Play, bound = Client)]
 pub struct PlayerInfoUpdatePS2C<'a> {
     pub actions: Cow<'a, [(Uuid, PlayerInfoUpdateAction<'a>)]>,
@@ -3177,55 +3424,2576 @@ impl<'a> ProtocolReadable<'a> for PlayerInfoUpdatePS2C<'a> {
 }
 
 #[cfg(test)]
-mod tests {
-    use super::*;
+impl<'a> ProtocolArbitrary<'a> for PlayerInfoUpdatePS2C<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        let actions = (0..bird_protocol::arbitrary::arbitrary_length(u)?)
+            .map(|_| Ok((Uuid::arbitrary(u)?, PlayerInfoUpdateAction {
+                add: Option::arbitrary(u)?,
+                initialize_chat: Option::arbitrary(u)?,
+                update_game_mode: Option::arbitrary(u)?,
+                update_listed: Option::arbitrary(u)?,
+                update_latency: Option::arbitrary(u)?,
+                update_display_name: Option::arbitrary(u)?,
+            })))
+            .collect::<ArbitraryResult<_>>()?;
+        Ok(Self { actions })
+    }
+}
 
-    #[test]
-    fn gap_compact_longs_reader_test() {
-        let mut compact_longs_reader = unsafe {
-            GapCompactLongsReader::<_, 19>::new(
-                vec![0b111111111_001111111_000011111_000000111_000000001_0; 3].into_iter(),
-                9,
-            ).unwrap()
-        };
-        for i in 0..3 {
-            assert_eq!(compact_longs_reader.next(), Some(0b1));
-            assert_eq!(compact_longs_reader.next(), Some(0b111));
-            assert_eq!(compact_longs_reader.next(), Some(0b11111));
-            assert_eq!(compact_longs_reader.next(), Some(0b1111111));
-            assert_eq!(compact_longs_reader.next(), Some(0b111111111));
-            if i == 2 {
-                assert_eq!(compact_longs_reader.next(), None);
-            } else {
-                assert_eq!(compact_longs_reader.next(), Some(0b0));
-                assert_eq!(compact_longs_reader.next(), Some(0b0));
-            }
-        }
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum LookAtAnchor {
+    Feet,
+    Eyes,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct LookAtEntity {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    pub entity_anchor: LookAtAnchor,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x37, state = Play, bound = Client)]
+pub struct LookAtPS2C {
+    pub anchor: LookAtAnchor,
+    pub target: Vector3D<f64>,
+    pub entity: Option<LookAtEntity>,
+}
+
+#[bitfield(u8)]
+#[derive(ProtocolAll, IntoOwned, PartialEq)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct SynchronizePlayerPositionFlags {
+    pub relative_x: bool,
+    pub relative_y: bool,
+    pub relative_z: bool,
+    pub relative_yaw: bool,
+    pub relative_pitch: bool,
+    #[bits(3)]
+    _gap: u8,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x38, state = Play, bound = Client)]
+pub struct SynchronizePlayerPositionPS2C {
+    pub position: Vector3D<f64>,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub flags: SynchronizePlayerPositionFlags,
+    #[bp(variant = VarInt)]
+    pub teleport_id: i32,
+    #[bp(until = 761)]
+    pub dismount_vehicle: bool,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct RecipeBookSettings {
+    pub crafting_open: bool,
+    pub crafting_filter_active: bool,
+    pub smelting_open: bool,
+    pub smelting_filter_active: bool,
+    pub blast_furnace_open: bool,
+    pub blast_furnace_filter_active: bool,
+    pub smoker_open: bool,
+    pub smoker_filter_active: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x39, state = Play, bound = Client, ty = i32, variant = VarInt)]
+pub enum UpdateRecipeBookPS2C<'a> {
+    Init {
+        settings: RecipeBookSettings,
+        #[bp(variant = "LengthProvidedArray<i32, VarInt, Identifier<'a>, Identifier<'a>>")]
+        recipe_ids: Cow<'a, [Identifier<'a>]>,
+        #[bp(variant = "LengthProvidedArray<i32, VarInt, Identifier<'a>, Identifier<'a>>")]
+        highlighted_recipe_ids: Cow<'a, [Identifier<'a>]>,
+    },
+    Add {
+        settings: RecipeBookSettings,
+        #[bp(variant = "LengthProvidedArray<i32, VarInt, Identifier<'a>, Identifier<'a>>")]
+        recipe_ids: Cow<'a, [Identifier<'a>]>,
+    },
+    Remove {
+        settings: RecipeBookSettings,
+        #[bp(variant = "LengthProvidedArray<i32, VarInt, Identifier<'a>, Identifier<'a>>")]
+        recipe_ids: Cow<'a, [Identifier<'a>]>,
+    },
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x3A, state = Play, bound = Client)]
+pub struct RemoveEntitiesPS2C<'a> {
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, i32, VarInt>")]
+    pub entity_ids: Cow<'a, [i32]>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x3B, state = Play, bound = Client)]
+pub struct RemoveEntityEffectPS2C {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    #[bp(variant = VarInt)]
+    pub effect_id: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x3C, state = Play, bound = Client)]
+pub struct ResourcePackPS2C<'a> {
+    pub url: Cow<'a, str>,
+    pub hash: Cow<'a, str>,
+    pub forced: bool,
+    pub prompt_message: Option<Component<'a>>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x3D, state = Play, bound = Client)]
+pub struct RespawnPS2C<'a> {
+    pub dimension_type: Identifier<'a>,
+    pub dimension_name: Identifier<'a>,
+    pub hashed_seed: i64,
+    pub game_mode: LoginGameMode,
+    pub previous_game_mode: PreviousLoginGameMode,
+    pub is_debug: bool,
+    pub is_flat: bool,
+    pub copy_metadata: bool,
+    pub death_location: Option<LoginDeathLocation<'a>>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x3E, state = Play, bound = Client)]
+pub struct SetHeadRotationPS2C {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    #[bp(variant = Angle)]
+    pub head_yaw: f32,
+}
+
+/// Block state with its position inside of the section. It is sent as one VarLong
+#[derive(IntoOwned, Clone, Copy, PartialEq, Debug)]
+pub struct UpdateSectionBlocksEntry {
+    pub block_state: i32,
+    pub position: Vector3D<u8>,
+}
+
+impl ProtocolSize for UpdateSectionBlocksEntry {
+    const SIZE: Range<u32> = VarLong::SIZE;
+}
+
+impl ProtocolWritable for UpdateSectionBlocksEntry {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        VarLong::write_variant(&(
+            (self.block_state as i64) << 12 |
+                ((self.position.x as i64 & 0xF) << 8) |
+                ((self.position.z as i64 & 0xF) << 4) |
+                (self.position.y as i64 & 0xF)
+        ), writer)
     }
+}
 
-    #[test]
-    fn gap_compact_longs_writer_test() {
-        let mut vec = Vec::new();
-        let mut compact_longs_writer = unsafe { GapCompactLongsWriter::new(&mut vec, 9) };
-        unsafe {
-            for i in 0..3 {
-                compact_longs_writer.write(0b1).unwrap();
-                compact_longs_writer.write(0b111).unwrap();
-                compact_longs_writer.write(0b11111).unwrap();
-                compact_longs_writer.write(0b1111111).unwrap();
-                compact_longs_writer.write(0b111111111).unwrap();
-                if i != 2 {
-                    compact_longs_writer.write(0b0).unwrap();
-                    compact_longs_writer.write(0b0).unwrap();
-                }
-            }
-        }
-        compact_longs_writer.finish().unwrap();
-        let mut res_vec = Vec::new();
-        for _ in 0..3 {
-            0b111111111_001111111_000011111_000000111_000000001_0_u64.write(&mut res_vec).unwrap();
-        }
-        assert_eq!(vec, res_vec);
+impl<'a> ProtocolReadable<'a> for UpdateSectionBlocksEntry {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let value: i64 = VarLong::read_variant(cursor)?;
+        Ok(Self {
+            block_state: (value >> 12) as i32,
+            position: Vector3D::new(
+                ((value >> 8) & 0xF) as u8,
+                (value & 0xF) as u8,
+                ((value >> 4) & 0xF) as u8,
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+impl<'a> ProtocolArbitrary<'a> for UpdateSectionBlocksEntry {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        Ok(Self {
+            block_state: u.arbitrary()?,
+            position: Vector3D::new(u.int_in_range(0..=15)?, u.int_in_range(0..=15)?, u.int_in_range(0..=15)?),
+        })
+    }
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x3F, state = Play, bound = Client)]
+pub struct UpdateSectionBlocksPS2C<'a> {
+    #[bp(variant = SectionPosition)]
+    pub section_position: Vector3D<i32>,
+    pub suppress_light_updates: bool,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, UpdateSectionBlocksEntry, UpdateSectionBlocksEntry>")]
+    pub blocks: Cow<'a, [UpdateSectionBlocksEntry]>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x40, state = Play, bound = Client)]
+pub struct SelectAdvancementsTabPS2C<'a> {
+    pub identifier: Option<Identifier<'a>>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x41, state = Play, bound = Client)]
+pub struct ServerDataPS2C<'a> {
+    pub motd: Option<Component<'a>>,
+    /// Base64 encoded PNG image
+    pub icon: Option<Cow<'a, str>>,
+    pub enforces_secure_chat: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x42, state = Play, bound = Client)]
+pub struct SetActionBarTextPS2C<'a> {
+    pub text: Component<'a>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x43, state = Play, bound = Client)]
+pub struct SetBorderCenterPS2C {
+    pub x: f64,
+    pub z: f64,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x44, state = Play, bound = Client)]
+pub struct SetBorderLerpSizePS2C {
+    pub old_diameter: f64,
+    pub new_diameter: f64,
+    #[bp(variant = VarLong)]
+    pub speed: i64,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x45, state = Play, bound = Client)]
+pub struct SetBorderSizePS2C {
+    pub diameter: f64,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x46, state = Play, bound = Client)]
+pub struct SetBorderWarningDelayPS2C {
+    #[bp(variant = VarInt)]
+    pub warning_time: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x47, state = Play, bound = Client)]
+pub struct SetBorderWarningDistancePS2C {
+    #[bp(variant = VarInt)]
+    pub warning_blocks: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x48, state = Play, bound = Client)]
+pub struct SetCameraPS2C {
+    #[bp(variant = VarInt)]
+    pub camera_id: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x49, state = Play, bound = Client)]
+pub struct SetHeldItemPS2C {
+    pub slot: i8,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x4A, state = Play, bound = Client)]
+pub struct SetCenterChunkPS2C {
+    #[bp(variant = VarInt)]
+    pub chunk_x: i32,
+    #[bp(variant = VarInt)]
+    pub chunk_z: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x4B, state = Play, bound = Client)]
+pub struct SetRenderDistancePS2C {
+    #[bp(variant = VarInt)]
+    pub view_distance: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x4C, state = Play, bound = Client)]
+pub struct SetDefaultSpawnPositionPS2C {
+    #[bp(variant = BlockPosition)]
+    pub location: Vector3D<i32>,
+    pub angle: f32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x4D, state = Play, bound = Client)]
+pub struct DisplayObjectivePS2C<'a> {
+    /// 0 is list, 1 is sidebar, 2 is below name and 3-18 are team specific sidebars
+    pub position: i8,
+    pub score_name: Cow<'a, str>,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum EntityMetadataDirection {
+    Down,
+    Up,
+    North,
+    South,
+    West,
+    East,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct EntityMetadataVillagerData {
+    #[bp(variant = VarInt)]
+    pub villager_type: i32,
+    #[bp(variant = VarInt)]
+    pub villager_profession: i32,
+    #[bp(variant = VarInt)]
+    pub level: i32,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum EntityMetadataPose {
+    Standing,
+    FallFlying,
+    Sleeping,
+    Swimming,
+    SpinAttack,
+    Sneaking,
+    LongJumping,
+    Dying,
+    Croaking,
+    UsingTongue,
+    Roaring,
+    Sniffing,
+    Emerging,
+    Digging,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum EntityMetadataValue<'a> {
+    Byte(i8),
+    VarInt(
+        #[bp(variant = VarInt)]
+        i32
+    ),
+    VarLong(
+        #[bp(variant = VarLong)]
+        i64
+    ),
+    Float(f32),
+    String(Cow<'a, str>),
+    Chat(Component<'a>),
+    OptionalChat(Option<Component<'a>>),
+    Slot(Option<Slot<'a>>),
+    Boolean(bool),
+    Rotation(Vector3D<f32>),
+    Position(
+        #[bp(variant = BlockPosition)]
+        Vector3D<i32>
+    ),
+    OptionalPosition(
+        #[bp(variant = "ProtocolVariantOption<Vector3D<i32>, BlockPosition>")]
+        Option<Vector3D<i32>>
+    ),
+    Direction(EntityMetadataDirection),
+    OptionalUuid(Option<Uuid>),
+    /// 0 means absent (air)
+    OptionalBlockState(
+        #[bp(variant = VarInt)]
+        i32
+    ),
+    Nbt(
+        #[bp(variant = NbtBytes)]
+        Cow<'a, [u8]>
+    ),
+    Particle(Particle<'a>),
+    VillagerData(EntityMetadataVillagerData),
+    /// 0 means absent, otherwise the value is increased by one
+    OptionalVarInt(
+        #[bp(variant = VarInt)]
+        i32
+    ),
+    Pose(EntityMetadataPose),
+    CatVariant(
+        #[bp(variant = VarInt)]
+        i32
+    ),
+    FrogVariant(
+        #[bp(variant = VarInt)]
+        i32
+    ),
+    OptionalGlobalPosition(Option<LoginDeathLocation<'a>>),
+    PaintingVariant(
+        #[bp(variant = VarInt)]
+        i32
+    ),
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct EntityMetadataEntry<'a> {
+    pub index: u8,
+    pub value: EntityMetadataValue<'a>,
+}
+
+/// Index which ends the entity metadata
+pub const ENTITY_METADATA_END: u8 = 0xFF;
+
+/// Indexes are below the end index, so there are no more entries in the vanilla metadata
+pub const MAX_ENTITY_METADATA_ENTRIES: usize = ENTITY_METADATA_END as usize;
+
+#[derive(ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[bp(id = 0x4E, state = Play, bound = Client)]
+pub struct SetEntityMetadataPS2C<'a> {
+    pub entity_id: i32,
+    pub metadata: Cow<'a, [EntityMetadataEntry<'a>]>,
+}
+
+impl<'a> ProtocolSize for SetEntityMetadataPS2C<'a> {
+    const SIZE: Range<u32> = (VarInt::SIZE.start + u8::SIZE.start..u32::MAX);
+}
+
+impl<'a> ProtocolWritable for SetEntityMetadataPS2C<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        VarInt::write_variant(&self.entity_id, writer)?;
+        for entry in self.metadata.as_ref() {
+            entry.write(writer)?;
+        }
+        ENTITY_METADATA_END.write(writer)
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for SetEntityMetadataPS2C<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let entity_id = VarInt::read_variant(cursor)?;
+        let mut metadata = Vec::new();
+        loop {
+            let index = u8::read(cursor)?;
+            if index == ENTITY_METADATA_END {
+                break;
+            }
+            if metadata.len() == MAX_ENTITY_METADATA_ENTRIES {
+                return Err(ProtocolError::ArrayTooLong {
+                    length: MAX_ENTITY_METADATA_ENTRIES + 1,
+                    limit: MAX_ENTITY_METADATA_ENTRIES,
+                });
+            }
+            cursor.reserve_allocation(1, std::mem::size_of::<EntityMetadataEntry>())?;
+            metadata.push(EntityMetadataEntry { index, value: EntityMetadataValue::read(cursor)? });
+        }
+        Ok(Self { entity_id, metadata: Cow::Owned(metadata) })
+    }
+}
+
+#[cfg(test)]
+impl<'a> ProtocolArbitrary<'a> for SetEntityMetadataPS2C<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        let entity_id = u.arbitrary()?;
+        let metadata = (0..bird_protocol::arbitrary::arbitrary_length(u)?)
+            .map(|_| match EntityMetadataEntry::arbitrary(u)? {
+                entry if entry.index != ENTITY_METADATA_END => Ok(entry),
+                _ => Err(ArbitraryError::IncorrectFormat),
+            })
+            .collect::<ArbitraryResult<_>>()?;
+        Ok(Self { entity_id, metadata })
+    }
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x4F, state = Play, bound = Client)]
+pub struct LinkEntitiesPS2C {
+    pub attached_entity_id: i32,
+    pub holding_entity_id: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x50, state = Play, bound = Client)]
+pub struct SetEntityVelocityPS2C {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    pub velocity: Vector3D<i16>,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = u8)]
+pub enum EquipmentSlot {
+    MainHand,
+    OffHand,
+    Boots,
+    Leggings,
+    Chestplate,
+    Helmet,
+}
+
+#[derive(IntoOwned, Clone, PartialEq, Debug)]
+pub struct SetEquipmentEntry<'a> {
+    pub slot: EquipmentSlot,
+    pub item: Option<Slot<'a>>,
+}
+
+/// Bit of the slot byte which tells that another entry follows
+const SET_EQUIPMENT_HAS_NEXT: u8 = 0x80;
+
+#[derive(ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[bp(id = 0x51, state = Play, bound = Client)]
+pub struct SetEquipmentPS2C<'a> {
+    pub entity_id: i32,
+    pub equipment: Cow<'a, [SetEquipmentEntry<'a>]>,
+}
+
+impl<'a> ProtocolSize for SetEquipmentPS2C<'a> {
+    const SIZE: Range<u32> = (VarInt::SIZE.start..u32::MAX);
+}
+
+impl<'a> ProtocolWritable for SetEquipmentPS2C<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        // the last entry is the one without the next bit, so there is always at least one
+        if self.equipment.is_empty() {
+            return Err(anyhow::Error::msg("Set equipment must have at least one entry"));
+        }
+        VarInt::write_variant(&self.entity_id, writer)?;
+        let mut entries = self.equipment.iter().peekable();
+        while let Some(entry) = entries.next() {
+            let mut slot = entry.slot as u8;
+            if entries.peek().is_some() {
+                slot |= SET_EQUIPMENT_HAS_NEXT;
+            }
+            slot.write(writer)?;
+            entry.item.write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for SetEquipmentPS2C<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let entity_id = VarInt::read_variant(cursor)?;
+        let mut equipment = Vec::new();
+        loop {
+            let slot = u8::read(cursor)?;
+            equipment.push(SetEquipmentEntry {
+                slot: EquipmentSlot::read(&mut [slot & !SET_EQUIPMENT_HAS_NEXT].as_slice())?,
+                item: Option::read(cursor)?,
+            });
+            if slot & SET_EQUIPMENT_HAS_NEXT == 0 {
+                break;
+            }
+        }
+        Ok(Self { entity_id, equipment: Cow::Owned(equipment) })
+    }
+}
+
+#[cfg(test)]
+impl<'a> ProtocolArbitrary<'a> for SetEquipmentPS2C<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        let entity_id = u.arbitrary()?;
+        // at least one entry is always written
+        let equipment = (0..u.int_in_range(1..=6)?)
+            .map(|_| Ok(SetEquipmentEntry { slot: EquipmentSlot::arbitrary(u)?, item: Option::arbitrary(u)? }))
+            .collect::<ArbitraryResult<_>>()?;
+        Ok(Self { entity_id, equipment })
+    }
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x52, state = Play, bound = Client)]
+pub struct SetExperiencePS2C {
+    pub experience_bar: f32,
+    #[bp(variant = VarInt)]
+    pub level: i32,
+    #[bp(variant = VarInt)]
+    pub total_experience: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x53, state = Play, bound = Client)]
+pub struct SetHealthPS2C {
+    pub health: f32,
+    #[bp(variant = VarInt)]
+    pub food: i32,
+    pub food_saturation: f32,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum ObjectiveType {
+    Integer,
+    Hearts,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i8)]
+pub enum UpdateObjectivesAction<'a> {
+    Create {
+        value: Component<'a>,
+        ty: ObjectiveType,
+    },
+    Remove,
+    Update {
+        value: Component<'a>,
+        ty: ObjectiveType,
+    },
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x54, state = Play, bound = Client)]
+pub struct UpdateObjectivesPS2C<'a> {
+    pub objective_name: Cow<'a, str>,
+    pub action: UpdateObjectivesAction<'a>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x55, state = Play, bound = Client)]
+pub struct SetPassengersPS2C<'a> {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, i32, VarInt>")]
+    pub passengers: Cow<'a, [i32]>,
+}
+
+#[bitfield(u8)]
+#[derive(ProtocolAll, IntoOwned, PartialEq)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct TeamFlags {
+    pub allow_friendly_fire: bool,
+    pub can_see_invisible_teammates: bool,
+    #[bits(6)]
+    _gap: u8,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum TeamColor {
+    Black,
+    DarkBlue,
+    DarkGreen,
+    DarkAqua,
+    DarkRed,
+    DarkPurple,
+    Gold,
+    Gray,
+    DarkGray,
+    Blue,
+    Green,
+    Aqua,
+    Red,
+    LightPurple,
+    Yellow,
+    White,
+    Obfuscated,
+    Bold,
+    Strikethrough,
+    Underlined,
+    Italic,
+    Reset,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct TeamInfo<'a> {
+    pub display_name: Component<'a>,
+    pub flags: TeamFlags,
+    pub name_tag_visibility: Cow<'a, str>,
+    pub collision_rule: Cow<'a, str>,
+    pub color: TeamColor,
+    pub prefix: Component<'a>,
+    pub suffix: Component<'a>,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i8)]
+pub enum UpdateTeamsAction<'a> {
+    Create {
+        info: TeamInfo<'a>,
+        #[bp(variant = "LengthProvidedArray<i32, VarInt, Cow<'a, str>, Cow<'a, str>>")]
+        entities: Cow<'a, [Cow<'a, str>]>,
+    },
+    Remove,
+    UpdateInfo {
+        info: TeamInfo<'a>,
+    },
+    AddEntities {
+        #[bp(variant = "LengthProvidedArray<i32, VarInt, Cow<'a, str>, Cow<'a, str>>")]
+        entities: Cow<'a, [Cow<'a, str>]>,
+    },
+    RemoveEntities {
+        #[bp(variant = "LengthProvidedArray<i32, VarInt, Cow<'a, str>, Cow<'a, str>>")]
+        entities: Cow<'a, [Cow<'a, str>]>,
+    },
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x56, state = Play, bound = Client)]
+pub struct UpdateTeamsPS2C<'a> {
+    pub team_name: Cow<'a, str>,
+    pub action: UpdateTeamsAction<'a>,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum UpdateScoreAction<'a> {
+    Update {
+        objective_name: Cow<'a, str>,
+        #[bp(variant = VarInt)]
+        value: i32,
+    },
+    Remove {
+        objective_name: Cow<'a, str>,
+    },
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x57, state = Play, bound = Client)]
+pub struct UpdateScorePS2C<'a> {
+    pub entity_name: Cow<'a, str>,
+    pub action: UpdateScoreAction<'a>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x58, state = Play, bound = Client)]
+pub struct SetSimulationDistancePS2C {
+    #[bp(variant = VarInt)]
+    pub simulation_distance: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x59, state = Play, bound = Client)]
+pub struct SetSubtitleTextPS2C<'a> {
+    pub subtitle_text: Component<'a>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x5A, state = Play, bound = Client)]
+pub struct UpdateTimePS2C {
+    pub world_age: i64,
+    /// Negative value stops the sun
+    pub time_of_day: i64,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x5B, state = Play, bound = Client)]
+pub struct SetTitleTextPS2C<'a> {
+    pub title_text: Component<'a>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x5C, state = Play, bound = Client)]
+pub struct SetTitleAnimationTimesPS2C {
+    pub fade_in: i32,
+    pub stay: i32,
+    pub fade_out: i32,
+}
+
+/// Sound which is either taken from the registry or sent inline
+#[derive(IntoOwned, Clone, PartialEq, Debug)]
+pub enum SoundEvent<'a> {
+    Registry(i32),
+    Direct {
+        sound_name: Identifier<'a>,
+        fixed_range: Option<f32>,
+    },
+}
+
+impl<'a> ProtocolSize for SoundEvent<'a> {
+    const SIZE: Range<u32> = (VarInt::SIZE.start..u32::MAX);
+}
+
+impl<'a> ProtocolWritable for SoundEvent<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        match self {
+            // id is sent increased by one, because zero means that the sound is sent inline
+            Self::Registry(id) => VarInt::write_variant(
+                &id.checked_add(1).ok_or_else(|| anyhow::Error::msg("Sound id is too big"))?,
+                writer,
+            ),
+            Self::Direct { sound_name, fixed_range } => {
+                VarInt::write_variant(&0, writer)?;
+                sound_name.write(writer)?;
+                fixed_range.write(writer)
+            }
+        }
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for SoundEvent<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let id: i32 = VarInt::read_variant(cursor)?;
+        Ok(match id {
+            0 => Self::Direct {
+                sound_name: Identifier::read(cursor)?,
+                fixed_range: Option::read(cursor)?,
+            },
+            id => Self::Registry(id.checked_sub(1).ok_or_else(|| anyhow::Error::msg("Sound id is too small"))?),
+        })
+    }
+}
+
+#[cfg(test)]
+impl<'a> ProtocolArbitrary<'a> for SoundEvent<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        Ok(match u.arbitrary()? {
+            true => Self::Registry(u.int_in_range(0..=i32::MAX - 1)?),
+            false => Self::Direct { sound_name: Identifier::arbitrary(u)?, fixed_range: Option::arbitrary(u)? },
+        })
+    }
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x5D, state = Play, bound = Client)]
+pub struct EntitySoundEffectPS2C<'a> {
+    pub sound: SoundEvent<'a>,
+    pub sound_category: SoundCategory,
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    pub volume: f32,
+    pub pitch: f32,
+    pub seed: i64,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x5E, state = Play, bound = Client)]
+pub struct SoundEffectPS2C<'a> {
+    pub sound: SoundEvent<'a>,
+    pub sound_category: SoundCategory,
+    #[bp(variant = "FixedPointNumber<i32, 3>")]
+    pub effect_position_x: f32,
+    #[bp(variant = "FixedPointNumber<i32, 3>")]
+    pub effect_position_y: f32,
+    #[bp(variant = "FixedPointNumber<i32, 3>")]
+    pub effect_position_z: f32,
+    pub volume: f32,
+    pub pitch: f32,
+    pub seed: i64,
+}
+
+#[derive(ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[bp(id = 0x5F, state = Play, bound = Client)]
+pub struct StopSoundPS2C<'a> {
+    pub source: Option<SoundCategory>,
+    pub sound: Option<Identifier<'a>>,
+}
+
+impl<'a> ProtocolSize for StopSoundPS2C<'a> {
+    const SIZE: Range<u32> = (u8::SIZE.start..u32::MAX);
+}
+
+impl<'a> ProtocolWritable for StopSoundPS2C<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        (self.source.is_some() as u8 | (self.sound.is_some() as u8) << 1).write(writer)?;
+        if let Some(ref source) = self.source {
+            source.write(writer)?;
+        }
+        if let Some(ref sound) = self.sound {
+            sound.write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for StopSoundPS2C<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let flags = u8::read(cursor)?;
+        Ok(Self {
+            source: if flags & 0x1 != 0 { Some(SoundCategory::read(cursor)?) } else { None },
+            sound: if flags & 0x2 != 0 { Some(Identifier::read(cursor)?) } else { None },
+        })
+    }
+}
+
+#[cfg(test)]
+impl<'a> ProtocolArbitrary<'a> for StopSoundPS2C<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        Ok(Self { source: Option::arbitrary(u)?, sound: Option::arbitrary(u)? })
+    }
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x60, state = Play, bound = Client)]
+pub struct SystemChatMessagePS2C<'a> {
+    pub content: Component<'a>,
+    /// Whether the message is shown in the action bar
+    pub overlay: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x61, state = Play, bound = Client)]
+pub struct SetTabListHeaderAndFooterPS2C<'a> {
+    pub header: Component<'a>,
+    pub footer: Component<'a>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x62, state = Play, bound = Client)]
+pub struct TagQueryResponsePS2C<'a> {
+    #[bp(variant = VarInt)]
+    pub transaction_id: i32,
+    #[bp(variant = NbtBytes)]
+    pub nbt: Cow<'a, [u8]>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x63, state = Play, bound = Client)]
+pub struct PickupItemPS2C {
+    #[bp(variant = VarInt)]
+    pub collected_entity_id: i32,
+    #[bp(variant = VarInt)]
+    pub collector_entity_id: i32,
+    #[bp(variant = VarInt)]
+    pub pickup_item_count: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x64, state = Play, bound = Client)]
+pub struct TeleportEntityPS2C {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    pub position: Vector3D<f64>,
+    #[bp(variant = Angle)]
+    pub yaw: f32,
+    #[bp(variant = Angle)]
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum AdvancementFrameType {
+    Task,
+    Challenge,
+    Goal,
+}
+
+#[derive(IntoOwned, Clone, PartialEq, Debug)]
+pub struct AdvancementDisplay<'a> {
+    pub title: Component<'a>,
+    pub description: Component<'a>,
+    pub icon: Option<Slot<'a>>,
+    pub frame_type: AdvancementFrameType,
+    pub background_texture: Option<Identifier<'a>>,
+    pub show_toast: bool,
+    pub hidden: bool,
+    pub x: f32,
+    pub y: f32,
+}
+
+impl<'a> ProtocolSize for AdvancementDisplay<'a> {
+    const SIZE: Range<u32> = (
+        add_protocol_sizes_ty!(Component, Component, Option<Slot>, AdvancementFrameType, i32, f32, f32).start..u32::MAX
+    );
+}
+
+impl<'a> ProtocolWritable for AdvancementDisplay<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        self.title.write(writer)?;
+        self.description.write(writer)?;
+        self.icon.write(writer)?;
+        self.frame_type.write(writer)?;
+        (self.background_texture.is_some() as i32 |
+            (self.show_toast as i32) << 1 |
+            (self.hidden as i32) << 2
+        ).write(writer)?;
+        if let Some(ref background_texture) = self.background_texture {
+            background_texture.write(writer)?;
+        }
+        self.x.write(writer)?;
+        self.y.write(writer)
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for AdvancementDisplay<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let title = Component::read(cursor)?;
+        let description = Component::read(cursor)?;
+        let icon = Option::read(cursor)?;
+        let frame_type = AdvancementFrameType::read(cursor)?;
+        let flags = i32::read(cursor)?;
+        Ok(Self {
+            title,
+            description,
+            icon,
+            frame_type,
+            background_texture: if flags & 0x1 != 0 { Some(Identifier::read(cursor)?) } else { None },
+            show_toast: flags & 0x2 != 0,
+            hidden: flags & 0x4 != 0,
+            x: f32::read(cursor)?,
+            y: f32::read(cursor)?,
+        })
+    }
+}
+
+#[cfg(test)]
+impl<'a> ProtocolArbitrary<'a> for AdvancementDisplay<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        Ok(Self {
+            title: Component::arbitrary(u)?,
+            description: Component::arbitrary(u)?,
+            icon: Option::arbitrary(u)?,
+            frame_type: AdvancementFrameType::arbitrary(u)?,
+            background_texture: Option::arbitrary(u)?,
+            show_toast: u.arbitrary()?,
+            hidden: u.arbitrary()?,
+            x: f32::arbitrary(u)?,
+            y: f32::arbitrary(u)?,
+        })
+    }
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct Advancement<'a> {
+    pub parent_id: Option<Identifier<'a>>,
+    pub display: Option<AdvancementDisplay<'a>>,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, Identifier<'a>, Identifier<'a>>")]
+    pub criteria: Cow<'a, [Identifier<'a>]>,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, Cow<'a, [Cow<'a, str>]>, LengthProvidedArray<i32, VarInt, Cow<'a, str>, Cow<'a, str>>>")]
+    pub requirements: Cow<'a, [Cow<'a, [Cow<'a, str>]>]>,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct AdvancementMapping<'a> {
+    pub key: Identifier<'a>,
+    pub value: Advancement<'a>,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct AdvancementCriterionProgress<'a> {
+    pub criterion: Identifier<'a>,
+    /// Milliseconds since the epoch. None if the criterion is not achieved
+    pub date_of_achieving: Option<i64>,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct AdvancementProgressMapping<'a> {
+    pub key: Identifier<'a>,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, AdvancementCriterionProgress<'a>, AdvancementCriterionProgress<'a>>")]
+    pub criteria: Cow<'a, [AdvancementCriterionProgress<'a>]>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x65, state = Play, bound = Client)]
+pub struct UpdateAdvancementsPS2C<'a> {
+    pub reset: bool,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, AdvancementMapping<'a>, AdvancementMapping<'a>>")]
+    pub advancements: Cow<'a, [AdvancementMapping<'a>]>,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, Identifier<'a>, Identifier<'a>>")]
+    pub removed: Cow<'a, [Identifier<'a>]>,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, AdvancementProgressMapping<'a>, AdvancementProgressMapping<'a>>")]
+    pub progress: Cow<'a, [AdvancementProgressMapping<'a>]>,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i8)]
+pub enum AttributeModifierOperation {
+    Add,
+    MultiplyBase,
+    MultiplyTotal,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct AttributeModifier {
+    pub uuid: Uuid,
+    pub amount: f64,
+    pub operation: AttributeModifierOperation,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct AttributeProperty<'a> {
+    pub key: Identifier<'a>,
+    pub value: f64,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, AttributeModifier, AttributeModifier>")]
+    pub modifiers: Cow<'a, [AttributeModifier]>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x66, state = Play, bound = Client)]
+pub struct UpdateAttributesPS2C<'a> {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, AttributeProperty<'a>, AttributeProperty<'a>>")]
+    pub properties: Cow<'a, [AttributeProperty<'a>]>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x67, state = Play, bound = Client)]
+pub struct FeatureFlagsPS2C<'a> {
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, Identifier<'a>, Identifier<'a>>")]
+    pub feature_flags: Cow<'a, [Identifier<'a>]>,
+}
+
+#[bitfield(u8)]
+#[derive(ProtocolAll, IntoOwned, PartialEq)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct EntityEffectFlags {
+    pub ambient: bool,
+    pub show_particles: bool,
+    pub show_icon: bool,
+    #[bits(5)]
+    _gap: u8,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x68, state = Play, bound = Client)]
+pub struct EntityEffectPS2C<'a> {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    #[bp(variant = VarInt)]
+    pub effect_id: i32,
+    pub amplifier: i8,
+    /// -1 means infinite duration
+    #[bp(variant = VarInt)]
+    pub duration: i32,
+    pub flags: EntityEffectFlags,
+    #[bp(variant = "ProtocolVariantOption<Cow<'a, [u8]>, NbtBytes>")]
+    pub factor_codec: Option<Cow<'a, [u8]>>,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct RecipeIngredient<'a> {
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, Option<Slot<'a>>, Option<Slot<'a>>>")]
+    pub items: Cow<'a, [Option<Slot<'a>>]>,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum CraftingRecipeCategory {
+    Building,
+    Redstone,
+    Equipment,
+    Misc,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum CookingRecipeCategory {
+    Food,
+    Blocks,
+    Misc,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct CookingRecipe<'a> {
+    pub group: Cow<'a, str>,
+    pub category: CookingRecipeCategory,
+    pub ingredient: RecipeIngredient<'a>,
+    pub result: Option<Slot<'a>>,
+    pub experience: f32,
+    #[bp(variant = VarInt)]
+    pub cooking_time: i32,
+}
+
+#[derive(IntoOwned, Clone, PartialEq, Debug)]
+pub enum RecipeData<'a> {
+    CraftingShapeless {
+        group: Cow<'a, str>,
+        category: CraftingRecipeCategory,
+        ingredients: Cow<'a, [RecipeIngredient<'a>]>,
+        result: Option<Slot<'a>>,
+    },
+    /// Ingredients are sent row by row without length, because it is width * height
+    CraftingShaped {
+        width: i32,
+        height: i32,
+        group: Cow<'a, str>,
+        category: CraftingRecipeCategory,
+        ingredients: Cow<'a, [RecipeIngredient<'a>]>,
+        result: Option<Slot<'a>>,
+    },
+    /// All of the crafting_special_* recipes
+    CraftingSpecial {
+        ty: Identifier<'a>,
+        category: CraftingRecipeCategory,
+    },
+    Smelting(CookingRecipe<'a>),
+    Blasting(CookingRecipe<'a>),
+    Smoking(CookingRecipe<'a>),
+    CampfireCooking(CookingRecipe<'a>),
+    Stonecutting {
+        group: Cow<'a, str>,
+        ingredient: RecipeIngredient<'a>,
+        result: Option<Slot<'a>>,
+    },
+    Smithing {
+        base: RecipeIngredient<'a>,
+        addition: RecipeIngredient<'a>,
+        result: Option<Slot<'a>>,
+    },
+}
+
+#[derive(IntoOwned, Clone, PartialEq, Debug)]
+pub struct Recipe<'a> {
+    pub recipe_id: Identifier<'a>,
+    pub data: RecipeData<'a>,
+}
+
+/// Count of ingredients of a shaped recipe, none if the size is negative or too big
+fn shaped_recipe_size(width: i32, height: i32) -> Option<usize> {
+    match width >= 0 && height >= 0 {
+        true => width.checked_mul(height).map(|size| size as usize),
+        false => None,
+    }
+}
+
+impl<'a> ProtocolSize for Recipe<'a> {
+    const SIZE: Range<u32> = (add_protocol_sizes_ty!(Identifier, Identifier).start..u32::MAX);
+}
+
+impl<'a> ProtocolWritable for Recipe<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        match self.data {
+            RecipeData::CraftingShapeless { .. } => "minecraft:crafting_shapeless".write(writer)?,
+            RecipeData::CraftingShaped { .. } => "minecraft:crafting_shaped".write(writer)?,
+            RecipeData::CraftingSpecial { ref ty, .. } => ty.write(writer)?,
+            RecipeData::Smelting(_) => "minecraft:smelting".write(writer)?,
+            RecipeData::Blasting(_) => "minecraft:blasting".write(writer)?,
+            RecipeData::Smoking(_) => "minecraft:smoking".write(writer)?,
+            RecipeData::CampfireCooking(_) => "minecraft:campfire_cooking".write(writer)?,
+            RecipeData::Stonecutting { .. } => "minecraft:stonecutting".write(writer)?,
+            RecipeData::Smithing { .. } => "minecraft:smithing".write(writer)?,
+        }
+        self.recipe_id.write(writer)?;
+        match self.data {
+            RecipeData::CraftingShapeless { ref group, category, ref ingredients, ref result } => {
+                group.write(writer)?;
+                category.write(writer)?;
+                LengthProvidedArray::<i32, VarInt, RecipeIngredient, RecipeIngredient>::write_variant(ingredients, writer)?;
+                result.write(writer)
+            }
+            RecipeData::CraftingShaped { width, height, ref group, category, ref ingredients, ref result } => {
+                if shaped_recipe_size(width, height) != Some(ingredients.len()) {
+                    return Err(anyhow::Error::msg("Shaped recipe must have width * height ingredients"));
+                }
+                VarInt::write_variant(&width, writer)?;
+                VarInt::write_variant(&height, writer)?;
+                group.write(writer)?;
+                category.write(writer)?;
+                for ingredient in ingredients.as_ref() {
+                    ingredient.write(writer)?;
+                }
+                result.write(writer)
+            }
+            RecipeData::CraftingSpecial { category, .. } => category.write(writer),
+            RecipeData::Smelting(ref recipe) |
+            RecipeData::Blasting(ref recipe) |
+            RecipeData::Smoking(ref recipe) |
+            RecipeData::CampfireCooking(ref recipe) => recipe.write(writer),
+            RecipeData::Stonecutting { ref group, ref ingredient, ref result } => {
+                group.write(writer)?;
+                ingredient.write(writer)?;
+                result.write(writer)
+            }
+            RecipeData::Smithing { ref base, ref addition, ref result } => {
+                base.write(writer)?;
+                addition.write(writer)?;
+                result.write(writer)
+            }
+        }
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for Recipe<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let ty = Identifier::read(cursor)?;
+        let recipe_id = Identifier::read(cursor)?;
+        let data = match ty.get_partial() {
+            ("minecraft", "crafting_shapeless") => RecipeData::CraftingShapeless {
+                group: Cow::read(cursor)?,
+                category: CraftingRecipeCategory::read(cursor)?,
+                ingredients: LengthProvidedArray::<i32, VarInt, RecipeIngredient, RecipeIngredient>::read_variant(cursor)?,
+                result: Option::read(cursor)?,
+            },
+            ("minecraft", "crafting_shaped") => {
+                let width: i32 = VarInt::read_variant(cursor)?;
+                let height: i32 = VarInt::read_variant(cursor)?;
+                let group = Cow::read(cursor)?;
+                let category = CraftingRecipeCategory::read(cursor)?;
+                let size = shaped_recipe_size(width, height)
+                    .ok_or_else(|| anyhow::Error::msg("Bad size of shaped recipe"))?;
+                // every ingredient takes at least one byte, so the capacity is bounded by the remaining bytes
+                if !cursor.has_bytes(size.saturating_mul(RecipeIngredient::SIZE.start as usize)) {
+                    return Err(ProtocolError::End);
+                }
+                cursor.reserve_allocation(size, std::mem::size_of::<RecipeIngredient>())?;
+                let mut ingredients = Vec::with_capacity(size);
+                for _ in 0..size {
+                    ingredients.push(RecipeIngredient::read(cursor)?);
+                }
+                RecipeData::CraftingShaped {
+                    width,
+                    height,
+                    group,
+                    category,
+                    ingredients: Cow::Owned(ingredients),
+                    result: Option::read(cursor)?,
+                }
+            }
+            ("minecraft", key) if key.starts_with("crafting_special_") => RecipeData::CraftingSpecial {
+                ty: ty.clone(),
+                category: CraftingRecipeCategory::read(cursor)?,
+            },
+            ("minecraft", "smelting") => RecipeData::Smelting(CookingRecipe::read(cursor)?),
+            ("minecraft", "blasting") => RecipeData::Blasting(CookingRecipe::read(cursor)?),
+            ("minecraft", "smoking") => RecipeData::Smoking(CookingRecipe::read(cursor)?),
+            ("minecraft", "campfire_cooking") => RecipeData::CampfireCooking(CookingRecipe::read(cursor)?),
+            ("minecraft", "stonecutting") => RecipeData::Stonecutting {
+                group: Cow::read(cursor)?,
+                ingredient: RecipeIngredient::read(cursor)?,
+                result: Option::read(cursor)?,
+            },
+            ("minecraft", "smithing") => RecipeData::Smithing {
+                base: RecipeIngredient::read(cursor)?,
+                addition: RecipeIngredient::read(cursor)?,
+                result: Option::read(cursor)?,
+            },
+            _ => return Err(ProtocolError::Any(anyhow::Error::msg(format!("Unknown recipe type {}", ty)))),
+        };
+        Ok(Self { recipe_id, data })
+    }
+}
+
+#[cfg(test)]
+impl<'a> ProtocolArbitrary<'a> for Recipe<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        let recipe_id = Identifier::arbitrary(u)?;
+        let ingredients = |u: &mut Unstructured<'a>, count: usize| (0..count)
+            .map(|_| RecipeIngredient::arbitrary(u))
+            .collect::<ArbitraryResult<Cow<_>>>();
+        let data = match u.choose_index(9)? {
+            0 => RecipeData::CraftingShapeless {
+                group: Cow::arbitrary(u)?,
+                category: CraftingRecipeCategory::arbitrary(u)?,
+                ingredients: {
+                    let count = u.int_in_range(0..=9)?;
+                    ingredients(u, count)?
+                },
+                result: Option::arbitrary(u)?,
+            },
+            1 => {
+                let width = u.int_in_range(1..=3)?;
+                let height = u.int_in_range(1..=3)?;
+                RecipeData::CraftingShaped {
+                    width,
+                    height,
+                    group: Cow::arbitrary(u)?,
+                    category: CraftingRecipeCategory::arbitrary(u)?,
+                    ingredients: ingredients(u, shaped_recipe_size(width, height).ok_or(ArbitraryError::IncorrectFormat)?)?,
+                    result: Option::arbitrary(u)?,
+                }
+            }
+            2 => RecipeData::CraftingSpecial {
+                ty: Identifier::new_full(Cow::Owned(format!("minecraft:crafting_special_{}", u.int_in_range(0..=u32::MAX)?)))
+                    .ok_or(ArbitraryError::IncorrectFormat)?,
+                category: CraftingRecipeCategory::arbitrary(u)?,
+            },
+            3 => RecipeData::Smelting(CookingRecipe::arbitrary(u)?),
+            4 => RecipeData::Blasting(CookingRecipe::arbitrary(u)?),
+            5 => RecipeData::Smoking(CookingRecipe::arbitrary(u)?),
+            6 => RecipeData::CampfireCooking(CookingRecipe::arbitrary(u)?),
+            7 => RecipeData::Stonecutting {
+                group: Cow::arbitrary(u)?,
+                ingredient: RecipeIngredient::arbitrary(u)?,
+                result: Option::arbitrary(u)?,
+            },
+            _ => RecipeData::Smithing {
+                base: RecipeIngredient::arbitrary(u)?,
+                addition: RecipeIngredient::arbitrary(u)?,
+                result: Option::arbitrary(u)?,
+            },
+        };
+        Ok(Self { recipe_id, data })
+    }
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x69, state = Play, bound = Client)]
+pub struct UpdateRecipesPS2C<'a> {
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, Recipe<'a>, Recipe<'a>>")]
+    pub recipes: Cow<'a, [Recipe<'a>]>,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct Tag<'a> {
+    pub tag_name: Identifier<'a>,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, i32, VarInt>")]
+    pub entries: Cow<'a, [i32]>,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct TagRegistry<'a> {
+    pub registry: Identifier<'a>,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, Tag<'a>, Tag<'a>>")]
+    pub tags: Cow<'a, [Tag<'a>]>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x6A, state = Play, bound = Client)]
+pub struct UpdateTagsPS2C<'a> {
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, TagRegistry<'a>, TagRegistry<'a>>")]
+    pub registries: Cow<'a, [TagRegistry<'a>]>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x0, state = Play, bound = Server)]
+pub struct ConfirmTeleportationPC2S {
+    #[bp(variant = VarInt)]
+    pub teleport_id: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x1, state = Play, bound = Server)]
+pub struct QueryBlockEntityTagPC2S {
+    #[bp(variant = VarInt)]
+    pub transaction_id: i32,
+    #[bp(variant = BlockPosition)]
+    pub location: Vector3D<i32>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x2, state = Play, bound = Server)]
+pub struct ChangeDifficultyPC2S {
+    pub difficulty: Difficulty,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x3, state = Play, bound = Server)]
+pub struct MessageAcknowledgmentPC2S {
+    #[bp(variant = VarInt)]
+    pub message_count: i32,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct ChatCommandArgumentSignature<'a> {
+    pub argument_name: Cow<'a, str>,
+    #[bp(variant = "ConstLengthRawArray<u8, 256>")]
+    pub signature: Cow<'a, [u8; 256]>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x4, state = Play, bound = Server)]
+pub struct ChatCommandPC2S<'a> {
+    pub command: Cow<'a, str>,
+    pub timestamp: i64,
+    pub salt: i64,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, ChatCommandArgumentSignature<'a>, ChatCommandArgumentSignature<'a>>")]
+    pub argument_signatures: Cow<'a, [ChatCommandArgumentSignature<'a>]>,
+    #[bp(variant = VarInt)]
+    pub message_count: i32,
+    /// Fixed bit set of 20 bits
+    #[bp(variant = "ConstLengthArray<u8, 3>")]
+    pub acknowledged: [u8; 3],
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x5, state = Play, bound = Server)]
+pub struct ChatMessagePC2S<'a> {
+    pub message: Cow<'a, str>,
+    pub timestamp: i64,
+    pub salt: i64,
+    #[bp(variant = "ProtocolVariantOption<Cow<'a, [u8; 256]>, ConstLengthRawArray<u8, 256>>")]
+    pub signature: Option<Cow<'a, [u8; 256]>>,
+    #[bp(variant = VarInt)]
+    pub message_count: i32,
+    /// Fixed bit set of 20 bits
+    #[bp(variant = "ConstLengthArray<u8, 3>")]
+    pub acknowledged: [u8; 3],
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum ClientCommandAction {
+    PerformRespawn,
+    RequestStats,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x6, state = Play, bound = Server)]
+pub struct ClientCommandPC2S {
+    pub action: ClientCommandAction,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum ClientInformationChatMode {
+    Enabled,
+    CommandsOnly,
+    Hidden,
+}
+
+#[bitfield(u8)]
+#[derive(ProtocolAll, IntoOwned, PartialEq)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct ClientInformationSkinParts {
+    pub cape: bool,
+    pub jacket: bool,
+    pub left_sleeve: bool,
+    pub right_sleeve: bool,
+    pub left_pants_leg: bool,
+    pub right_pants_leg: bool,
+    pub hat: bool,
+    _gap: bool,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum ClientInformationMainHand {
+    Left,
+    Right,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x7, state = Play, bound = Server)]
+pub struct ClientInformationPC2S<'a> {
+    pub locale: Cow<'a, str>,
+    pub view_distance: i8,
+    pub chat_mode: ClientInformationChatMode,
+    pub chat_colors: bool,
+    pub displayed_skin_parts: ClientInformationSkinParts,
+    pub main_hand: ClientInformationMainHand,
+    pub enable_text_filtering: bool,
+    pub allow_server_listings: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x8, state = Play, bound = Server)]
+pub struct CommandSuggestionsRequestPC2S<'a> {
+    #[bp(variant = VarInt)]
+    pub transaction_id: i32,
+    pub text: Cow<'a, str>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x9, state = Play, bound = Server)]
+pub struct ClickContainerButtonPC2S {
+    pub window_id: i8,
+    pub button_id: i8,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum ClickContainerMode {
+    Pickup,
+    QuickMove,
+    Swap,
+    Clone,
+    Throw,
+    QuickCraft,
+    PickupAll,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct ClickContainerChangedSlot<'a> {
+    pub slot: i16,
+    pub slot_data: Option<Slot<'a>>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0xA, state = Play, bound = Server)]
+pub struct ClickContainerPC2S<'a> {
+    pub window_id: u8,
+    #[bp(variant = VarInt)]
+    pub state_id: i32,
+    pub slot: i16,
+    pub button: i8,
+    pub mode: ClickContainerMode,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, ClickContainerChangedSlot<'a>, ClickContainerChangedSlot<'a>>")]
+    pub changed_slots: Cow<'a, [ClickContainerChangedSlot<'a>]>,
+    pub carried_item: Option<Slot<'a>>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0xB, state = Play, bound = Server)]
+pub struct CloseContainerPC2S {
+    pub window_id: u8,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0xC, state = Play, bound = Server)]
+pub struct PluginMessagePC2S<'a> {
+    pub channel: Identifier<'a>,
+    #[bp(variant = RemainingBytesArray)]
+    pub data: Cow<'a, [u8]>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0xD, state = Play, bound = Server)]
+pub struct EditBookPC2S<'a> {
+    #[bp(variant = VarInt)]
+    pub slot: i32,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, Cow<'a, str>, Cow<'a, str>>")]
+    pub entries: Cow<'a, [Cow<'a, str>]>,
+    pub title: Option<Cow<'a, str>>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0xE, state = Play, bound = Server)]
+pub struct QueryEntityTagPC2S {
+    #[bp(variant = VarInt)]
+    pub transaction_id: i32,
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum Hand {
+    MainHand,
+    OffHand,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum InteractAction {
+    Interact {
+        hand: Hand,
+    },
+    Attack,
+    InteractAt {
+        target: Vector3D<f32>,
+        hand: Hand,
+    },
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0xF, state = Play, bound = Server)]
+pub struct InteractPC2S {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    pub action: InteractAction,
+    pub sneaking: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x10, state = Play, bound = Server)]
+pub struct JigsawGeneratePC2S {
+    #[bp(variant = BlockPosition)]
+    pub location: Vector3D<i32>,
+    #[bp(variant = VarInt)]
+    pub levels: i32,
+    pub keep_jigsaws: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x11, state = Play, bound = Server)]
+pub struct KeepAlivePC2S {
+    pub keep_alive_id: i64,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x12, state = Play, bound = Server)]
+pub struct LockDifficultyPC2S {
+    pub locked: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x13, state = Play, bound = Server)]
+pub struct SetPlayerPositionPC2S {
+    /// Y is feet position
+    pub position: Vector3D<f64>,
+    pub on_ground: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x14, state = Play, bound = Server)]
+pub struct SetPlayerPositionAndRotationPC2S {
+    /// Y is feet position
+    pub position: Vector3D<f64>,
+    /// Degrees
+    pub yaw: f32,
+    /// Degrees
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x15, state = Play, bound = Server)]
+pub struct SetPlayerRotationPC2S {
+    /// Degrees
+    pub yaw: f32,
+    /// Degrees
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x16, state = Play, bound = Server)]
+pub struct SetPlayerOnGroundPC2S {
+    pub on_ground: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x17, state = Play, bound = Server)]
+pub struct MoveVehiclePC2S {
+    pub position: Vector3D<f64>,
+    /// Degrees
+    pub yaw: f32,
+    /// Degrees
+    pub pitch: f32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x18, state = Play, bound = Server)]
+pub struct PaddleBoatPC2S {
+    pub left_paddle_turning: bool,
+    pub right_paddle_turning: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x19, state = Play, bound = Server)]
+pub struct PickItemPC2S {
+    #[bp(variant = VarInt)]
+    pub slot_to_use: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x1A, state = Play, bound = Server)]
+pub struct PlaceRecipePC2S<'a> {
+    pub window_id: i8,
+    pub recipe: Identifier<'a>,
+    pub make_all: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x1B, state = Play, bound = Server)]
+pub struct PlayerAbilitiesPC2S {
+    /// Only flying flag is used
+    pub flags: PlayerAbilitiesFlags,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum PlayerActionStatus {
+    StartedDigging,
+    CancelledDigging,
+    FinishedDigging,
+    DropItemStack,
+    DropItem,
+    ShootArrowOrFinishEating,
+    SwapItemInHand,
+}
+
+/// In use item on packet it is VarInt, but all values fit into one byte
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i8)]
+pub enum BlockFace {
+    Bottom,
+    Top,
+    North,
+    South,
+    West,
+    East,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x1C, state = Play, bound = Server)]
+pub struct PlayerActionPC2S {
+    pub status: PlayerActionStatus,
+    #[bp(variant = BlockPosition)]
+    pub location: Vector3D<i32>,
+    pub face: BlockFace,
+    #[bp(variant = VarInt)]
+    pub sequence: i32,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum PlayerCommandAction {
+    StartSneaking,
+    StopSneaking,
+    LeaveBed,
+    StartSprinting,
+    StopSprinting,
+    StartJumpWithHorse,
+    StopJumpWithHorse,
+    OpenHorseInventory,
+    StartFlyingWithElytra,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x1D, state = Play, bound = Server)]
+pub struct PlayerCommandPC2S {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    pub action: PlayerCommandAction,
+    /// Used only by start jump with horse action, from 0 to 100
+    #[bp(variant = VarInt)]
+    pub jump_boost: i32,
+}
+
+#[bitfield(u8)]
+#[derive(ProtocolAll, IntoOwned, PartialEq)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct PlayerInputFlags {
+    pub jump: bool,
+    pub unmount: bool,
+    #[bits(6)]
+    _gap: u8,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x1E, state = Play, bound = Server)]
+pub struct PlayerInputPC2S {
+    /// Positive to the left of the player
+    pub sideways: f32,
+    /// Positive forward
+    pub forward: f32,
+    pub flags: PlayerInputFlags,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x1F, state = Play, bound = Server)]
+pub struct PongPC2S {
+    pub id: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x20, state = Play, bound = Server)]
+pub struct PlayerSessionPC2S<'a> {
+    pub session_id: Uuid,
+    pub public_key_expire_time: i64,
+    #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
+    pub encoded_public_key: Cow<'a, [u8]>,
+    #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
+    pub public_key_signature: Cow<'a, [u8]>,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum RecipeBookType {
+    Crafting,
+    Furnace,
+    BlastFurnace,
+    Smoker,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x21, state = Play, bound = Server)]
+pub struct ChangeRecipeBookSettingsPC2S {
+    pub book_id: RecipeBookType,
+    pub book_open: bool,
+    pub filter_active: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x22, state = Play, bound = Server)]
+pub struct SetSeenRecipePC2S<'a> {
+    pub recipe_id: Identifier<'a>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x23, state = Play, bound = Server)]
+pub struct RenameItemPC2S<'a> {
+    pub item_name: Cow<'a, str>,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum ResourcePackResult {
+    SuccessfullyLoaded,
+    Declined,
+    FailedDownload,
+    Accepted,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x24, state = Play, bound = Server)]
+pub struct ResourcePackPC2S {
+    pub result: ResourcePackResult,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x25, state = Play, bound = Server, ty = i32, variant = VarInt)]
+pub enum SeenAdvancementsPC2S<'a> {
+    OpenedTab {
+        tab_id: Identifier<'a>,
+    },
+    ClosedScreen,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x26, state = Play, bound = Server)]
+pub struct SelectTradePC2S {
+    #[bp(variant = VarInt)]
+    pub selected_slot: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x27, state = Play, bound = Server)]
+pub struct SetBeaconEffectPC2S {
+    #[bp(variant = "ProtocolVariantOption<i32, VarInt>")]
+    pub primary_effect: Option<i32>,
+    #[bp(variant = "ProtocolVariantOption<i32, VarInt>")]
+    pub secondary_effect: Option<i32>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x28, state = Play, bound = Server)]
+pub struct SetHeldItemPC2S {
+    pub slot: i16,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum CommandBlockMode {
+    Sequence,
+    Auto,
+    Redstone,
+}
+
+#[bitfield(u8)]
+#[derive(ProtocolAll, IntoOwned, PartialEq)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct CommandBlockFlags {
+    pub track_output: bool,
+    pub conditional: bool,
+    pub automatic: bool,
+    #[bits(5)]
+    _gap: u8,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x29, state = Play, bound = Server)]
+pub struct ProgramCommandBlockPC2S<'a> {
+    #[bp(variant = BlockPosition)]
+    pub location: Vector3D<i32>,
+    pub command: Cow<'a, str>,
+    pub mode: CommandBlockMode,
+    pub flags: CommandBlockFlags,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x2A, state = Play, bound = Server)]
+pub struct ProgramCommandBlockMinecartPC2S<'a> {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    pub command: Cow<'a, str>,
+    pub track_output: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x2B, state = Play, bound = Server)]
+pub struct SetCreativeModeSlotPC2S<'a> {
+    pub slot: i16,
+    pub clicked_item: Option<Slot<'a>>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x2C, state = Play, bound = Server)]
+pub struct ProgramJigsawBlockPC2S<'a> {
+    #[bp(variant = BlockPosition)]
+    pub location: Vector3D<i32>,
+    pub name: Identifier<'a>,
+    pub target: Identifier<'a>,
+    pub pool: Identifier<'a>,
+    pub final_state: Cow<'a, str>,
+    pub joint_type: Cow<'a, str>,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum StructureBlockAction {
+    UpdateData,
+    SaveStructure,
+    LoadStructure,
+    DetectSize,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum StructureBlockMode {
+    Save,
+    Load,
+    Corner,
+    Data,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum StructureBlockMirror {
+    None,
+    LeftRight,
+    FrontBack,
+}
+
+#[derive(ProtocolAll, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum StructureBlockRotation {
+    None,
+    Clockwise90,
+    Clockwise180,
+    Counterclockwise90,
+}
+
+#[bitfield(u8)]
+#[derive(ProtocolAll, IntoOwned, PartialEq)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+pub struct StructureBlockFlags {
+    pub ignore_entities: bool,
+    pub show_air: bool,
+    pub show_bounding_box: bool,
+    #[bits(5)]
+    _gap: u8,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x2D, state = Play, bound = Server)]
+pub struct ProgramStructureBlockPC2S<'a> {
+    #[bp(variant = BlockPosition)]
+    pub location: Vector3D<i32>,
+    pub action: StructureBlockAction,
+    pub mode: StructureBlockMode,
+    pub name: Cow<'a, str>,
+    /// Between -48 and 48
+    pub offset: Vector3D<i8>,
+    /// Between 0 and 48
+    pub size: Vector3D<i8>,
+    pub mirror: StructureBlockMirror,
+    pub rotation: StructureBlockRotation,
+    pub metadata: Cow<'a, str>,
+    /// Between 0 and 1
+    pub integrity: f32,
+    #[bp(variant = VarLong)]
+    pub seed: i64,
+    pub flags: StructureBlockFlags,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x2E, state = Play, bound = Server)]
+pub struct UpdateSignPC2S<'a> {
+    #[bp(variant = BlockPosition)]
+    pub location: Vector3D<i32>,
+    pub first_line: Cow<'a, str>,
+    pub second_line: Cow<'a, str>,
+    pub third_line: Cow<'a, str>,
+    pub fourth_line: Cow<'a, str>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x2F, state = Play, bound = Server)]
+pub struct SwingArmPC2S {
+    pub hand: Hand,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x30, state = Play, bound = Server)]
+pub struct TeleportToEntityPC2S {
+    pub target_player: Uuid,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x31, state = Play, bound = Server)]
+pub struct UseItemOnPC2S {
+    pub hand: Hand,
+    #[bp(variant = BlockPosition)]
+    pub location: Vector3D<i32>,
+    pub face: BlockFace,
+    /// Position of the crosshair on the block, from 0 to 1
+    pub cursor_position: Vector3D<f32>,
+    pub inside_block: bool,
+    #[bp(variant = VarInt)]
+    pub sequence: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, IntoOwned, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(id = 0x32, state = Play, bound = Server)]
+pub struct UseItemPC2S {
+    pub hand: Hand,
+    #[bp(variant = VarInt)]
+    pub sequence: i32,
+}
+
+#[derive(ProtocolPacketRegistry, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(state = Handshake, bound = Server)]
+pub enum HandshakeC2SPacket<'a> {
+    Handshake(Handshake<'a>),
+}
+
+#[derive(ProtocolPacketRegistry, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(state = Status, bound = Client)]
+pub enum StatusS2CPacket<'a> {
+    StatusResponse(StatusResponseSS2C<'a>),
+    PingResponse(PingResponseSS2C),
+}
+
+#[derive(ProtocolPacketRegistry, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(state = Status, bound = Server)]
+pub enum StatusC2SPacket {
+    StatusRequest(StatusRequest),
+    PingRequest(PingRequestSC2S),
+}
+
+#[derive(ProtocolPacketRegistry, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(state = Login, bound = Client)]
+pub enum LoginS2CPacket<'a> {
+    LoginDisconnect(LoginDisconnectLS2C<'a>),
+    EncryptionRequest(EncryptionRequestLS2C<'a>),
+    LoginSuccess(LoginSuccessLS2C<'a>),
+    SetCompression(SetCompressionLS2C),
+    LoginPluginRequest(LoginPluginRequestLS2C<'a>),
+}
+
+#[derive(ProtocolPacketRegistry, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(state = Login, bound = Server)]
+pub enum LoginC2SPacket<'a> {
+    LoginStart(LoginStartLC2S<'a>),
+    EncryptionResponse(EncryptionResponseLC2S<'a>),
+    LoginPluginResponse(LoginPluginResponseLC2S<'a>),
+}
+
+#[derive(ProtocolPacketRegistry, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(state = Play, bound = Client)]
+pub enum PlayS2CPacket<'a> {
+    SpawnEntity(SpawnEntityPS2C),
+    SpawnExperienceOrb(SpawnExperienceOrbPS2C),
+    SpawnPlayer(SpawnPlayerPS2C),
+    EntityAnimation(EntityAnimationPS2C),
+    AwardStatistics(AwardStatisticsPS2C<'a>),
+    AcknowledgeBlockChange(AcknowledgeBlockChangePS2C),
+    SetBlockDestroyStage(SetBlockDestroyStagePS2C),
+    BlockEntityData(BlockEntityDataPS2C<'a>),
+    BlockAction(BlockActionPS2C),
+    BlockUpdate(BlockUpdatePS2C),
+    BossBar(BossBarPS2C<'a>),
+    ChangeDifficulty(ChangeDifficultyPS2C),
+    ClearTitles(ClearTitles),
+    CommandSuggestionsResponse(CommandSuggestionsResponsePS2C<'a>),
+    Commands(CommandsPS2C<'a>),
+    CloseContainer(CloseContainerPS2C),
+    SetContainerContent(SetContainerContentPS2C<'a>),
+    SetContainerProperty(SetContainerPropertyPS2C),
+    SetContainerSlot(SetContainerSlotPS2C<'a>),
+    SetCooldown(SetCooldownPS2C),
+    ChatSuggestions(ChatSuggestionsPS2C<'a>),
+    PluginMessage(PluginMessagePS2C<'a>),
+    DeleteMessage(DeleteMessagePS2C<'a>),
+    Disconnect(DisconnectPS2C<'a>),
+    DisguisedChatMessage(DisguisedChatMessagePS2C<'a>),
+    EntityEvent(EntityEventPS2C),
+    Explosion(ExplosionPS2C<'a>),
+    UnloadChunk(UnloadChunkPS2C),
+    GameEvent(GameEventPS2C),
+    OpenHorseScreen(OpenHorseScreenPS2C),
+    InitializeWorldBorder(InitializeWorldBorderPS2C),
+    KeepAlive(KeepAlivePS2C),
+    ChunkDataAndUpdateLight(ChunkDataAndUpdateLightPS2C<'a>),
+    WorldEvent(WorldEventPS2C),
+    Particle(ParticlePS2C<'a>),
+    UpdateLight(UpdateLightPS2C<'a>),
+    Login(LoginPS2C<'a>),
+    MapData(MapDataPS2C<'a>),
+    MerchantOffers(MerchantOffersPS2C<'a>),
+    UpdateEntityPosition(UpdateEntityPositionPS2C),
+    UpdateEntityPositionAndRotation(UpdateEntityPositionAndRotationPS2C),
+    UpdateEntityRotation(UpdateEntityRotationPS2C),
+    MoveVehicle(MoveVehiclePS2C),
+    OpenBook(OpenBookPS2C),
+    OpenScreen(OpenScreenPS2C<'a>),
+    OpenSignEditor(OpenSignEditorPS2C),
+    Ping(PingPS2C),
+    PlaceGhostRecipe(PlaceGhostRecipePS2C<'a>),
+    PlayerAbilities(PlayerAbilitiesPS2C),
+    PlayerChatMessage(PlayerChatMessagePS2C<'a>),
+    EndCombat(EndCombatPS2C),
+    EnterCombat(EnterCombatPS2C),
+    CombatDeath(CombatDeathPS2C<'a>),
+    PlayerInfoRemove(PlayerInfoRemovePS2C<'a>),
+    PlayerInfoUpdate(PlayerInfoUpdatePS2C<'a>),
+    LookAt(LookAtPS2C),
+    SynchronizePlayerPosition(SynchronizePlayerPositionPS2C),
+    UpdateRecipeBook(UpdateRecipeBookPS2C<'a>),
+    RemoveEntities(RemoveEntitiesPS2C<'a>),
+    RemoveEntityEffect(RemoveEntityEffectPS2C),
+    ResourcePack(ResourcePackPS2C<'a>),
+    Respawn(RespawnPS2C<'a>),
+    SetHeadRotation(SetHeadRotationPS2C),
+    UpdateSectionBlocks(UpdateSectionBlocksPS2C<'a>),
+    SelectAdvancementsTab(SelectAdvancementsTabPS2C<'a>),
+    ServerData(ServerDataPS2C<'a>),
+    SetActionBarText(SetActionBarTextPS2C<'a>),
+    SetBorderCenter(SetBorderCenterPS2C),
+    SetBorderLerpSize(SetBorderLerpSizePS2C),
+    SetBorderSize(SetBorderSizePS2C),
+    SetBorderWarningDelay(SetBorderWarningDelayPS2C),
+    SetBorderWarningDistance(SetBorderWarningDistancePS2C),
+    SetCamera(SetCameraPS2C),
+    SetHeldItem(SetHeldItemPS2C),
+    SetCenterChunk(SetCenterChunkPS2C),
+    SetRenderDistance(SetRenderDistancePS2C),
+    SetDefaultSpawnPosition(SetDefaultSpawnPositionPS2C),
+    DisplayObjective(DisplayObjectivePS2C<'a>),
+    SetEntityMetadata(SetEntityMetadataPS2C<'a>),
+    LinkEntities(LinkEntitiesPS2C),
+    SetEntityVelocity(SetEntityVelocityPS2C),
+    SetEquipment(SetEquipmentPS2C<'a>),
+    SetExperience(SetExperiencePS2C),
+    SetHealth(SetHealthPS2C),
+    UpdateObjectives(UpdateObjectivesPS2C<'a>),
+    SetPassengers(SetPassengersPS2C<'a>),
+    UpdateTeams(UpdateTeamsPS2C<'a>),
+    UpdateScore(UpdateScorePS2C<'a>),
+    SetSimulationDistance(SetSimulationDistancePS2C),
+    SetSubtitleText(SetSubtitleTextPS2C<'a>),
+    UpdateTime(UpdateTimePS2C),
+    SetTitleText(SetTitleTextPS2C<'a>),
+    SetTitleAnimationTimes(SetTitleAnimationTimesPS2C),
+    EntitySoundEffect(EntitySoundEffectPS2C<'a>),
+    SoundEffect(SoundEffectPS2C<'a>),
+    StopSound(StopSoundPS2C<'a>),
+    SystemChatMessage(SystemChatMessagePS2C<'a>),
+    SetTabListHeaderAndFooter(SetTabListHeaderAndFooterPS2C<'a>),
+    TagQueryResponse(TagQueryResponsePS2C<'a>),
+    PickupItem(PickupItemPS2C),
+    TeleportEntity(TeleportEntityPS2C),
+    UpdateAdvancements(UpdateAdvancementsPS2C<'a>),
+    UpdateAttributes(UpdateAttributesPS2C<'a>),
+    FeatureFlags(FeatureFlagsPS2C<'a>),
+    EntityEffect(EntityEffectPS2C<'a>),
+    UpdateRecipes(UpdateRecipesPS2C<'a>),
+    UpdateTags(UpdateTagsPS2C<'a>),
+}
+
+#[derive(ProtocolPacketRegistry, IntoOwned, Clone, PartialEq, Debug)]
+#[cfg_attr(test, derive(ProtocolArbitrary))]
+#[bp(state = Play, bound = Server)]
+pub enum PlayC2SPacket<'a> {
+    ConfirmTeleportation(ConfirmTeleportationPC2S),
+    QueryBlockEntityTag(QueryBlockEntityTagPC2S),
+    ChangeDifficulty(ChangeDifficultyPC2S),
+    MessageAcknowledgment(MessageAcknowledgmentPC2S),
+    ChatCommand(ChatCommandPC2S<'a>),
+    ChatMessage(ChatMessagePC2S<'a>),
+    ClientCommand(ClientCommandPC2S),
+    ClientInformation(ClientInformationPC2S<'a>),
+    CommandSuggestionsRequest(CommandSuggestionsRequestPC2S<'a>),
+    ClickContainerButton(ClickContainerButtonPC2S),
+    ClickContainer(ClickContainerPC2S<'a>),
+    CloseContainer(CloseContainerPC2S),
+    PluginMessage(PluginMessagePC2S<'a>),
+    EditBook(EditBookPC2S<'a>),
+    QueryEntityTag(QueryEntityTagPC2S),
+    Interact(InteractPC2S),
+    JigsawGenerate(JigsawGeneratePC2S),
+    KeepAlive(KeepAlivePC2S),
+    LockDifficulty(LockDifficultyPC2S),
+    SetPlayerPosition(SetPlayerPositionPC2S),
+    SetPlayerPositionAndRotation(SetPlayerPositionAndRotationPC2S),
+    SetPlayerRotation(SetPlayerRotationPC2S),
+    SetPlayerOnGround(SetPlayerOnGroundPC2S),
+    MoveVehicle(MoveVehiclePC2S),
+    PaddleBoat(PaddleBoatPC2S),
+    PickItem(PickItemPC2S),
+    PlaceRecipe(PlaceRecipePC2S<'a>),
+    PlayerAbilities(PlayerAbilitiesPC2S),
+    PlayerAction(PlayerActionPC2S),
+    PlayerCommand(PlayerCommandPC2S),
+    PlayerInput(PlayerInputPC2S),
+    Pong(PongPC2S),
+    PlayerSession(PlayerSessionPC2S<'a>),
+    ChangeRecipeBookSettings(ChangeRecipeBookSettingsPC2S),
+    SetSeenRecipe(SetSeenRecipePC2S<'a>),
+    RenameItem(RenameItemPC2S<'a>),
+    ResourcePack(ResourcePackPC2S),
+    SeenAdvancements(SeenAdvancementsPC2S<'a>),
+    SelectTrade(SelectTradePC2S),
+    SetBeaconEffect(SetBeaconEffectPC2S),
+    SetHeldItem(SetHeldItemPC2S),
+    ProgramCommandBlock(ProgramCommandBlockPC2S<'a>),
+    ProgramCommandBlockMinecart(ProgramCommandBlockMinecartPC2S<'a>),
+    SetCreativeModeSlot(SetCreativeModeSlotPC2S<'a>),
+    ProgramJigsawBlock(ProgramJigsawBlockPC2S<'a>),
+    ProgramStructureBlock(ProgramStructureBlockPC2S<'a>),
+    UpdateSign(UpdateSignPC2S<'a>),
+    SwingArm(SwingArmPC2S),
+    TeleportToEntity(TeleportToEntityPC2S),
+    UseItemOn(UseItemOnPC2S),
+    UseItem(UseItemPC2S),
+}
+
+#[derive(IntoOwned, Clone, PartialEq, Debug)]
+pub enum Packet<'a> {
+    HandshakeC2S(HandshakeC2SPacket<'a>),
+    StatusS2C(StatusS2CPacket<'a>),
+    StatusC2S(StatusC2SPacket),
+    LoginS2C(LoginS2CPacket<'a>),
+    LoginC2S(LoginC2SPacket<'a>),
+    PlayS2C(PlayS2CPacket<'a>),
+    PlayC2S(PlayC2SPacket<'a>),
+}
+
+impl<'a> Packet<'a> {
+    pub fn read_by_id<C: ProtocolCursor<'a>>(
+        state: ProtocolPacketState, bound: ProtocolPacketBound, id: i32, cursor: &mut C,
+    ) -> ProtocolResult<Self> {
+        Ok(match (state, bound) {
+            (Handshake, Server) => Self::HandshakeC2S(HandshakeC2SPacket::read_by_id(id, cursor)?),
+            (Status, Client) => Self::StatusS2C(StatusS2CPacket::read_by_id(id, cursor)?),
+            (Status, Server) => Self::StatusC2S(StatusC2SPacket::read_by_id(id, cursor)?),
+            (Login, Client) => Self::LoginS2C(LoginS2CPacket::read_by_id(id, cursor)?),
+            (Login, Server) => Self::LoginC2S(LoginC2SPacket::read_by_id(id, cursor)?),
+            (Play, Client) => Self::PlayS2C(PlayS2CPacket::read_by_id(id, cursor)?),
+            (Play, Server) => Self::PlayC2S(PlayC2SPacket::read_by_id(id, cursor)?),
+            (state, bound) => return Err(ProtocolError::Any(anyhow::Error::msg(
+                format!("There are no packets with state {:?} and bound {:?}", state, bound)
+            ))),
+        })
+    }
+
+    pub fn read_with_id<C: ProtocolCursor<'a>>(
+        state: ProtocolPacketState, bound: ProtocolPacketBound, cursor: &mut C,
+    ) -> ProtocolResult<Self> {
+        let id: i32 = VarInt::read_variant(cursor)?;
+        Self::read_by_id(state, bound, id, cursor)
+    }
+
+    /// Explains which bytes of the frame (packet id and data) are read as which fields
+    pub fn dissect(
+        state: ProtocolPacketState, bound: ProtocolPacketBound, frame: &'a [u8], version: Option<i32>,
+    ) -> Dissection<'a> {
+        Dissection::new(frame, version, |cursor| {
+            let id = read_packet_id(cursor)?;
+            Self::read_by_id(state, bound, id, cursor)
+        })
+    }
+
+    pub fn write_with_id<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        match self {
+            Self::HandshakeC2S(packet) => packet.write_with_id(writer),
+            Self::StatusS2C(packet) => packet.write_with_id(writer),
+            Self::StatusC2S(packet) => packet.write_with_id(writer),
+            Self::LoginS2C(packet) => packet.write_with_id(writer),
+            Self::LoginC2S(packet) => packet.write_with_id(writer),
+            Self::PlayS2C(packet) => packet.write_with_id(writer),
+            Self::PlayC2S(packet) => packet.write_with_id(writer),
+        }
+    }
+
+    pub fn packet_id(&self) -> i32 {
+        match self {
+            Self::HandshakeC2S(packet) => packet.packet_id(),
+            Self::StatusS2C(packet) => packet.packet_id(),
+            Self::StatusC2S(packet) => packet.packet_id(),
+            Self::LoginS2C(packet) => packet.packet_id(),
+            Self::LoginC2S(packet) => packet.packet_id(),
+            Self::PlayS2C(packet) => packet.packet_id(),
+            Self::PlayC2S(packet) => packet.packet_id(),
+        }
+    }
+}
+
+impl<'a> Packet<'a> {
+    /// Decodes the packet of a replayed capture record. The record is decompressed in place
+    pub fn read_record(record: &'a mut ProtocolCaptureRecord, version: Option<i32>) -> ProtocolFrameResult<Packet<'a>> {
+        record.decompress()?;
+        let record: &'a ProtocolCaptureRecord = record;
+        let mut cursor = record.payload.as_slice();
+        Ok(match version {
+            Some(version) => Packet::read_with_id(record.state, record.bound, &mut ProtocolVersionedCursor::new(cursor, version)),
+            None => Packet::read_with_id(record.state, record.bound, &mut cursor),
+        }?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bird_chat::component::ComponentType;
+    use bird_protocol::capture::{ProtocolCaptureReader, ProtocolCaptureWriter};
+    use bird_protocol::version::ProtocolVersionedWriter;
+
+    #[test]
+    fn gap_compact_longs_reader_test() {
+        let mut compact_longs_reader = unsafe {
+            GapCompactLongsReader::<_, 19>::new(
+                vec![0b111111111_001111111_000011111_000000111_000000001_0; 3].into_iter(),
+                9,
+            ).unwrap()
+        };
+        for i in 0..3 {
+            assert_eq!(compact_longs_reader.next(), Some(0b1));
+            assert_eq!(compact_longs_reader.next(), Some(0b111));
+            assert_eq!(compact_longs_reader.next(), Some(0b11111));
+            assert_eq!(compact_longs_reader.next(), Some(0b1111111));
+            assert_eq!(compact_longs_reader.next(), Some(0b111111111));
+            if i == 2 {
+                assert_eq!(compact_longs_reader.next(), None);
+            } else {
+                assert_eq!(compact_longs_reader.next(), Some(0b0));
+                assert_eq!(compact_longs_reader.next(), Some(0b0));
+            }
+        }
+    }
+
+    #[test]
+    fn gap_compact_longs_writer_test() {
+        let mut vec = Vec::new();
+        let mut compact_longs_writer = unsafe { GapCompactLongsWriter::new(&mut vec, 9) };
+        unsafe {
+            for i in 0..3 {
+                compact_longs_writer.write(0b1).unwrap();
+                compact_longs_writer.write(0b111).unwrap();
+                compact_longs_writer.write(0b11111).unwrap();
+                compact_longs_writer.write(0b1111111).unwrap();
+                compact_longs_writer.write(0b111111111).unwrap();
+                if i != 2 {
+                    compact_longs_writer.write(0b0).unwrap();
+                    compact_longs_writer.write(0b0).unwrap();
+                }
+            }
+        }
+        compact_longs_writer.finish().unwrap();
+        let mut res_vec = Vec::new();
+        for _ in 0..3 {
+            0b111111111_001111111_000011111_000000111_000000001_0_u64.write(&mut res_vec).unwrap();
+        }
+        assert_eq!(vec, res_vec);
     }
 
     #[test]
@@ -3320,5 +6088,925 @@ mod tests {
         assert_eq!(Particle::Glow.get_id(), 83);
         assert_eq!(Particle::ItemSlime.get_id(), 37);
         assert_eq!(Particle::Block { block_state: 2 }.get_id(), 2);
+        let falling_dust = Particle::FallingDust { block_state: 300 };
+        let mut bytes = Vec::new();
+        falling_dust.write(&mut bytes).unwrap();
+        assert_eq!(bytes, [24, 0xAC, 0x02]);
+        let mut cursor = bytes.as_slice();
+        assert_eq!(<Particle as ProtocolReadable>::read(&mut cursor).unwrap(), falling_dust);
+        assert!(cursor.is_empty());
+    }
+
+    #[test]
+    fn block_action_variant_test() {
+        // the block type is the key, it is written after the action id and the parameter
+        let mut bytes = vec![1, 3];
+        VarInt::write_variant(&(bird_data::block_data::CHEST.id as i32), &mut bytes).unwrap();
+        let chest = BlockActionVariant::Chest { players_looking_in: 3 };
+        let mut written = Vec::new();
+        chest.write(&mut written).unwrap();
+        assert_eq!(written, bytes);
+        let mut cursor = bytes.as_slice();
+        assert_eq!(BlockActionVariant::read(&mut cursor).unwrap(), chest);
+        assert!(cursor.is_empty());
+    }
+
+    #[test]
+    fn brigadier_range_properties_test() {
+        // 0x1 is set if there is a minimum and 0x2 is set if there is a maximum
+        let bytes = [0x2, 0, 0, 0, 5];
+        let range = BrigadierNodeRangeProperties { min: None, max: Some(5) };
+        let mut written = Vec::new();
+        range.write(&mut written).unwrap();
+        assert_eq!(written, bytes);
+        let mut cursor = bytes.as_slice();
+        assert_eq!(BrigadierNodeRangeProperties::read(&mut cursor).unwrap(), range);
+        assert!(cursor.is_empty());
+        let mut cursor = [0x1, 0, 0, 0, 3].as_slice();
+        assert_eq!(BrigadierNodeRangeProperties::read(&mut cursor).unwrap(), BrigadierNodeRangeProperties { min: Some(3), max: None });
+    }
+
+    #[test]
+    fn world_event_size_test() {
+        // event id, block position, event data and the relative volume flag
+        assert_eq!(WorldEventPS2C::SIZE, 17..17);
+        let event = WorldEventPS2C { event: WorldEvent::Dispense, location: Vector3D::new(1, 2, 3), disable_relative_volume: false };
+        assert_eq!(event.encoded_len(), 17);
+    }
+
+    #[test]
+    fn packet_registry_test() {
+        let packet = LoginS2CPacket::from(SetCompressionLS2C { threshold: 256 });
+        let mut bytes = Vec::new();
+        packet.write_with_id(&mut bytes).unwrap();
+        assert_eq!(bytes, [0x3, 0x80, 0x2]);
+        let read = Packet::read_with_id(Login, Client, &mut bytes.as_slice()).unwrap();
+        assert_eq!(read.packet_id(), 0x3);
+        match read {
+            Packet::LoginS2C(read) => assert_eq!(read, packet),
+            _ => panic!("Bad packet registry"),
+        }
+        assert!(Packet::read_by_id(Login, Client, 0x7F, &mut [0u8].as_slice()).is_err());
+    }
+
+    fn assert_play_c2s_round_trip(packets: Vec<PlayC2SPacket>) {
+        for packet in packets {
+            let mut bytes = Vec::new();
+            packet.write_with_id(&mut bytes).unwrap();
+            assert_eq!(packet.encoded_len_with_id(None), bytes.len());
+            let mut cursor = bytes.as_slice();
+            let read = PlayC2SPacket::read_with_id(&mut cursor).unwrap();
+            assert!(cursor.is_empty());
+            assert_eq!(format!("{:?}", read), format!("{:?}", packet));
+            let owned: PlayC2SPacket<'static> = read.clone().into_owned();
+            assert_eq!(format!("{:?}", owned), format!("{:?}", packet));
+            let mut rewritten = Vec::new();
+            read.write_with_id(&mut rewritten).unwrap();
+            assert_eq!(rewritten, bytes);
+        }
+    }
+
+    #[test]
+    fn play_c2s_round_trip_test() {
+        let signature = [7u8; 256];
+        let identifier = || Identifier::new_full(Cow::Borrowed("minecraft:stone")).unwrap();
+        let slot = Slot { item_id: 1, item_count: 64, nbt: Cow::Borrowed(&[10, 0, 0, 0]) };
+        assert_play_c2s_round_trip(vec![
+            ConfirmTeleportationPC2S { teleport_id: 300 }.into(),
+            QueryBlockEntityTagPC2S { transaction_id: 1, location: Vector3D::new(-5, 64, 1000) }.into(),
+            ChangeDifficultyPC2S { difficulty: Difficulty::Hard }.into(),
+            MessageAcknowledgmentPC2S { message_count: 3 }.into(),
+            ChatCommandPC2S {
+                command: Cow::Borrowed("say hi"),
+                timestamp: 1,
+                salt: -2,
+                argument_signatures: Cow::Owned(vec![ChatCommandArgumentSignature { argument_name: Cow::Borrowed("message"), signature: Cow::Borrowed(&signature) }]),
+                message_count: 1,
+                acknowledged: [1, 2, 3],
+            }.into(),
+            ChatMessagePC2S {
+                message: Cow::Borrowed("Hello"),
+                timestamp: 10,
+                salt: 20,
+                signature: Some(Cow::Borrowed(&signature)),
+                message_count: 0,
+                acknowledged: [0, 0, 0],
+            }.into(),
+            ClientCommandPC2S { action: ClientCommandAction::RequestStats }.into(),
+            ClientInformationPC2S {
+                locale: Cow::Borrowed("en_us"),
+                view_distance: 12,
+                chat_mode: ClientInformationChatMode::CommandsOnly,
+                chat_colors: true,
+                displayed_skin_parts: ClientInformationSkinParts::new().with_cape(true).with_hat(true),
+                main_hand: ClientInformationMainHand::Right,
+                enable_text_filtering: false,
+                allow_server_listings: true,
+            }.into(),
+            CommandSuggestionsRequestPC2S { transaction_id: 5, text: Cow::Borrowed("/tp ") }.into(),
+            ClickContainerButtonPC2S { window_id: 1, button_id: 2 }.into(),
+            ClickContainerPC2S {
+                window_id: 1,
+                state_id: 200,
+                slot: -999,
+                button: 0,
+                mode: ClickContainerMode::QuickCraft,
+                changed_slots: Cow::Owned(vec![
+                    ClickContainerChangedSlot { slot: 3, slot_data: Some(slot.clone()) },
+                    ClickContainerChangedSlot { slot: 4, slot_data: None },
+                ]),
+                carried_item: Some(slot.clone()),
+            }.into(),
+            CloseContainerPC2S { window_id: 0 }.into(),
+            PluginMessagePC2S { channel: identifier(), data: Cow::Borrowed(&[1, 2, 3]) }.into(),
+            EditBookPC2S { slot: 0, entries: Cow::Owned(vec![Cow::Borrowed("first"), Cow::Borrowed("second")]), title: Some(Cow::Borrowed("Title")) }.into(),
+            QueryEntityTagPC2S { transaction_id: 1, entity_id: 2 }.into(),
+            InteractPC2S { entity_id: 10, action: InteractAction::Attack, sneaking: true }.into(),
+            InteractPC2S {
+                entity_id: 11,
+                action: InteractAction::InteractAt { target: Vector3D::new(0.5, 1.0, 0.25), hand: Hand::OffHand },
+                sneaking: false,
+            }.into(),
+            JigsawGeneratePC2S { location: Vector3D::new(1, 2, 3), levels: 7, keep_jigsaws: true }.into(),
+            KeepAlivePC2S { keep_alive_id: i64::MAX }.into(),
+            LockDifficultyPC2S { locked: true }.into(),
+            SetPlayerPositionPC2S { position: Vector3D::new(1.5, 64.0, -3.25), on_ground: true }.into(),
+            SetPlayerPositionAndRotationPC2S { position: Vector3D::new(0.0, 1.0, 2.0), yaw: 90.0, pitch: -45.0, on_ground: false }.into(),
+            SetPlayerRotationPC2S { yaw: 180.0, pitch: 0.0, on_ground: true }.into(),
+            SetPlayerOnGroundPC2S { on_ground: false }.into(),
+            MoveVehiclePC2S { position: Vector3D::new(4.0, 5.0, 6.0), yaw: 1.0, pitch: 2.0 }.into(),
+            PaddleBoatPC2S { left_paddle_turning: true, right_paddle_turning: false }.into(),
+            PickItemPC2S { slot_to_use: 8 }.into(),
+            PlaceRecipePC2S { window_id: 1, recipe: identifier(), make_all: true }.into(),
+            PlayerAbilitiesPC2S { flags: PlayerAbilitiesFlags::new().with_flying(true) }.into(),
+            PlayerActionPC2S {
+                status: PlayerActionStatus::FinishedDigging,
+                location: Vector3D::new(-1, -64, 1),
+                face: BlockFace::East,
+                sequence: 42,
+            }.into(),
+            PlayerCommandPC2S { entity_id: 1, action: PlayerCommandAction::StartJumpWithHorse, jump_boost: 100 }.into(),
+            PlayerInputPC2S { sideways: 0.98, forward: -0.98, flags: PlayerInputFlags::new().with_jump(true) }.into(),
+            PongPC2S { id: -1 }.into(),
+            PlayerSessionPC2S {
+                session_id: Uuid::from_u128(1),
+                public_key_expire_time: 1000,
+                encoded_public_key: Cow::Borrowed(&[1, 2, 3]),
+                public_key_signature: Cow::Borrowed(&[4, 5, 6]),
+            }.into(),
+            ChangeRecipeBookSettingsPC2S { book_id: RecipeBookType::Smoker, book_open: true, filter_active: false }.into(),
+            SetSeenRecipePC2S { recipe_id: identifier() }.into(),
+            RenameItemPC2S { item_name: Cow::Borrowed("Sword") }.into(),
+            ResourcePackPC2S { result: ResourcePackResult::Accepted }.into(),
+            SeenAdvancementsPC2S::OpenedTab { tab_id: identifier() }.into(),
+            SeenAdvancementsPC2S::ClosedScreen.into(),
+            SelectTradePC2S { selected_slot: 2 }.into(),
+            SetBeaconEffectPC2S { primary_effect: Some(1), secondary_effect: None }.into(),
+            SetHeldItemPC2S { slot: 4 }.into(),
+            ProgramCommandBlockPC2S {
+                location: Vector3D::new(0, 0, 0),
+                command: Cow::Borrowed("say hi"),
+                mode: CommandBlockMode::Redstone,
+                flags: CommandBlockFlags::new().with_conditional(true),
+            }.into(),
+            ProgramCommandBlockMinecartPC2S { entity_id: 3, command: Cow::Borrowed("say hi"), track_output: true }.into(),
+            SetCreativeModeSlotPC2S { slot: 36, clicked_item: Some(slot.clone()) }.into(),
+            ProgramJigsawBlockPC2S {
+                location: Vector3D::new(1, 1, 1),
+                name: identifier(),
+                target: identifier(),
+                pool: identifier(),
+                final_state: Cow::Borrowed("minecraft:air"),
+                joint_type: Cow::Borrowed("rollable"),
+            }.into(),
+            ProgramStructureBlockPC2S {
+                location: Vector3D::new(1, 2, 3),
+                action: StructureBlockAction::SaveStructure,
+                mode: StructureBlockMode::Save,
+                name: Cow::Borrowed("house"),
+                offset: Vector3D::new(-48, 0, 48),
+                size: Vector3D::new(1, 2, 3),
+                mirror: StructureBlockMirror::FrontBack,
+                rotation: StructureBlockRotation::Clockwise180,
+                metadata: Cow::Borrowed(""),
+                integrity: 1.0,
+                seed: -1,
+                flags: StructureBlockFlags::new().with_show_air(true),
+            }.into(),
+            UpdateSignPC2S {
+                location: Vector3D::new(5, 6, 7),
+                first_line: Cow::Borrowed("1"),
+                second_line: Cow::Borrowed("2"),
+                third_line: Cow::Borrowed(""),
+                fourth_line: Cow::Borrowed("4"),
+            }.into(),
+            SwingArmPC2S { hand: Hand::MainHand }.into(),
+            TeleportToEntityPC2S { target_player: Uuid::from_u128(u128::MAX) }.into(),
+            UseItemOnPC2S {
+                hand: Hand::OffHand,
+                location: Vector3D::new(10, 20, 30),
+                face: BlockFace::Top,
+                cursor_position: Vector3D::new(0.5, 1.0, 0.5),
+                inside_block: false,
+                sequence: 7,
+            }.into(),
+            UseItemPC2S { hand: Hand::MainHand, sequence: 8 }.into(),
+        ]);
+    }
+
+    fn assert_play_s2c_round_trip(packets: Vec<PlayS2CPacket>) {
+        for packet in packets {
+            let mut bytes = Vec::new();
+            packet.write_with_id(&mut bytes).unwrap();
+            assert_eq!(packet.encoded_len_with_id(None), bytes.len());
+            let mut cursor = bytes.as_slice();
+            let read = PlayS2CPacket::read_with_id(&mut cursor).unwrap();
+            assert!(cursor.is_empty());
+            assert_eq!(format!("{:?}", read), format!("{:?}", packet));
+            let owned: PlayS2CPacket<'static> = read.clone().into_owned();
+            assert_eq!(format!("{:?}", owned), format!("{:?}", packet));
+            let mut rewritten = Vec::new();
+            read.write_with_id(&mut rewritten).unwrap();
+            assert_eq!(rewritten, bytes);
+        }
+    }
+
+    #[test]
+    fn play_s2c_round_trip_test() {
+        let identifier = |name| Identifier::new_full(Cow::Borrowed(name)).unwrap();
+        let slot = Slot { item_id: 1, item_count: 64, nbt: Cow::Borrowed(&[10, 0, 0, 0]) };
+        let text = |text| Component {
+            bold: None,
+            italic: None,
+            underlined: None,
+            strikethrough: None,
+            obfuscated: None,
+            font: None,
+            color: None,
+            insertion: None,
+            click_event: None,
+            extra: Cow::Owned(vec![]),
+            hover_event: None,
+            ty: Some(ComponentType::Text { text: Cow::Borrowed(text) }),
+        };
+        let ingredient = RecipeIngredient { items: Cow::Owned(vec![Some(slot.clone()), None]) };
+        assert_play_s2c_round_trip(vec![
+            LookAtPS2C {
+                anchor: LookAtAnchor::Eyes,
+                target: Vector3D::new(1.0, 2.0, 3.0),
+                entity: Some(LookAtEntity { entity_id: 5, entity_anchor: LookAtAnchor::Feet }),
+            }.into(),
+            UpdateRecipeBookPS2C::Init {
+                settings: RecipeBookSettings {
+                    crafting_open: true,
+                    crafting_filter_active: false,
+                    smelting_open: false,
+                    smelting_filter_active: true,
+                    blast_furnace_open: false,
+                    blast_furnace_filter_active: false,
+                    smoker_open: true,
+                    smoker_filter_active: false,
+                },
+                recipe_ids: Cow::Owned(vec![identifier("minecraft:stick")]),
+                highlighted_recipe_ids: Cow::Owned(vec![]),
+            }.into(),
+            UpdateSectionBlocksPS2C {
+                section_position: Vector3D::new(-3, -4, 100),
+                suppress_light_updates: false,
+                blocks: Cow::Owned(vec![
+                    UpdateSectionBlocksEntry { block_state: 1, position: Vector3D::new(15, 0, 7) },
+                    UpdateSectionBlocksEntry { block_state: 20000, position: Vector3D::new(0, 15, 1) },
+                ]),
+            }.into(),
+            SetEntityMetadataPS2C {
+                entity_id: 12,
+                metadata: Cow::Owned(vec![
+                    EntityMetadataEntry { index: 0, value: EntityMetadataValue::Byte(0x20) },
+                    EntityMetadataEntry { index: 2, value: EntityMetadataValue::OptionalChat(None) },
+                    EntityMetadataEntry { index: 6, value: EntityMetadataValue::Pose(EntityMetadataPose::Sneaking) },
+                    EntityMetadataEntry { index: 8, value: EntityMetadataValue::Slot(Some(slot.clone())) },
+                    EntityMetadataEntry {
+                        index: 10,
+                        value: EntityMetadataValue::OptionalPosition(Some(Vector3D::new(1, -2, 3))),
+                    },
+                    EntityMetadataEntry {
+                        index: 11,
+                        value: EntityMetadataValue::Particle(Particle::Block { block_state: 9 }),
+                    },
+                ]),
+            }.into(),
+            SetEntityMetadataPS2C { entity_id: 13, metadata: Cow::Owned(vec![]) }.into(),
+            SetEquipmentPS2C {
+                entity_id: 3,
+                equipment: Cow::Owned(vec![
+                    SetEquipmentEntry { slot: EquipmentSlot::MainHand, item: Some(slot.clone()) },
+                    SetEquipmentEntry { slot: EquipmentSlot::Helmet, item: None },
+                ]),
+            }.into(),
+            UpdateTeamsPS2C {
+                team_name: Cow::Borrowed("red"),
+                action: UpdateTeamsAction::AddEntities { entities: Cow::Owned(vec![Cow::Borrowed("Notch"), Cow::Borrowed("jeb_")]) },
+            }.into(),
+            UpdateScorePS2C {
+                entity_name: Cow::Borrowed("Notch"),
+                action: UpdateScoreAction::Update { objective_name: Cow::Borrowed("kills"), value: 10 },
+            }.into(),
+            EntitySoundEffectPS2C {
+                sound: SoundEvent::Registry(0),
+                sound_category: SoundCategory::Hostile,
+                entity_id: 1,
+                volume: 1.0,
+                pitch: 0.5,
+                seed: 7,
+            }.into(),
+            SoundEffectPS2C {
+                sound: SoundEvent::Direct { sound_name: identifier("custom:sound"), fixed_range: Some(16.0) },
+                sound_category: SoundCategory::Master,
+                effect_position_x: 1.5,
+                effect_position_y: -64.0,
+                effect_position_z: 0.125,
+                volume: 1.0,
+                pitch: 1.0,
+                seed: -7,
+            }.into(),
+            StopSoundPS2C { source: None, sound: Some(identifier("minecraft:music")) }.into(),
+            StopSoundPS2C { source: Some(SoundCategory::Weather), sound: None }.into(),
+            UpdateAdvancementsPS2C {
+                reset: true,
+                advancements: Cow::Owned(vec![AdvancementMapping {
+                    key: identifier("minecraft:story/root"),
+                    value: Advancement {
+                        parent_id: None,
+                        display: Some(AdvancementDisplay {
+                            title: text("Minecraft"),
+                            description: text("The heart and story of the game"),
+                            icon: Some(slot.clone()),
+                            frame_type: AdvancementFrameType::Task,
+                            background_texture: Some(identifier("minecraft:textures/gui/advancements/backgrounds/stone.png")),
+                            show_toast: false,
+                            hidden: true,
+                            x: 0.0,
+                            y: 1.5,
+                        }),
+                        criteria: Cow::Owned(vec![identifier("minecraft:crafting_table")]),
+                        requirements: Cow::Owned(vec![Cow::Owned(vec![Cow::Borrowed("crafting_table")])]),
+                    },
+                }]),
+                removed: Cow::Owned(vec![]),
+                progress: Cow::Owned(vec![AdvancementProgressMapping {
+                    key: identifier("minecraft:story/root"),
+                    criteria: Cow::Owned(vec![AdvancementCriterionProgress {
+                        criterion: identifier("minecraft:crafting_table"),
+                        date_of_achieving: Some(1000),
+                    }]),
+                }]),
+            }.into(),
+            UpdateAttributesPS2C {
+                entity_id: 1,
+                properties: Cow::Owned(vec![AttributeProperty {
+                    key: identifier("minecraft:generic.movement_speed"),
+                    value: 0.1,
+                    modifiers: Cow::Owned(vec![AttributeModifier {
+                        uuid: Uuid::from_u128(5),
+                        amount: 0.3,
+                        operation: AttributeModifierOperation::MultiplyTotal,
+                    }]),
+                }]),
+            }.into(),
+            EntityEffectPS2C {
+                entity_id: 1,
+                effect_id: 2,
+                amplifier: 1,
+                duration: -1,
+                flags: EntityEffectFlags::new().with_show_icon(true),
+                factor_codec: None,
+            }.into(),
+            UpdateRecipesPS2C {
+                recipes: Cow::Owned(vec![
+                    Recipe {
+                        recipe_id: identifier("minecraft:stick"),
+                        data: RecipeData::CraftingShaped {
+                            width: 1,
+                            height: 2,
+                            group: Cow::Borrowed("sticks"),
+                            category: CraftingRecipeCategory::Misc,
+                            ingredients: Cow::Owned(vec![ingredient.clone(), ingredient.clone()]),
+                            result: Some(slot.clone()),
+                        },
+                    },
+                    Recipe {
+                        recipe_id: identifier("minecraft:armor_dye"),
+                        data: RecipeData::CraftingSpecial {
+                            ty: identifier("minecraft:crafting_special_armordye"),
+                            category: CraftingRecipeCategory::Equipment,
+                        },
+                    },
+                    Recipe {
+                        recipe_id: identifier("minecraft:iron_ingot"),
+                        data: RecipeData::Blasting(CookingRecipe {
+                            group: Cow::Borrowed(""),
+                            category: CookingRecipeCategory::Misc,
+                            ingredient,
+                            result: Some(slot.clone()),
+                            experience: 0.7,
+                            cooking_time: 100,
+                        }),
+                    },
+                ]),
+            }.into(),
+            UpdateTagsPS2C {
+                registries: Cow::Owned(vec![TagRegistry {
+                    registry: identifier("minecraft:block"),
+                    tags: Cow::Owned(vec![Tag { tag_name: identifier("minecraft:logs"), entries: Cow::Owned(vec![1, 2, 300]) }]),
+                }]),
+            }.into(),
+        ]);
+    }
+
+    #[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+    #[bp(id = 0x2, state = Play, bound = Client, versions = [(until = 759, id = 0x1), (since = 760, until = 760, id = 0x3)])]
+    struct VersionedPacket {
+        value: i32,
+        #[bp(until = 760)]
+        old_value: i8,
+        #[bp(since = 760)]
+        new_value: Option<bool>,
+    }
+
+    #[test]
+    fn var_int_id_arrays_test() {
+        let packet = RemoveEntitiesPS2C { entity_ids: Cow::Borrowed(&[1, 300]) };
+        let mut bytes = Vec::new();
+        packet.write(&mut bytes).unwrap();
+        assert_eq!(bytes, vec![2, 1, 0xAC, 0x02]);
+        assert_eq!(RemoveEntitiesPS2C::read(&mut bytes.as_slice()).unwrap(), packet);
+
+        let packet = SetPassengersPS2C { entity_id: 5, passengers: Cow::Borrowed(&[300, -1]) };
+        let mut bytes = Vec::new();
+        packet.write(&mut bytes).unwrap();
+        assert_eq!(bytes, vec![5, 2, 0xAC, 0x02, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+        assert_eq!(SetPassengersPS2C::read(&mut bytes.as_slice()).unwrap(), packet);
+
+        let tag = Tag {
+            tag_name: Identifier::new_full(Cow::Borrowed("minecraft:logs")).unwrap(),
+            entries: Cow::Borrowed(&[128]),
+        };
+        let mut bytes = Vec::new();
+        tag.write(&mut bytes).unwrap();
+        let mut expected = vec![14];
+        expected.extend_from_slice(b"minecraft:logs");
+        expected.extend_from_slice(&[1, 0x80, 0x01]);
+        assert_eq!(bytes, expected);
+        assert_eq!(Tag::read(&mut bytes.as_slice()).unwrap(), tag);
+    }
+
+    #[test]
+    fn play_s2c_bad_values_test() {
+        let equipment = SetEquipmentPS2C { entity_id: 1, equipment: Cow::Borrowed(&[]) };
+        assert!(equipment.write(&mut Vec::new()).is_err());
+        assert!(SoundEvent::Registry(i32::MAX).write(&mut Vec::new()).is_err());
+        let mut bytes = Vec::new();
+        VarInt::write_variant(&i32::MIN, &mut bytes).unwrap();
+        assert!(SoundEvent::read(&mut bytes.as_slice()).is_err());
+
+        // the size of 46340 * 46340 ingredients fits into i32, but not into the remaining bytes
+        for (width, height) in [(-1, -1), (i32::MAX, 2), (46340, 46340)] {
+            let mut bytes = Vec::new();
+            "minecraft:crafting_shaped".write(&mut bytes).unwrap();
+            "minecraft:stick".write(&mut bytes).unwrap();
+            VarInt::write_variant(&width, &mut bytes).unwrap();
+            VarInt::write_variant(&height, &mut bytes).unwrap();
+            "".write(&mut bytes).unwrap();
+            CraftingRecipeCategory::Misc.write(&mut bytes).unwrap();
+            assert!(Recipe::read(&mut bytes.as_slice()).is_err());
+        }
+        let recipe = Recipe {
+            recipe_id: Identifier::new_full(Cow::Borrowed("minecraft:stick")).unwrap(),
+            data: RecipeData::CraftingShaped {
+                width: -1,
+                height: -1,
+                group: Cow::Borrowed(""),
+                category: CraftingRecipeCategory::Misc,
+                ingredients: Cow::Borrowed(&[]),
+                result: None,
+            },
+        };
+        assert!(recipe.write(&mut Vec::new()).is_err());
+
+        let metadata = |entries: usize| {
+            let mut bytes = vec![1];
+            for _ in 0..entries {
+                bytes.extend_from_slice(&[0, 0, 0]);
+            }
+            bytes.push(ENTITY_METADATA_END);
+            bytes
+        };
+        let bytes = metadata(MAX_ENTITY_METADATA_ENTRIES);
+        assert_eq!(SetEntityMetadataPS2C::read(&mut bytes.as_slice()).unwrap().metadata.len(), MAX_ENTITY_METADATA_ENTRIES);
+        let bytes = metadata(MAX_ENTITY_METADATA_ENTRIES + 1);
+        assert!(matches!(SetEntityMetadataPS2C::read(&mut bytes.as_slice()), Err(ProtocolError::ArrayTooLong { .. })));
+    }
+
+    #[test]
+    fn protocol_versions_test() {
+        assert_eq!(VersionedPacket::id_for_version(None), 0x2);
+        assert_eq!(VersionedPacket::id_for_version(Some(758)), 0x1);
+        assert_eq!(VersionedPacket::id_for_version(Some(760)), 0x3);
+        assert_eq!(VersionedPacket::id_for_version(Some(761)), 0x2);
+        let packet = VersionedPacket { value: 1, old_value: 2, new_value: Some(true) };
+        let write_versioned = |version| {
+            let mut writer = ProtocolVersionedWriter::new(Vec::new(), version);
+            packet.write(&mut writer).unwrap();
+            writer.into_inner()
+        };
+        assert_eq!(write_versioned(759), vec![0, 0, 0, 1, 2]);
+        assert_eq!(write_versioned(760), vec![0, 0, 0, 1, 2, 1, 1]);
+        assert_eq!(write_versioned(761), vec![0, 0, 0, 1, 1, 1]);
+        let mut cursor = ProtocolVersionedCursor::new([0u8, 0, 0, 1, 2].as_slice(), 759);
+        assert_eq!(
+            VersionedPacket::read(&mut cursor).unwrap(),
+            VersionedPacket { value: 1, old_value: 2, new_value: None },
+        );
+        assert_eq!(cursor.remaining_bytes(), 0);
+        let mut cursor = [0u8, 0, 0, 1, 1, 0].as_slice();
+        assert_eq!(
+            VersionedPacket::read(&mut cursor).unwrap(),
+            VersionedPacket { value: 1, old_value: 0, new_value: Some(false) },
+        );
+        assert_eq!(VersionedPacket::SIZE, 4..7);
+
+        // unknown version is the current one, so fields which are removed after it are present
+        let position = SynchronizePlayerPositionPS2C {
+            position: Vector3D::new(1.0, 2.0, 3.0),
+            yaw: 0.0,
+            pitch: 0.0,
+            flags: SynchronizePlayerPositionFlags::new(),
+            teleport_id: 1,
+            dismount_vehicle: true,
+        };
+        let mut bytes = Vec::new();
+        position.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 35);
+        assert_eq!(bytes.last(), Some(&1));
+        assert_eq!(SynchronizePlayerPositionPS2C::read(&mut bytes.as_slice()).unwrap(), position);
+    }
+
+    #[test]
+    fn error_context_test() {
+        let bytes = [0xF9, 0x05, 1, b'a', 0x63, 0xDD, 3];
+        let err = super::Handshake::read(&mut bytes.as_slice()).unwrap_err();
+        assert!(matches!(err.root(), ProtocolError::BadEnumKey { ty: "HandshakeNextState", .. }));
+        assert_eq!(err.to_string(), "Handshake.next_state @ byte 6: Bad key 3 of HandshakeNextState");
+        let mut bytes = vec![11];
+        bytes.extend_from_slice(b"minecraft:a");
+        bytes.extend_from_slice(&[0, 0, 0]);
+        let err = LoginDeathLocation::read(&mut bytes.as_slice()).unwrap_err();
+        assert!(err.is_end());
+        assert_eq!(err.to_string(), "LoginDeathLocation.location @ byte 12: Tried to take too many bytes");
+        let err = LoginDeathLocation::read(&mut [2, 0xFF, 0xFE].as_slice()).unwrap_err();
+        assert!(matches!(err.root(), ProtocolError::InvalidUtf8(_)));
+    }
+
+    #[test]
+    fn enum_error_context_test() {
+        let mut bytes = vec![0];
+        bytes.extend_from_slice(&1i64.to_be_bytes());
+        bytes.extend_from_slice(&[5, 1]);
+        let err = EncryptionResponseVariant::read(&mut bytes.as_slice()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "EncryptionResponseVariant.Otherwise.message_signature @ byte 9: Tried to take too many bytes",
+        );
+        let err = EncryptionResponseVariant::read(&mut [].as_slice()).unwrap_err();
+        assert_eq!(err.to_string(), "EncryptionResponseVariant.key @ byte 0: Tried to take too many bytes");
+        let err = BlockActionVariant::read(&mut [1, 0].as_slice()).unwrap_err();
+        assert_eq!(err.to_string(), "BlockActionVariant.key @ byte 2: Tried to take too many bytes");
+    }
+
+    #[test]
+    fn max_len_test() {
+        let login_start = |name| LoginStartLC2S { name, signature_data: None, uuid: None };
+        let mut bytes = Vec::new();
+        login_start(Cow::Borrowed("sixteen_letters_")).write(&mut bytes).unwrap();
+        assert_eq!(LoginStartLC2S::read(&mut bytes.as_slice()).unwrap(), login_start(Cow::Borrowed("sixteen_letters_")));
+        assert!(login_start(Cow::Borrowed("seventeen_letters")).write(&mut Vec::new()).is_err());
+        let mut bytes = vec![17];
+        bytes.extend_from_slice(b"seventeen_letters");
+        bytes.extend_from_slice(&[0, 0]);
+        let err = LoginStartLC2S::read(&mut bytes.as_slice()).unwrap_err();
+        assert!(matches!(err.root(), ProtocolError::StringTooLong { length: 17, limit: 16 }));
+        // strings are limited in UTF-16 code units, so a bird takes two of them
+        let birds = |count| Cow::Owned("\u{1F426}".repeat(count));
+        login_start(birds(8)).write(&mut Vec::new()).unwrap();
+        assert!(login_start(birds(9)).write(&mut Vec::new()).is_err());
+        let mut bytes = Vec::new();
+        VarInt::write_variant(&(17 * 3), &mut bytes).unwrap();
+        let err = LoginStartLC2S::read(&mut bytes.as_slice()).unwrap_err();
+        assert!(matches!(err.root(), ProtocolError::StringTooLong { length: 17, limit: 16 }));
+        // protocol version, server address of 255 chars with 4 bytes each, port and next state
+        assert_eq!(super::Handshake::SIZE.end, 5 + (5 + 255 * 4) + 2 + 5);
+        type Limited = LengthLimited<LengthProvidedBytesArray<i32, VarInt>, 4>;
+        assert_eq!(Limited::SIZE, 1..9);
+        let mut bytes = Vec::new();
+        assert!(Limited::write_variant(&[1u8, 2, 3, 4, 5].as_slice(), &mut bytes).is_err());
+        Limited::write_variant(&[1u8, 2, 3, 4].as_slice(), &mut bytes).unwrap();
+        let read: &[u8] = Limited::read_variant(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read, &[1, 2, 3, 4]);
+    }
+
+    #[derive(ProtocolAll, Clone, PartialEq, Debug)]
+    struct LimitedArrays<'a> {
+        #[bp(max_len = 2, variant = "LengthProvidedArray<i32, VarInt, i32, VarInt>")]
+        values: Vec<i32>,
+        #[bp(max_len = 3, variant = "LengthProvidedBytesArray<i32, VarInt>")]
+        bytes: Cow<'a, [u8]>,
+    }
+
+    #[test]
+    fn max_len_array_test() {
+        let arrays = LimitedArrays { values: vec![1, 300], bytes: Cow::Borrowed(&[1, 2, 3]) };
+        let mut bytes = Vec::new();
+        arrays.write(&mut bytes).unwrap();
+        assert_eq!(LimitedArrays::read(&mut bytes.as_slice()).unwrap(), arrays);
+        assert!(LimitedArrays { values: vec![1, 2, 3], ..arrays.clone() }.write(&mut Vec::new()).is_err());
+        // the declared lengths are rejected before the elements, which are not present, are read
+        let mut bytes = Vec::new();
+        VarInt::write_variant(&i32::MAX, &mut bytes).unwrap();
+        let err = LimitedArrays::read(&mut bytes.as_slice()).unwrap_err();
+        assert!(matches!(err.root(), ProtocolError::ArrayTooLong { length: 2147483647, limit: 2 }));
+        let mut bytes = vec![0];
+        VarInt::write_variant(&4, &mut bytes).unwrap();
+        let err = LimitedArrays::read(&mut bytes.as_slice()).unwrap_err();
+        assert!(matches!(err.root(), ProtocolError::ArrayTooLong { length: 4, limit: 3 }));
+    }
+
+    #[derive(ProtocolAll, Clone, PartialEq, Debug)]
+    struct DependentFields {
+        count: u8,
+        #[bp(len_from = count, variant = VarInt)]
+        values: Vec<i32>,
+        #[bp(if = "*count > 1")]
+        extra: Option<bool>,
+    }
+
+    #[derive(ProtocolAll, Clone, PartialEq, Debug)]
+    struct FlaggedFields<'a> {
+        flags: BrigadierNodeFlags,
+        #[bp(flag = flags.redirect, variant = VarInt)]
+        redirect_node: Option<i32>,
+        #[bp(if = "flags.node_type() != ROOT_NODE_TYPE")]
+        name: Option<&'a str>,
+    }
+
+    #[test]
+    fn dependent_fields_test() {
+        let node = BrigadierNode {
+            flags: BrigadierNodeFlags::new()
+                .with_node_type(ARGUMENT_NODE_TYPE)
+                .with_executable(true)
+                .with_redirect(true)
+                .with_suggestions_type(true),
+            children: Cow::Owned(vec![1, 300]),
+            redirect_node: Some(300),
+            name: Some(Cow::Borrowed("amount")),
+            parser: Some(BrigadierNodeParser::Bool),
+            suggestions_type: Some(Identifier::new_full(Cow::Borrowed("minecraft:ask_server")).unwrap()),
+        };
+        let mut bytes = Vec::new();
+        node.write(&mut bytes).unwrap();
+        assert_eq!(bytes[..7], [0x1E, 2, 1, 0xAC, 0x02, 0xAC, 0x02]);
+        assert_eq!(BrigadierNode::read(&mut bytes.as_slice()).unwrap(), node);
+        let root = BrigadierNode {
+            flags: BrigadierNodeFlags::new(),
+            children: Cow::Owned(vec![]),
+            redirect_node: None,
+            name: None,
+            parser: None,
+            suggestions_type: None,
+        };
+        let mut bytes = Vec::new();
+        root.write(&mut bytes).unwrap();
+        assert_eq!(bytes, vec![0, 0]);
+        assert_eq!(BrigadierNode::read(&mut bytes.as_slice()).unwrap(), root);
+        let literal = BrigadierNode {
+            flags: BrigadierNodeFlags::new().with_node_type(LITERAL_NODE_TYPE),
+            name: Some(Cow::Borrowed("tp")),
+            ..root.clone()
+        };
+        let mut bytes = Vec::new();
+        literal.write(&mut bytes).unwrap();
+        assert_eq!(bytes, vec![1, 0, 2, b't', b'p']);
+        assert_eq!(BrigadierNode::read(&mut bytes.as_slice()).unwrap(), literal);
+        assert!(BrigadierNode { parser: Some(BrigadierNodeParser::Bool), ..literal }.write(&mut Vec::new()).is_err());
+        assert!(BrigadierNode { name: Some(Cow::Borrowed("tp")), ..root }.write(&mut Vec::new()).is_err());
+
+        let flagged = FlaggedFields {
+            flags: BrigadierNodeFlags::new().with_node_type(LITERAL_NODE_TYPE).with_redirect(true),
+            redirect_node: Some(300),
+            name: Some("tp"),
+        };
+        let mut bytes = Vec::new();
+        flagged.write(&mut bytes).unwrap();
+        assert_eq!(bytes, vec![0x09, 0xAC, 0x02, 2, b't', b'p']);
+        assert_eq!(FlaggedFields::read(&mut bytes.as_slice()).unwrap(), flagged);
+        assert!(FlaggedFields { redirect_node: None, ..flagged.clone() }.write(&mut Vec::new()).is_err());
+        assert!(FlaggedFields { flags: BrigadierNodeFlags::new(), redirect_node: None, ..flagged }.write(&mut Vec::new()).is_err());
+
+        let fields = DependentFields { count: 2, values: vec![1, 300], extra: Some(true) };
+        let mut bytes = Vec::new();
+        fields.write(&mut bytes).unwrap();
+        assert_eq!(bytes, vec![2, 1, 0xAC, 0x02, 1]);
+        assert_eq!(DependentFields::read(&mut bytes.as_slice()).unwrap(), fields);
+        let fields = DependentFields { count: 1, values: vec![5], extra: None };
+        let mut bytes = Vec::new();
+        fields.write(&mut bytes).unwrap();
+        assert_eq!(bytes, vec![1, 5]);
+        assert_eq!(DependentFields::read(&mut bytes.as_slice()).unwrap(), fields);
+        assert!(DependentFields { count: 3, ..fields.clone() }.write(&mut Vec::new()).is_err());
+        assert!(DependentFields::read(&mut [3, 1].as_slice()).unwrap_err().is_end());
+        assert_eq!(DependentFields::SIZE, 1..u32::MAX);
+    }
+
+    #[derive(ProtocolAll, Clone, PartialEq, Debug)]
+    struct GenericFields<T: Clone, const N: usize> where T: std::fmt::Debug {
+        value: Option<T>,
+        #[bp(variant = "ConstLengthArray<u8, N>")]
+        fixed: [u8; N],
+    }
+
+    #[test]
+    fn generic_derive_test() {
+        let fields = GenericFields::<i16, 2> { value: Some(5), fixed: [1, 2] };
+        let mut bytes = Vec::new();
+        fields.write(&mut bytes).unwrap();
+        assert_eq!(bytes, vec![1, 0, 5, 1, 2]);
+        assert_eq!(GenericFields::read(&mut bytes.as_slice()).unwrap(), fields);
+        assert_eq!(GenericFields::<i16, 2>::SIZE, 3..5);
+        assert_eq!(GenericFields::<i16, 4>::SIZE, 5..7);
+        let section = ChunkSectionData {
+            block_count: 1,
+            block_states: PalettedContainer::new_single(1),
+            biomes: PalettedContainer::new_single(2),
+        };
+        let mut bytes = Vec::new();
+        section.write(&mut bytes).unwrap();
+        let mut written = Vec::new();
+        ChunkSectionData::read(&mut bytes.as_slice()).unwrap().write(&mut written).unwrap();
+        assert_eq!(written, bytes);
+    }
+
+    #[test]
+    fn dissect_test() {
+        let mut frame = Vec::new();
+        PlayS2CPacket::from(UpdateObjectivesPS2C {
+            objective_name: Cow::Borrowed("a"),
+            action: UpdateObjectivesAction::Remove,
+        }).write_with_id(&mut frame).unwrap();
+        let dissection = Packet::dissect(Play, Client, &frame, None);
+        assert!(dissection.error.is_none());
+        assert_eq!(dissection.read, 4);
+        let fields: Vec<_> = dissection.nodes.iter()
+            .map(|node| (node.ty, node.path, node.range.clone(), node.value.as_deref()))
+            .collect();
+        assert_eq!(fields, vec![
+            ("packet", "id", 0..1, Some("84")),
+            ("UpdateObjectivesPS2C", "objective_name", 1..3, Some("\"a\"")),
+            ("UpdateObjectivesPS2C", "action", 3..4, Some("Remove")),
+        ]);
+        let key = &dissection.nodes[2].children[0];
+        assert_eq!((key.ty, key.path, key.range.clone()), ("UpdateObjectivesAction", "key", 3..4));
+        let dissection = Packet::dissect(Play, Client, &frame[..3], None);
+        assert!(dissection.error.is_some());
+        assert!(!dissection.nodes[2].complete);
+        assert!(dissection.to_string().contains("UpdateObjectivesPS2C.action <failed>"));
+    }
+
+    #[test]
+    fn read_capture_test() {
+        let mut frame = Vec::new();
+        PlayS2CPacket::from(UpdateObjectivesPS2C {
+            objective_name: Cow::Borrowed("a"),
+            action: UpdateObjectivesAction::Remove,
+        }).write_with_id(&mut frame).unwrap();
+        let mut writer = ProtocolCaptureWriter::new(Vec::new(), Some(761)).unwrap();
+        let mut record = ProtocolCaptureRecord::new(Client, Play, false, frame);
+        record.timestamp = 5;
+        writer.write_record(&record).unwrap();
+        record.state = Login;
+        writer.write_record(&record).unwrap();
+        let bytes = writer.into_inner();
+        let mut reader = ProtocolCaptureReader::new(bytes.as_slice()).unwrap();
+        let version = reader.protocol_version();
+        let mut record = reader.next().unwrap().unwrap();
+        assert_eq!(record.timestamp, 5);
+        match Packet::read_record(&mut record, version) {
+            Ok(Packet::PlayS2C(PlayS2CPacket::UpdateObjectives(packet))) => {
+                assert_eq!(packet.objective_name, "a");
+                assert_eq!(packet.action, UpdateObjectivesAction::Remove);
+            }
+            packet => panic!("Unexpected packet {:?}", packet),
+        }
+        assert!(Packet::read_record(&mut reader.next().unwrap().unwrap(), version).is_err());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn nbt_value_field_test() {
+        #[derive(BirdNbt, Clone, PartialEq, Debug)]
+        struct TestNbt<'a> {
+            name: Cow<'a, str>,
+            tag: NbtValue<'a>,
+            list: Vec<NbtValue<'a>>,
+        }
+
+        let value = TestNbt {
+            name: Cow::Borrowed("test"),
+            tag: NbtValue::Compound([(Cow::Borrowed("a"), NbtValue::IntArray(Cow::Borrowed(&[1, 2])))].into()),
+            list: vec![NbtValue::Long(1), NbtValue::Long(2)],
+        };
+        let mut bytes = Vec::new();
+        value.write_nbt(&mut bytes).unwrap();
+        assert_eq!(TestNbt::read_nbt(&mut bytes.as_slice()).unwrap(), value);
+        assert_eq!(TestNbt::skip_nbt(&mut bytes.as_slice(), 1).unwrap(), bytes.len());
+    }
+
+    #[test]
+    fn snbt_derive_test() {
+        use bird_protocol::nbt::snbt::{from_snbt, to_snbt};
+
+        let frame: MapDataColRowsFramesData = from_snbt("{EntityId: 1, Rotation: 2, Pos: {X: 3, Y: 4, Z: 5}}").unwrap();
+        assert_eq!((frame.entity_id, frame.rotation, frame.pos), (1, 2, Vector3D::new(3, 4, 5)));
+        assert_eq!(to_snbt(&frame, false).unwrap(), "{EntityId:1,Pos:{X:3,Y:4,Z:5},Rotation:2}");
+        assert!(from_snbt::<MapDataColRowsFramesData>("{EntityId: 1b, Rotation: 2, Pos: {X: 3, Y: 4, Z: 5}}").is_err());
+        assert!(from_snbt::<MapDataColRowsFramesData>("[1, 2]").is_err());
+    }
+
+    #[test]
+    fn nbt_serde_variant_test() {
+        let dimension = LoginRegistryCodecDimension {
+            piglin_safe: false,
+            has_raids: true,
+            monster_spawn_light_level: 0,
+            monster_spawn_block_light_limit: 0,
+            natural: true,
+            ambient_light: 0.0,
+            fixed_time: None,
+            infiniburn: Cow::Borrowed("#minecraft:infiniburn_overworld"),
+            respawn_anchor_works: false,
+            has_skylight: true,
+            bed_works: true,
+            effects: Cow::Borrowed("minecraft:overworld"),
+            min_y: -64,
+            height: 384,
+            logical_height: 384,
+            coordinate_scale: 1.0,
+            ultrawarm: false,
+            has_ceiling: false,
+        };
+        let codec = LoginRegistryCodec {
+            dimension_type: LoginRegistryCodecRegistry {
+                ty: Cow::Borrowed("minecraft:dimension_type"),
+                value: Cow::Owned(vec![LoginRegistryCodecValue {
+                    name: Cow::Borrowed("minecraft:overworld"),
+                    id: 0,
+                    element: dimension,
+                }]),
+            },
+            worldgen_biome: LoginRegistryCodecRegistry { ty: Cow::Borrowed("minecraft:worldgen/biome"), value: Cow::Owned(vec![]) },
+            chat_type: LoginRegistryCodecRegistry { ty: Cow::Borrowed("minecraft:chat_type"), value: Cow::Owned(vec![]) },
+        };
+        let mut bytes = Vec::new();
+        Nbt::write_variant(&codec, &mut bytes).unwrap();
+        let mut cursor = bytes.as_slice();
+        let read: LoginRegistryCodec = Nbt::read_variant(&mut cursor).unwrap();
+        assert!(cursor.is_empty());
+        assert!(matches!(read.dimension_type.ty, Cow::Borrowed("minecraft:dimension_type")));
+        let element = &read.dimension_type.value[0].element;
+        assert_eq!((element.fixed_time, element.min_y, element.effects.as_ref()), (None, -64, "minecraft:overworld"));
+        assert!(read.worldgen_biome.value.is_empty());
+        let raw: &[u8] = NbtBytes::read_variant(&mut bytes.as_slice()).unwrap();
+        assert_eq!(raw, bytes.as_slice());
+    }
+
+    #[test]
+    fn arbitrary_round_trip_test() {
+        HandshakeC2SPacket::assert_arbitrary_round_trips(64);
+        StatusS2CPacket::assert_arbitrary_round_trips(64);
+        StatusC2SPacket::assert_arbitrary_round_trips(64);
+        LoginS2CPacket::assert_arbitrary_round_trips(64);
+        LoginC2SPacket::assert_arbitrary_round_trips(64);
+        PlayS2CPacket::assert_arbitrary_round_trips(64);
+        PlayC2SPacket::assert_arbitrary_round_trips(64);
+    }
+
+    #[test]
+    fn arbitrary_into_owned_test() {
+        HandshakeC2SPacket::assert_arbitrary_into_owned(64);
+        StatusS2CPacket::assert_arbitrary_into_owned(64);
+        StatusC2SPacket::assert_arbitrary_into_owned(64);
+        LoginS2CPacket::assert_arbitrary_into_owned(64);
+        LoginC2SPacket::assert_arbitrary_into_owned(64);
+        PlayS2CPacket::assert_arbitrary_into_owned(64);
+        PlayC2SPacket::assert_arbitrary_into_owned(64);
     }
 }
@@ -1,3 +1,7 @@
+mod owned;
+
+pub use owned::*;
+
 pub struct ConstAssert<const EXPR: bool>;
 
 pub trait ConstAssertTrue {}
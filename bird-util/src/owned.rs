@@ -0,0 +1,154 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+/// Converts a value which may borrow data into the same value which owns all of its data,
+/// so it can be stored across ticks or sent to another thread
+pub trait IntoOwned {
+    type Owned: 'static;
+
+    fn into_owned(self) -> Self::Owned;
+}
+
+#[macro_export]
+macro_rules! into_owned_self {
+    ($($ty: ty),* $(,)?) => {
+        $(
+        impl $crate::IntoOwned for $ty {
+            type Owned = Self;
+
+            #[inline]
+            fn into_owned(self) -> Self {
+                self
+            }
+        }
+        )*
+    }
+}
+
+into_owned_self!((), bool, char, u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, usize, isize, f32, f64, String);
+
+#[cfg(feature = "uuid")]
+into_owned_self!(uuid::Uuid);
+
+impl<'a> IntoOwned for Cow<'a, str> {
+    type Owned = Cow<'static, str>;
+
+    fn into_owned(self) -> Cow<'static, str> {
+        Cow::Owned(Cow::into_owned(self))
+    }
+}
+
+impl<'a, T: Clone + IntoOwned> IntoOwned for Cow<'a, [T]> where T::Owned: Clone {
+    type Owned = Cow<'static, [T::Owned]>;
+
+    fn into_owned(self) -> Cow<'static, [T::Owned]> {
+        Cow::Owned(match self {
+            Cow::Borrowed(borrowed) => borrowed.iter().cloned().map(IntoOwned::into_owned).collect(),
+            Cow::Owned(owned) => owned.into_iter().map(IntoOwned::into_owned).collect(),
+        })
+    }
+}
+
+impl<'a, T: Clone + IntoOwned, const N: usize> IntoOwned for Cow<'a, [T; N]> where T::Owned: Clone {
+    type Owned = Cow<'static, [T::Owned; N]>;
+
+    fn into_owned(self) -> Cow<'static, [T::Owned; N]> {
+        Cow::Owned(Cow::into_owned(self).map(IntoOwned::into_owned))
+    }
+}
+
+impl<T: IntoOwned> IntoOwned for Option<T> {
+    type Owned = Option<T::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        self.map(IntoOwned::into_owned)
+    }
+}
+
+impl<T: IntoOwned> IntoOwned for Box<T> {
+    type Owned = Box<T::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        Box::new((*self).into_owned())
+    }
+}
+
+impl<T: IntoOwned> IntoOwned for Vec<T> {
+    type Owned = Vec<T::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        self.into_iter().map(IntoOwned::into_owned).collect()
+    }
+}
+
+impl<K: IntoOwned, V: IntoOwned> IntoOwned for HashMap<K, V> where K::Owned: Eq + Hash {
+    type Owned = HashMap<K::Owned, V::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        self.into_iter().map(|(key, value)| (key.into_owned(), value.into_owned())).collect()
+    }
+}
+
+impl<T: IntoOwned, const N: usize> IntoOwned for [T; N] {
+    type Owned = [T::Owned; N];
+
+    fn into_owned(self) -> Self::Owned {
+        self.map(IntoOwned::into_owned)
+    }
+}
+
+impl<T: 'static + ?Sized> IntoOwned for PhantomData<T> {
+    type Owned = Self;
+
+    fn into_owned(self) -> Self {
+        self
+    }
+}
+
+macro_rules! tuple_into_owned {
+    ($($name: ident),*) => {
+        impl<$($name: IntoOwned),*> IntoOwned for ($($name,)*) {
+            type Owned = ($($name::Owned,)*);
+
+            #[allow(non_snake_case)]
+            fn into_owned(self) -> Self::Owned {
+                let ($($name,)*) = self;
+                ($($name.into_owned(),)*)
+            }
+        }
+    }
+}
+
+tuple_into_owned!(A);
+tuple_into_owned!(A, B);
+tuple_into_owned!(A, B, C);
+tuple_into_owned!(A, B, C, D);
+
+#[cfg(feature = "either")]
+impl<L: IntoOwned, R: IntoOwned> IntoOwned for either::Either<L, R> {
+    type Owned = either::Either<L::Owned, R::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        self.map_left(IntoOwned::into_owned).map_right(IntoOwned::into_owned)
+    }
+}
+
+#[cfg(feature = "euclid")]
+impl<T: IntoOwned, U: 'static> IntoOwned for euclid::Vector2D<T, U> {
+    type Owned = euclid::Vector2D<T::Owned, U>;
+
+    fn into_owned(self) -> Self::Owned {
+        euclid::Vector2D::new(self.x.into_owned(), self.y.into_owned())
+    }
+}
+
+#[cfg(feature = "euclid")]
+impl<T: IntoOwned, U: 'static> IntoOwned for euclid::Vector3D<T, U> {
+    type Owned = euclid::Vector3D<T::Owned, U>;
+
+    fn into_owned(self) -> Self::Owned {
+        euclid::Vector3D::new(self.x.into_owned(), self.y.into_owned(), self.z.into_owned())
+    }
+}